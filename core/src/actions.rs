@@ -1,37 +1,42 @@
 use crate::modrinth_wrapper::modrinth::Mod;
 use cli::Source;
-use color_eyre::eyre::bail;
+use color_eyre::eyre::{ContextCompat, bail};
 use colored::Colorize;
-use curseforge_wrapper::{API_KEY, CurseForgeAPI, CurseForgeMod};
-use gh_releases::GHReleasesAPI;
+use config::{Config, Defaults};
+use content::ContentType;
+use curseforge_wrapper::SearchOptions;
+use curseforge_wrapper::{CurseForgeAPI, CurseForgeProvider};
+use gh_releases::{AssetPattern, AssetSelector, GHReleasesAPI, RepoSpec};
+use hangar_wrapper::HangarAPI;
+use info::ProjectInfo;
 use itertools::Itertools;
-use metadata::Metadata;
+use metadata::{DirMetadata, Metadata};
 use modrinth_wrapper::modrinth::{self, VersionData};
-use modrinth_wrapper::modrinth::{GetProject, Modrinth};
-use percent_encoding::percent_decode;
-use std::collections::HashMap;
+use modrinth_wrapper::modrinth::{GetProject, Modrinth, ModrinthProvider};
+use modrinth_wrapper::search::SearchQuery;
+use resolver::{Requirement, Resolution, Resolver, VersionProvider};
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tabwriter::TabWriter;
-use tokio::task::JoinHandle;
 
 use crate::*;
 const GRAY: (u8, u8, u8) = (128, 128, 128);
 
 pub async fn run(cli: Cli) -> color_eyre::Result<()> {
-    let dependencies = Arc::new(Mutex::new(Vec::new()));
+    // A broken config isn't skipped: the commands would fall back to the current directory
+    // instead of the configured `mods_dir`
+    let defaults = Config::load()?.defaults;
     match cli.command {
         Commands::QuickAdd {
             version,
             limit,
             loader,
+            with_optional,
+            no_optional,
         } => {
-            let version = if let Some(version) = version {
-                version
-            } else {
-                inquire::Text::new("Version").prompt().unwrap()
-            };
+            let version = game_version(version, &defaults)?;
+            let loader = defaults.loader(loader);
             let mods: Vec<modrinth::Project> = Modrinth::get_top_mods(limit).await;
             let mods = mods
                 .into_iter()
@@ -79,40 +84,24 @@ pub async fn run(cli: Cli) -> color_eyre::Result<()> {
                     title: "Status".into(),
                 },
             ];
-            let mods = mods
-                .into_iter()
-                .chain(extras.into_iter())
-                .collect::<HashSet<Mod>>();
+            let mods = mods.into_iter().chain(extras).collect::<HashSet<Mod>>();
             let mods = mods.into_iter().collect::<Vec<Mod>>();
             let prompt = inquire::MultiSelect::new("Select Mods", mods);
             let mods = prompt.prompt().unwrap();
-            let mut handles = Vec::new();
-            for mod_ in mods {
-                let version = version.clone();
-                let dependencies = Arc::clone(&dependencies);
-                let loader = loader.clone();
-                let handle = tokio::spawn(async move {
-                    let version_data =
-                        Modrinth::get_version(&mod_.slug, &version, loader.clone()).await;
-                    if let Some(version_data) = version_data {
-                        info!("Downloading {}", mod_.title);
-                        modrinth::download_file(&version_data.clone().files.unwrap()[0], "./")
-                            .await;
-                        Modrinth::download_dependencies(
-                            &mod_,
-                            &version,
-                            dependencies,
-                            "./",
-                            loader,
-                        )
-                        .await;
-                    }
-                });
-                handles.push(handle);
-            }
-            for handle in handles {
-                handle.await.unwrap();
-            }
+            let provider = ModrinthProvider {
+                game_version: version,
+                loader,
+                content_type: ContentType::Mod,
+                channel: defaults.channel,
+            };
+            let dir = Path::new("./");
+            let roots = mods
+                .iter()
+                .map(|mod_| Requirement::project(&mod_.slug))
+                .collect();
+            let resolution =
+                resolve(&provider, dir, roots, optional(with_optional, no_optional)).await?;
+            resolution.install(&provider, dir).await?;
             return Ok(());
         }
         Commands::Update {
@@ -121,33 +110,63 @@ pub async fn run(cli: Cli) -> color_eyre::Result<()> {
             delete_previous,
             token,
             source,
-            other_sources,
+            no_fallback,
             loader,
+            changelog,
+            changelog_file,
+            prereleases,
+            channel,
+            concurrency,
+            content_type,
+            world,
         } => {
-            let version = if let Some(version) = version {
-                version
-            } else {
-                inquire::Text::new("Version").prompt().unwrap()
-            };
+            let version = game_version(version, &defaults)?;
+            let dir = defaults.dir(dir);
+            let (_, dir) = ContentType::resolve(content_type, &dir, world.as_deref())?;
             let update_dir = dir.into_os_string().into_string().unwrap();
-            let mut github = GHReleasesAPI::new();
-            if let Some(token) = token {
-                github.token(token.clone());
+            let loader = loader.or(defaults.loader.clone());
+            let mut clients = Clients::from_env(token);
+            if let Some(channel) = channel {
+                clients.github.prereleases(channel.prereleases());
+            }
+            if prereleases {
+                clients.github.prereleases(true);
             }
-            let curseforge = CurseForgeAPI::new(API_KEY.to_string());
 
-            modder::update_dir(
-                &mut github,
-                curseforge,
+            let changelogs = modder::update_dir(
+                &clients,
                 &update_dir,
                 &version,
                 delete_previous,
                 &update_dir,
                 source,
-                other_sources,
-                loader,
+                &defaults.sources,
+                !no_fallback,
+                loader.clone(),
+                channel.or(defaults.channel),
+                concurrency.or(defaults.concurrency),
+                changelog || changelog_file.is_some(),
             )
             .await?;
+            if changelog {
+                for changelog in &changelogs {
+                    println!("{}", changelog.format());
+                }
+            }
+            if let Some(changelog_file) = changelog_file {
+                fs::write(
+                    &changelog_file,
+                    changelog::markdown_report(&changelogs, &version),
+                )?;
+                info!("Wrote the changelog to {}", changelog_file.display());
+            }
+            let issues = check::ModSet::read_dir(Path::new(&update_dir))?.issues(loader.as_ref());
+            for issue in &issues {
+                warn!("{}", issue);
+            }
+            if !issues.is_empty() {
+                warn!("Run `modder check` for details");
+            }
         }
         Commands::Add {
             mod_,
@@ -155,176 +174,575 @@ pub async fn run(cli: Cli) -> color_eyre::Result<()> {
             source,
             token,
             loader,
+            asset_pattern,
+            prereleases,
+            channel,
+            check_updates,
+            maven_repo,
+            content_type,
+            world,
+            with_optional,
+            no_optional,
+            force,
             dir,
         } => {
-            let version = if let Some(version) = version {
-                version
-            } else {
-                inquire::Text::new("Version").prompt().unwrap()
-            };
             let source = match source {
                 Some(source) => source,
-                None => {
-                    if mod_.contains('/') {
-                        Source::Github
-                    } else {
-                        Source::Modrinth
-                    }
-                }
+                None => infer_source(&mod_, &defaults)?,
             };
+            let check_loader = loader.clone().or(defaults.loader.clone());
+            let loader = defaults.loader(loader);
+            let channel = channel.or(defaults.channel);
+            let dir = defaults.dir(dir);
+            let (content_type, dir) = ContentType::resolve(content_type, &dir, world.as_deref())?;
+            if !content_type.is_mod() {
+                if !matches!(
+                    source,
+                    Source::Modrinth | Source::CurseForge | Source::Url | Source::Local
+                ) {
+                    bail!("Only mods can be added from {}", source);
+                }
+                fs::create_dir_all(&dir)?;
+            }
+            // Only mods can clash, and only with the loader the user asked for
+            let before = content_type
+                .is_mod()
+                .then(|| check::jar_files(&dir).unwrap_or_default());
+            // A jar or pack from a url or file is added as is, whatever the game version
             match source {
-                Source::Github => {
-                    let mod_ = mod_.split('/').collect_vec();
-                    let mut gh = GHReleasesAPI::new();
-                    if let Some(token) = token {
-                        gh.token(token);
+                Source::Url => {
+                    let path = direct::add_from_url(&mod_, &dir, check_updates).await?;
+                    info!("Added {}", path.display());
+                    return check_added(&dir, before, check_loader.as_ref(), force).await;
+                }
+                Source::Local => {
+                    let path = direct::add_from_file(Path::new(&mod_), &dir)?;
+                    info!("Added {}", path.display());
+                    return check_added(&dir, before, check_loader.as_ref(), force).await;
+                }
+                _ => {}
+            }
+            let version = game_version(version, &defaults)?;
+            match source {
+                Source::Url | Source::Local => unreachable!(),
+                Source::Hangar => {
+                    // The default loader is Fabric, which has no plugins
+                    let loader = if loader.is_plugin_loader() {
+                        loader
+                    } else {
+                        ModLoader::Paper
+                    };
+                    let path = HangarAPI::new()
+                        .download(&mod_, &version, &loader, &dir)
+                        .await?;
+                    info!("Added {}", path.display());
+                }
+                Source::Maven => {
+                    let Some(coordinate) = maven::Coordinate::parse(&mod_) else {
+                        bail!(maven::Error::InvalidCoordinate(mod_));
+                    };
+                    let pattern = coordinate
+                        .version
+                        .as_deref()
+                        .filter(|_| coordinate.has_version_pattern())
+                        .map(AssetPattern::parse)
+                        .transpose()?;
+                    let path = maven::MavenAPI::from_config(maven_repo)
+                        .download(&coordinate, &version, &loader, pattern.as_ref(), &dir)
+                        .await?;
+                    info!("Added {}", path.display());
+                }
+                Source::Github | Source::Gitea | Source::Gitlab => {
+                    let Some(spec) = RepoSpec::parse(&mod_) else {
+                        bail!(
+                            "Invalid repo {}, expected <owner>/<repo> or <host>/<owner>/<repo>",
+                            mod_
+                        );
+                    };
+                    let mut gh = GHReleasesAPI::from_env(token);
+                    if let Some(channel) = channel {
+                        gh.prereleases(channel.prereleases());
                     }
-                    let releases = gh.get_releases(mod_[0], mod_[1]).await.unwrap();
-                    //  TODO: Add support for other loaders
-                    let release =
-                        gh_releases::get_mod_from_release(&releases, "fabric", &version).await?;
-                    let url = release.get_download_url().unwrap();
-                    let file_name =
-                        percent_decode(url.path_segments().unwrap().last().unwrap().as_bytes())
-                            .decode_utf8_lossy()
-                            .to_string();
-                    let path = format!("./{}", file_name);
-                    info!("Downloading {}", file_name);
-                    release
-                        .download(path.clone().into(), mod_.join("/"))
+                    if prereleases {
+                        gh.prereleases(true);
+                    }
+                    let gh = gh.for_repo(&spec, Some(&source)).context("Unknown forge")?;
+                    let repo = spec.to_string();
+                    let asset_pattern = match asset_pattern {
+                        Some(pattern) => Some(pattern),
+                        None => DirMetadata::load(&dir)?
+                            .get_asset_pattern(&repo)
+                            .map(String::from),
+                    };
+                    let pattern = asset_pattern
+                        .as_deref()
+                        .map(gh_releases::AssetPattern::parse)
+                        .transpose()?;
+                    let selector = AssetSelector::new(&version, loader).pattern(pattern);
+                    let path = gh
+                        .download_mod(&spec.owner, &spec.repo, &selector, &dir, &source)
                         .await?;
+                    info!(
+                        "Downloaded {}",
+                        path.file_name().unwrap_or_default().to_string_lossy()
+                    );
+                    record_install(&path, source, &repo, None);
+                    if let Some(asset_pattern) = asset_pattern {
+                        DirMetadata::modify(&dir, |metadata| {
+                            metadata.set_asset_pattern(&repo, &asset_pattern)
+                        })?;
+                    }
                 }
                 Source::Modrinth => {
-                    let res = Modrinth::search_mods(&mod_, 100, 0).await;
+                    let res = Modrinth::search_projects(&mod_, content_type, 100, 0).await?;
                     let hits = res.hits;
                     if hits.is_empty() {
-                        bail!("Could not find mod {}", mod_);
+                        bail!("Could not find {} {}", content_type, mod_);
                     }
-                    if hits.len() == 1 {
-                        let mod_ = hits[0].clone();
-                        let version_data =
-                            Modrinth::get_version(&mod_.slug, &version, loader.clone()).await;
-                        if let Some(version_data) = version_data {
-                            info!("Downloading {}", mod_.title);
-                            modrinth::download_file(&version_data.clone().files.unwrap()[0], "./")
-                                .await;
-                            Modrinth::download_dependencies(
-                                &mod_.into(),
-                                &version,
-                                dependencies.clone(),
-                                "./",
-                                loader,
-                            )
-                            .await;
-                            return Ok(());
-                        } else {
-                            info!(
-                                "Could not find version {} for {}, trying curseforge",
-                                version, mod_.title
-                            );
-                            bail!("Could not find version {} for {}", version, mod_.title);
-                        }
+                    let hits = if hits.len() == 1 {
+                        hits
+                    } else {
+                        inquire::MultiSelect::new("Select Mods", hits).prompt()?
+                    };
+                    let provider = ModrinthProvider {
+                        game_version: version,
+                        loader,
+                        content_type,
+                        channel,
+                    };
+                    let roots = hits
+                        .iter()
+                        .map(|hit| Requirement::project(&hit.project_id))
+                        .collect();
+                    let resolution =
+                        resolve(&provider, &dir, roots, optional(with_optional, no_optional))
+                            .await?;
+                    resolution.install(&provider, &dir).await?;
+                }
+                Source::CurseForge => {
+                    let api = CurseForgeAPI::from_env();
+                    let mods = api
+                        .search_projects(&version, loader.clone(), content_type, &mod_, 30)
+                        .await?;
+                    let prompt = inquire::MultiSelect::new("Select mods", mods);
+                    let selected = prompt.prompt().unwrap();
+                    let provider = CurseForgeProvider {
+                        api,
+                        game_version: version,
+                        loader,
+                        content_type,
+                    };
+                    let roots = selected
+                        .iter()
+                        .map(|mod_| Requirement::project(&mod_.id.to_string()))
+                        .collect();
+                    let resolution =
+                        resolve(&provider, &dir, roots, optional(with_optional, no_optional))
+                            .await?;
+                    resolution.install(&provider, &dir).await?;
+                }
+            }
+            check_added(&dir, before, check_loader.as_ref(), force).await?;
+        }
+        Commands::Check { dir, loader } => {
+            let dir = defaults.dir(dir);
+            let loader = loader.or(defaults.loader);
+            let mods = check::ModSet::read_dir(&dir)?;
+            let mut issues = mods.issues(loader.as_ref());
+            match check::modrinth_incompatibilities(&dir, &mods).await {
+                Ok(found) => issues.extend(found),
+                Err(err) => warn!("Could not look the mods up on Modrinth: {}", err),
+            }
+            let issues = issues.into_iter().unique().collect_vec();
+            if issues.is_empty() {
+                info!("Found no problems in {}", dir.display());
+                return Ok(());
+            }
+            for issue in &issues {
+                if issue.is_error() {
+                    println!("{} {}", "error:".red().bold(), issue);
+                } else {
+                    println!("{} {}", "warning:".yellow().bold(), issue);
+                }
+            }
+            let errors = issues.iter().filter(|issue| issue.is_error()).count();
+            if errors > 0 {
+                bail!("Found {} problems that keep the game from starting", errors);
+            }
+        }
+        Commands::Doctor {
+            dir,
+            version,
+            loader,
+            java,
+            fix,
+        } => {
+            let dir = defaults.dir(dir);
+            let checked_version = version.clone().or(defaults.game_version.clone());
+            let loader = loader.or(defaults.loader.clone());
+            let diagnose = || -> color_eyre::Result<Vec<doctor::Problem>> {
+                let jars = doctor::read_dir(&dir)?;
+                Ok(doctor::diagnose(
+                    &jars,
+                    checked_version.as_deref(),
+                    loader.as_ref(),
+                ))
+            };
+            let mut problems = diagnose()?;
+            let missing = doctor::missing(&problems);
+            if fix && !missing.is_empty() {
+                let version = game_version(version, &defaults)?;
+                let loader = defaults.loader(loader.clone());
+                let not_found = install_by_mod_id(&dir, missing, &version, loader).await?;
+                for id in not_found {
+                    warn!("Could not find {} on Modrinth or CurseForge", id);
+                }
+                problems = diagnose()?;
+            }
+            let report = java::JavaReport::new(&java::read_dir(&dir)?);
+            info!("The mods in {} need {}", dir.display(), report);
+            for warning in report.warnings(|| java.or_else(java::installed_version)) {
+                warn!("{}", warning);
+            }
+            if problems.is_empty() {
+                info!("Every dependency in {} is satisfied", dir.display());
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("{} {}", "error:".red().bold(), problem);
+            }
+            if !fix && problems.iter().any(|problem| problem.missing().is_some()) {
+                println!("Run with --fix to install the missing dependencies");
+            }
+            bail!("Found {} unsatisfied dependencies", problems.len());
+        }
+        Commands::Search {
+            query,
+            source,
+            content_type,
+            category,
+            loader,
+            version,
+            client_side,
+            server_side,
+            license,
+            sort,
+            sort_field,
+            order,
+            category_id,
+            class_id,
+            author_id,
+            limit,
+            page,
+            json,
+        } => {
+            let page_of_hits = match source {
+                Source::Modrinth => {
+                    let mut search = SearchQuery::new(&query)
+                        .project_type(content_type.modrinth_project_type())
+                        .categories(category)
+                        .loaders(loader)
+                        .game_versions(version)
+                        .index(sort)
+                        .limit(limit)
+                        .page(page);
+                    if let Some(client_side) = client_side {
+                        search = search.client_side(client_side);
                     }
-                    let prompt = inquire::MultiSelect::new("Select Mods", hits);
-                    let hits = prompt.prompt().unwrap();
-                    let mut handles = Vec::new();
-                    for hit in hits {
-                        let loader = loader.clone();
-                        let version = version.clone();
-                        let dependencies = Arc::clone(&dependencies);
-                        let handle = tokio::spawn(async move {
-                            let version_data =
-                                Modrinth::get_version(&hit.slug, &version, loader.clone()).await;
-                            if let Some(version_data) = version_data {
-                                info!("Downloading {}", hit.title);
-                                modrinth::download_file(
-                                    &version_data.clone().files.unwrap()[0],
-                                    "./",
-                                )
-                                .await;
-                                Modrinth::download_dependencies(
-                                    &hit.into(),
-                                    &version,
-                                    dependencies,
-                                    "./",
-                                    loader,
-                                )
-                                .await;
-                            } else {
-                                bail!("Could not find version {} for {}", version, hit.title);
-                            }
-                            Ok(())
-                        });
-
-                        handles.push(handle);
+                    if let Some(server_side) = server_side {
+                        search = search.server_side(server_side);
                     }
-                    for handle in handles {
-                        handle.await??;
+                    if let Some(license) = license {
+                        search = search.license(&license);
+                    }
+                    let res = Modrinth::search(&search).await?;
+                    SearchPage {
+                        total_hits: Some(res.total_hits),
+                        offset: res.offset as u32,
+                        hits: res.hits.into_iter().map(SearchHit::from).collect(),
                     }
                 }
                 Source::CurseForge => {
-                    let api = CurseForgeAPI::new(API_KEY.to_string());
-                    let dependencies = Arc::new(Mutex::new(Vec::new()));
-                    let mods = api.search_mods(&version, loader, &mod_, 30).await.unwrap();
-                    let prompt = inquire::MultiSelect::new("Select mods", mods);
-                    let selected = prompt.prompt().unwrap();
-                    let mut handles = Vec::new();
-                    let dir = Arc::new(dir.clone());
-                    for mod_ in selected {
-                        let dependencies = Arc::clone(&dependencies);
-                        let version = version.clone();
-                        let api = api.clone();
-                        let dir = dir.clone();
-                        let handle: JoinHandle<Result<()>> = tokio::spawn(async move {
-                            info!("Downloading {}", mod_.name);
-                            let v = mod_.get_version_and_loader(&version).unwrap();
-
-                            api.download_mod(mod_.id, v.file_id, dir.to_path_buf())
-                                .await?;
-                            let deps = api.get_dependencies(mod_.id, &version).await?;
-                            for dep in deps {
-                                if dependencies.lock().await.contains(&dep.id) {
-                                    info!("Skipping dependency {}", dep.name);
-                                }
-                                info!("Downloading dependency {}", dep.name);
-                                let v = dep.get_version_and_loader(&version).unwrap();
-                                api.download_mod(dep.id, v.file_id, dir.to_path_buf())
-                                    .await?;
-                            }
-                            Ok(())
-                        });
-                        handles.push(handle);
+                    let curseforge = CurseForgeAPI::from_env();
+                    let mut options = SearchOptions::new(&query)
+                        .game_version(version.first().map(String::as_str).unwrap_or_default())
+                        .loader(loader.first().cloned().unwrap_or(ModLoader::Any))
+                        .sort_field(sort_field.unwrap_or(sort.into()))
+                        .sort_order(order)
+                        .content_type(content_type)
+                        .page_size(limit as u32)
+                        .page(page.saturating_sub(1) as u32);
+                    if let Some(category_id) = category_id {
+                        options = options.category_id(category_id);
+                    }
+                    if let Some(class_id) = class_id {
+                        options = options.class_id(class_id);
+                    }
+                    if let Some(author_id) = author_id {
+                        options = options.author_id(author_id);
                     }
-                    for handle in handles {
-                        handle.await?.unwrap();
+                    let res = curseforge.search(&options).await?;
+                    SearchPage {
+                        total_hits: res.pagination.map(|pagination| pagination.total_count),
+                        offset: res.pagination.map_or(0, |pagination| pagination.index),
+                        hits: res.data.into_iter().map(SearchHit::from).collect(),
                     }
                 }
+                Source::Hangar if !content_type.is_mod() => {
+                    bail!("Hangar only hosts plugins")
+                }
+                Source::Hangar => {
+                    let res = HangarAPI::new()
+                        .search(
+                            &query,
+                            loader
+                                .first()
+                                .and_then(hangar_wrapper::Platform::from_loader),
+                            version.first().map(String::as_str),
+                            limit as u32,
+                            page.saturating_sub(1) as u32 * limit as u32,
+                        )
+                        .await?;
+                    SearchPage {
+                        total_hits: Some(res.pagination.count),
+                        offset: res.pagination.offset,
+                        hits: res.result.into_iter().map(SearchHit::from).collect(),
+                    }
+                }
+                Source::Github
+                | Source::Gitea
+                | Source::Gitlab
+                | Source::Maven
+                | Source::Url
+                | Source::Local => {
+                    bail!("Searching {} is not supported", source)
+                }
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&page_of_hits)?);
+            } else {
+                println!("{}", page_of_hits.format());
+            }
+        }
+        Commands::Info {
+            mod_,
+            version,
+            source,
+            loader,
+            token,
+        } => {
+            let source = match source {
+                Some(source) => source,
+                None => infer_source(&mod_, &defaults)?,
+            };
+            let version = version.or(defaults.game_version);
+            let loader = loader.or(defaults.loader);
+            let clients = Clients::from_env(token);
+            let id = match source {
+                Source::Github
+                | Source::Gitea
+                | Source::Gitlab
+                | Source::Hangar
+                | Source::Maven
+                | Source::Url
+                | Source::Local => mod_,
+                Source::Modrinth => {
+                    let direct =
+                        modrinth::get_project_info(&mod_, version.as_deref(), loader.clone()).await;
+                    // Only an id or slug that doesn't exist is searched for instead
+                    match direct {
+                        Ok(info) => {
+                            print!("{}", info.format());
+                            return Ok(());
+                        }
+                        Err(err) if err.is_not_found() => {}
+                        Err(err) => return Err(err.into()),
+                    }
+                    let hits = Modrinth::search_mods(&mod_, 20, 0).await.hits;
+                    let hit = match hits.len() {
+                        0 => bail!("Could not find mod {}", mod_),
+                        1 => hits[0].clone(),
+                        _ => inquire::Select::new("Select mod", hits).prompt()?,
+                    };
+                    hit.slug
+                }
+                Source::CurseForge => {
+                    let mods = clients
+                        .curseforge
+                        .search_mods(
+                            version.as_deref().unwrap_or_default(),
+                            loader.clone().unwrap_or(ModLoader::Any),
+                            &mod_,
+                            20,
+                        )
+                        .await?;
+                    let mod_ = match mods.len() {
+                        0 => bail!("Could not find mod {}", mod_),
+                        1 => mods[0].clone(),
+                        _ => inquire::Select::new("Select mod", mods).prompt()?,
+                    };
+                    mod_.id.to_string()
+                }
+            };
+            let info =
+                ProjectInfo::fetch(&source, &id, version.as_deref(), loader, &clients).await?;
+            print!("{}", info.format());
+        }
+        Commands::Toggle { version: _, dir } => toggle(defaults.dir(dir))?,
+        Commands::Pin { mod_, version, dir } => {
+            let dir = defaults.dir(dir);
+            let file_name = select_mod_file(&dir, &mod_)?;
+            if let Some(version) = &version {
+                check_pin_version(&dir, &file_name, version).await?;
+            }
+            let mut dir_metadata = DirMetadata::load(&dir)?;
+            dir_metadata.pin(&file_name, version.clone());
+            dir_metadata.save(&dir)?;
+            match version {
+                Some(version) => info!("Pinned {} at {}", file_name, version),
+                None => info!("Pinned {}", file_name),
+            }
+        }
+        Commands::Remove { mods, dir } => {
+            let dir = defaults.dir(dir);
+            let mut file_names = Vec::new();
+            for mod_ in mods {
+                file_names.push(select_mod_file(&dir, &mod_)?);
             }
+            remove_mods(&dir, file_names)?;
         }
-        Commands::Toggle { version: _, dir } => toggle(dir)?,
-        Commands::List { dir, verbose } => {
-            let files = fs::read_dir(dir).unwrap();
+        Commands::Why { mod_, dir } => {
+            let dir = defaults.dir(dir);
+            let file_name = select_mod_file(&dir, &mod_)?;
+            let dir_metadata = DirMetadata::load(&dir)?;
+            let Some(install_reason) = dir_metadata.describe_install(&file_name) else {
+                bail!("{} was not installed by modder", file_name);
+            };
+            println!("{} was installed {}", file_name.bold(), install_reason);
+            for chain in dir_metadata.requirement_chains(&file_name) {
+                println!("\t{}", chain.join(" <- "));
+            }
+        }
+        Commands::Unpin { mod_, dir } => {
+            let dir = defaults.dir(dir);
+            let file_name = select_mod_file(&dir, &mod_)?;
+            let mut dir_metadata = DirMetadata::load(&dir)?;
+            if dir_metadata.unpin(&file_name).is_none() {
+                bail!("{} is not pinned", file_name);
+            }
+            dir_metadata.save(&dir)?;
+            info!("Unpinned {}", file_name);
+        }
+        Commands::Prefer { mod_, sources, dir } => {
+            let dir = defaults.dir(dir);
+            let file_name = select_mod_file(&dir, &mod_)?;
+            let mut dir_metadata = DirMetadata::load(&dir)?;
+            if sources.is_empty() {
+                info!("{} follows the global source priority again", file_name);
+            } else {
+                info!(
+                    "Updating {} from {}",
+                    file_name,
+                    sources.iter().join(", then ")
+                );
+            }
+            dir_metadata.set_sources(&file_name, sources);
+            dir_metadata.save(&dir)?;
+        }
+        Commands::List {
+            dir,
+            verbose,
+            content_type,
+            world,
+        } => {
+            let dir = defaults.dir(dir);
+            let (content_type, dir) = ContentType::resolve(content_type, &dir, world.as_deref())?;
+            let files = fs::read_dir(&dir).unwrap();
+            let dir_metadata = Arc::new(DirMetadata::load(&dir)?);
 
             let mut output = String::new();
             let mut handles = Vec::new();
             for f in files {
+                let dir_metadata = Arc::clone(&dir_metadata);
                 let handle = tokio::spawn(async move {
                     let Ok(f) = f else {
                         return None;
                     };
                     let path = f.path();
-                    let extension = path
-                        .extension()
-                        .unwrap_or_default()
-                        .to_str()
-                        .unwrap_or_default();
-
-                    if extension != "jar" && extension != "disabled" {
+                    let install_reason =
+                        dir_metadata.describe_install(&f.file_name().to_string_lossy());
+                    if !content_type.matches_file(&f.file_name().to_string_lossy()) {
                         return None;
                     }
 
                     let path_str = path.to_str().unwrap_or_default().to_string();
                     let hash = calc_sha512(&path_str);
+                    if let Some(origin) = dir_metadata.get_origin(&f.file_name().to_string_lossy())
+                    {
+                        let label = origin.source().to_string().to_uppercase();
+                        let name = f.file_name().to_string_lossy().to_string();
+                        let mut location = origin
+                            .location
+                            .truecolor(GRAY.0, GRAY.1, GRAY.2)
+                            .to_string();
+                        if origin.sha512 != hash {
+                            location.push_str(&format!(" {}", "(changed since added)".red()));
+                        }
+                        let out = if verbose {
+                            with_install_reason(
+                                format!("{}  {}  {}\n", label.yellow(), name.bold(), location),
+                                install_reason,
+                            )
+                        } else {
+                            format!("{}\t{}\t{}\n", label.yellow(), name.bold(), location)
+                        };
+                        return Some(out);
+                    }
+                    if let Some(artifact) =
+                        dir_metadata.get_artifact(&f.file_name().to_string_lossy())
+                    {
+                        let label = Source::Maven.to_string().to_uppercase();
+                        let name = f.file_name().to_string_lossy().to_string();
+                        let coordinate = artifact
+                            .coordinate
+                            .truecolor(GRAY.0, GRAY.1, GRAY.2)
+                            .to_string();
+                        let out = if verbose {
+                            with_install_reason(
+                                format!(
+                                    "{}  {}  {}  {}\n",
+                                    label.yellow(),
+                                    name.bold(),
+                                    coordinate,
+                                    artifact.repository
+                                ),
+                                install_reason,
+                            )
+                        } else {
+                            format!("{}\t{}\t{}\n", label.yellow(), name.bold(), coordinate)
+                        };
+                        return Some(out);
+                    }
+                    if let Some(record) = dir_metadata
+                        .get_install(&f.file_name().to_string_lossy())
+                        .filter(|record| record.source == Source::Hangar)
+                    {
+                        let label = Source::Hangar.to_string().to_uppercase();
+                        let name = f.file_name().to_string_lossy().to_string();
+                        let link = Link::new(
+                            record.project_id.clone(),
+                            format!("{}/{}", hangar_wrapper::WEB_URL, record.project_id),
+                        );
+                        let out = if verbose {
+                            with_install_reason(
+                                format!("{}  {}  {}\n", label.yellow(), name.bold(), link),
+                                install_reason,
+                            )
+                        } else {
+                            format!("{}\t{}\t{}\n", label.yellow(), name.bold(), link)
+                        };
+                        return Some(out);
+                    }
                     let version_data = VersionData::from_hash(hash).await;
                     if version_data.is_err() {
                         println!("   ");
@@ -340,22 +758,23 @@ pub async fn run(cli: Cli) -> color_eyre::Result<()> {
                             return None;
                         }
                         let repo = metadata.get("repo")?;
-                        let repo_name = repo.split('/').last()?;
-                        let link = Link::new(
-                            repo_name.to_string(),
-                            format!("https://github.com/{}", repo),
-                        );
+                        let spec = RepoSpec::parse(repo)?;
+                        let link = Link::new(spec.repo.clone(), spec.web_url());
+                        let label = source.to_uppercase();
                         let out = if verbose {
-                            format!(
-                                "{}  {}  {}",
-                                "GITHUB".yellow(),
-                                repo.truecolor(GRAY.0, GRAY.1, GRAY.2),
-                                link.to_string().bold()
+                            with_install_reason(
+                                format!(
+                                    "{}  {}  {}",
+                                    label.yellow(),
+                                    repo.truecolor(GRAY.0, GRAY.1, GRAY.2),
+                                    link.to_string().bold()
+                                ),
+                                install_reason,
                             )
                         } else {
                             format!(
-                                "{}\t{}\t{}",
-                                "GITHUB".yellow(),
+                                "{}\t{}\t{}\n",
+                                label.yellow(),
                                 repo.truecolor(GRAY.0, GRAY.1, GRAY.2),
                                 link.to_string().bold()
                             )
@@ -367,7 +786,11 @@ pub async fn run(cli: Cli) -> color_eyre::Result<()> {
                     };
                     let project = GetProject::from_id(&version_data.project_id).await?;
                     let out = if verbose {
-                        version_data.format_verbose(&project.get_title(), &project.get_categories())
+                        with_install_reason(
+                            version_data
+                                .format_verbose(&project.get_title(), &project.get_categories()),
+                            install_reason,
+                        )
                     } else {
                         version_data.format(&project.get_title())
                     };
@@ -388,55 +811,461 @@ pub async fn run(cli: Cli) -> color_eyre::Result<()> {
             tw.flush().unwrap();
             let written = String::from_utf8(tw.into_inner().unwrap()).unwrap();
             println!("{}", written);
+            if content_type.is_mod() {
+                let report = java::JavaReport::new(&java::read_dir(&dir)?);
+                for warning in report.warnings(java::installed_version) {
+                    warn!("{}", warning);
+                }
+            }
         }
     };
     Ok(())
 }
 
-fn toggle(dir: PathBuf) -> color_eyre::Result<()> {
-    let files = fs::read_dir(dir.clone()).unwrap();
-    let toggle_map = files.map(|f| {
-        let f = f.unwrap();
-        let path = f.path().to_str().unwrap().to_string();
-        let file_name = f.file_name().to_string_lossy().to_string();
-        if file_name.ends_with(".disabled") {
-            (path, false)
-        } else {
-            (path, true)
+/// One page of search results, from either source.
+#[derive(Debug, serde::Serialize)]
+struct SearchPage {
+    total_hits: Option<u32>,
+    offset: u32,
+    hits: Vec<SearchHit>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct SearchHit {
+    source: Source,
+    id: String,
+    slug: String,
+    title: String,
+    author: String,
+    downloads: u64,
+    description: String,
+}
+
+impl From<modrinth::Project> for SearchHit {
+    fn from(project: modrinth::Project) -> Self {
+        Self {
+            source: Source::Modrinth,
+            id: project.project_id,
+            slug: project.slug,
+            title: project.title,
+            author: project.author,
+            downloads: project.downloads,
+            description: project.description,
         }
-    });
-    let toggle_map = toggle_map.collect::<HashMap<_, _>>();
-    let filenames = toggle_map
-        .keys()
-        .map(|f| f.split('/').last().unwrap())
-        .collect::<Vec<&str>>();
-    let defaults = filenames
+    }
+}
+
+impl From<curseforge_wrapper::Mod> for SearchHit {
+    fn from(mod_: curseforge_wrapper::Mod) -> Self {
+        Self {
+            source: Source::CurseForge,
+            id: mod_.id.to_string(),
+            slug: mod_.slug,
+            title: mod_.name,
+            author: mod_
+                .authors
+                .into_iter()
+                .map(|author| author.name)
+                .join(", "),
+            downloads: mod_.download_count as u64,
+            description: mod_.summary,
+        }
+    }
+}
+
+impl From<hangar_wrapper::Project> for SearchHit {
+    fn from(project: hangar_wrapper::Project) -> Self {
+        Self {
+            source: Source::Hangar,
+            id: project.id.to_string(),
+            slug: project.full_slug(),
+            title: project.name,
+            author: project.namespace.owner,
+            downloads: project.stats.downloads,
+            description: project.description.unwrap_or_default(),
+        }
+    }
+}
+
+impl SearchPage {
+    fn format(&self) -> String {
+        let mut output = String::new();
+        for hit in &self.hits {
+            output.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                hit.title.bold(),
+                hit.slug.truecolor(GRAY.0, GRAY.1, GRAY.2),
+                hit.author,
+                hit.downloads.to_string().green(),
+                hit.description.chars().take(60).collect::<String>()
+            ));
+        }
+        let mut tw = TabWriter::new(vec![]);
+        tw.write_all(output.as_bytes()).unwrap();
+        tw.flush().unwrap();
+        let mut written = String::from_utf8(tw.into_inner().unwrap()).unwrap();
+        let shown = self.offset as usize + self.hits.len();
+        match self.total_hits {
+            Some(total_hits) if self.hits.is_empty() => {
+                written.push_str(&format!("No results ({} in total)", total_hits))
+            }
+            Some(total_hits) => written.push_str(&format!(
+                "Showing {}-{} of {}",
+                self.offset + 1,
+                shown,
+                total_hits
+            )),
+            None => written.push_str(&format!("Showing {} results", self.hits.len())),
+        }
+        written
+    }
+}
+
+/// The game version to work with: `version` if given, else the configured one, else asked for
+/// if there is a terminal to ask on.
+fn game_version(version: Option<String>, defaults: &Defaults) -> color_eyre::Result<String> {
+    if let Some(version) = version.or_else(|| defaults.game_version.clone()) {
+        return Ok(version);
+    }
+    match inquire::Text::new("Version").prompt() {
+        Ok(version) => Ok(version),
+        Err(inquire::InquireError::NotTTY) => {
+            bail!(
+                "No game version given, pass --version or set defaults.game_version in the config"
+            )
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Whether to add optional dependencies, or `None` to ask.
+fn optional(with_optional: bool, no_optional: bool) -> Option<bool> {
+    match (with_optional, no_optional) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Resolves `roots` with the optional dependencies picked from those offered, asking for them
+/// again as picking some may offer more.
+async fn resolve<P: VersionProvider>(
+    provider: &P,
+    dir: &Path,
+    roots: Vec<Requirement>,
+    with_optional: Option<bool>,
+) -> color_eyre::Result<Resolution> {
+    let mut picked = Vec::new();
+    let mut offered = HashSet::new();
+    loop {
+        let resolution = Resolver::new(provider)
+            .installed_in(dir)
+            .await?
+            .with_optional(picked.clone())
+            .resolve(roots.clone())
+            .await?;
+        let new = resolution
+            .optional
+            .iter()
+            .filter(|optional| offered.insert(optional.project_id.clone()))
+            .cloned()
+            .collect_vec();
+        if new.is_empty() || with_optional == Some(false) {
+            return Ok(resolution);
+        }
+        let chosen = match with_optional {
+            Some(_) => new,
+            None => inquire::MultiSelect::new("Add optional dependencies", new).prompt()?,
+        };
+        if chosen.is_empty() {
+            return Ok(resolution);
+        }
+        picked.extend(chosen.into_iter().map(|optional| optional.project_id));
+    }
+}
+
+/// Checks the jars added to `dir` since `before` was listed against the rest, including the
+/// incompatibilities Modrinth knows of. The added jars that duplicate or can't be loaded with
+/// other mods are removed again unless `force` is set; the rest of what was added stays.
+/// Nothing is checked without a `before`.
+async fn check_added(
+    dir: &Path,
+    before: Option<Vec<PathBuf>>,
+    loader: Option<&ModLoader>,
+    force: bool,
+) -> color_eyre::Result<()> {
+    let Some(before) = before else {
+        return Ok(());
+    };
+    let added = check::jar_files(dir)?
+        .into_iter()
+        .filter(|path| !before.contains(path))
+        .filter_map(|path| Some(path.file_name()?.to_string_lossy().to_string()))
+        .collect_vec();
+    let mods = check::ModSet::read_dir(dir)?;
+    let mut issues = mods.issues(loader);
+    match check::modrinth_incompatibilities(dir, &mods).await {
+        Ok(found) => issues.extend(found),
+        Err(err) => warn!("Could not look the mods up on Modrinth: {}", err),
+    }
+    let issues = issues
+        .into_iter()
+        .unique()
+        .filter(|issue| added.iter().any(|file_name| issue.involves(file_name)))
+        .collect_vec();
+    let errors = issues.iter().filter(|issue| issue.is_error()).collect_vec();
+    if force || errors.is_empty() {
+        for issue in &issues {
+            warn!("{}", issue);
+        }
+        return Ok(());
+    }
+    let offending = added
         .iter()
-        .enumerate()
-        .filter_map(|(i, f)| {
-            let path = &format!("{}{}", dir.to_str().unwrap(), f);
-            if *toggle_map.get(path).unwrap() {
-                Some(i)
-            } else {
-                None
+        .filter(|file_name| errors.iter().any(|issue| issue.involves(file_name)))
+        .collect_vec();
+    for file_name in &offending {
+        fs::remove_file(dir.join(file_name))?;
+        DirMetadata::modify(dir, |metadata| {
+            metadata.remove_mod(file_name);
+        })?;
+    }
+    bail!(
+        "Removed {} again:\n{}\nPass --force to keep it anyway",
+        offending.iter().join(", "),
+        errors
+            .iter()
+            .map(|issue| format!("  - {}", issue))
+            .join("\n")
+    );
+}
+
+/// Installs the mods with the mod ids `ids`, with their dependencies, from Modrinth or else
+/// CurseForge, looking the ids up as slugs since those usually match. Returns the ids found on
+/// neither; ids that couldn't be looked up are only logged.
+async fn install_by_mod_id(
+    dir: &Path,
+    ids: Vec<String>,
+    game_version: &str,
+    loader: ModLoader,
+) -> color_eyre::Result<Vec<String>> {
+    let modrinth = ModrinthProvider {
+        game_version: game_version.to_string(),
+        loader: loader.clone(),
+        content_type: ContentType::Mod,
+        channel: None,
+    };
+    let mut roots = Vec::new();
+    let mut rest = Vec::new();
+    for id in ids {
+        let requirement = Requirement::project(&id);
+        match modrinth.find(&requirement).await {
+            Ok(Some(_)) => roots.push(requirement),
+            Ok(None) => rest.push(id),
+            Err(err) => warn!("Could not look {} up on Modrinth: {}", id, err),
+        }
+    }
+    if !roots.is_empty() {
+        let resolution = resolve(&modrinth, dir, roots, Some(false)).await?;
+        resolution.install(&modrinth, dir).await?;
+    }
+    if rest.is_empty() {
+        return Ok(rest);
+    }
+
+    let curseforge = CurseForgeProvider {
+        api: CurseForgeAPI::from_env(),
+        game_version: game_version.to_string(),
+        loader: loader.clone(),
+        content_type: ContentType::Mod,
+    };
+    let mut roots = Vec::new();
+    let mut not_found = Vec::new();
+    for id in rest {
+        let mods = match curseforge
+            .api
+            .search_mods(game_version, loader.clone(), &id, 10)
+            .await
+        {
+            Ok(mods) => mods,
+            Err(err) => {
+                warn!("Could not look {} up on CurseForge: {}", id, err);
+                continue;
+            }
+        };
+        match mods.iter().find(|mod_| mod_.slug == id) {
+            Some(mod_) => roots.push(Requirement::project(&mod_.id.to_string())),
+            None => not_found.push(id),
+        }
+    }
+    if !roots.is_empty() {
+        let resolution = resolve(&curseforge, dir, roots, Some(false)).await?;
+        resolution.install(&curseforge, dir).await?;
+    }
+    Ok(not_found)
+}
+
+/// Guesses the source of a mod spec: repos are looked up on the forge their host runs, anything
+/// else on the first platform in the config's `sources` that can be searched, or Modrinth.
+fn infer_source(mod_: &str, defaults: &Defaults) -> color_eyre::Result<Source> {
+    // Jars are mods, zips are packs
+    let is_archive = |path: &str| {
+        let path = path.to_lowercase();
+        path.ends_with(".jar") || path.ends_with(".zip")
+    };
+    if let Ok(url) = url::Url::parse(mod_)
+        && matches!(url.scheme(), "http" | "https")
+        && is_archive(url.path())
+    {
+        return Ok(Source::Url);
+    }
+    if is_archive(mod_) && Path::new(mod_).is_file() {
+        return Ok(Source::Local);
+    }
+    if maven::Coordinate::parse(mod_).is_some() {
+        return Ok(Source::Maven);
+    }
+    let Some(spec) = RepoSpec::parse(mod_) else {
+        // Only the platforms that can be searched by name
+        let source = defaults
+            .sources
+            .iter()
+            .find(|source| {
+                matches!(
+                    source,
+                    Source::Modrinth | Source::CurseForge | Source::Hangar
+                )
+            })
+            .cloned();
+        return Ok(source.unwrap_or(Source::Modrinth));
+    };
+    match spec.detect_forge(&Config::load()?) {
+        Some(forge) => Ok(forge.source()),
+        None => bail!(
+            "Could not tell which forge {} runs, pass it with --source",
+            spec.host.unwrap_or_default()
+        ),
+    }
+}
+
+/// Adds an `Installed:` line to the verbose listing of a mod, if modder knows why it is installed.
+fn with_install_reason(out: String, install_reason: Option<String>) -> String {
+    match install_reason {
+        Some(install_reason) => format!("{}\n\tInstalled: {}\n\n", out.trim_end(), install_reason),
+        None => out,
+    }
+}
+
+/// Deletes `file_names` from `dir`, then offers to delete the dependencies that were only
+/// installed for them, repeating until nothing else is orphaned.
+fn remove_mods(dir: &Path, mut file_names: Vec<String>) -> color_eyre::Result<()> {
+    while !file_names.is_empty() {
+        let mut dir_metadata = DirMetadata::load(dir)?;
+        let mut removed = Vec::new();
+        for file_name in &file_names {
+            fs::remove_file(dir.join(file_name))?;
+            info!("Removed {}", file_name);
+            if let Some(record) = dir_metadata.remove_mod(file_name) {
+                removed.push(record.project_id);
             }
+        }
+        let orphans = dir_metadata
+            .orphans_after_removing(&removed)
+            .into_iter()
+            .filter_map(|file_name| {
+                let disabled = format!("{}.disabled", file_name);
+                if dir.join(&file_name).exists() {
+                    Some(file_name)
+                } else if dir.join(&disabled).exists() {
+                    Some(disabled)
+                } else {
+                    None
+                }
+            })
+            .collect_vec();
+        dir_metadata.save(dir)?;
+        if orphans.is_empty() {
+            break;
+        }
+        let defaults = (0..orphans.len()).collect_vec();
+        file_names = inquire::MultiSelect::new(
+            "These dependencies are no longer required by any mod. Remove them?",
+            orphans,
+        )
+        .with_default(&defaults)
+        .prompt()?;
+    }
+    Ok(())
+}
+
+/// Resolves `query` to a single mod file in `dir`, prompting if more than one file matches.
+fn select_mod_file(dir: &Path, query: &str) -> color_eyre::Result<String> {
+    let files = find_mod_files(dir, query)?
+        .into_iter()
+        .filter_map(|path| Some(path.file_name()?.to_string_lossy().to_string()))
+        .collect_vec();
+    match files.len() {
+        0 => bail!("Could not find mod {} in {}", query, dir.display()),
+        1 => Ok(files[0].clone()),
+        _ => Ok(inquire::Select::new("Select Mod", files).prompt()?),
+    }
+}
+
+/// Refuses to pin `file_name` at `version` unless it is that version: either its file name says
+/// so or Modrinth knows the file as that version.
+async fn check_pin_version(dir: &Path, file_name: &str, version: &str) -> color_eyre::Result<()> {
+    if file_name.contains(version) {
+        return Ok(());
+    }
+    let hash = calc_sha512(&dir.join(file_name).to_string_lossy());
+    match VersionData::from_hash(hash).await {
+        Ok(version_data) if version_data.get_version() == version => Ok(()),
+        Ok(version_data) => bail!(
+            "{} is version {}, not {}",
+            file_name,
+            version_data.get_version(),
+            version
+        ),
+        Err(_) => bail!(
+            "Could not tell whether {} is version {}. Pin it without a version to hold the \
+             installed file",
+            file_name,
+            version
+        ),
+    }
+}
+
+fn toggle(dir: PathBuf) -> color_eyre::Result<()> {
+    // Only the mods (or packs) are offered, never `.modder.json` or anything else in `dir`
+    let paths = find_mod_files(&dir, "")?;
+    let filenames = paths
+        .iter()
+        .map(|path| {
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
         })
+        .collect_vec();
+    let defaults = filenames
+        .iter()
+        .enumerate()
+        .filter_map(|(i, f)| (!f.ends_with(".disabled")).then_some(i))
         .collect::<Vec<usize>>();
 
-    let prompt = inquire::MultiSelect::new("Select Mods", filenames).with_default(&defaults);
-    let filenames = prompt.prompt().unwrap();
-    for filename in toggle_map.iter() {
-        let name = &filename.0.split('/').last().unwrap_or("");
-        let predicate = !filenames.contains(name);
-        let path = filename.0.clone();
-        if predicate {
+    let prompt =
+        inquire::MultiSelect::new("Select Mods", filenames.clone()).with_default(&defaults);
+    let selected = prompt.prompt()?;
+    for (path, filename) in paths.iter().zip(&filenames) {
+        let path = path.to_string_lossy();
+        if !selected.contains(filename) {
             if !path.ends_with(".disabled") {
-                fs::rename(&path, format!("{}.disabled", path)).unwrap();
+                fs::rename(path.as_ref(), format!("{}.disabled", path))?;
             }
             continue;
         }
-        if path.ends_with(".disabled") {
-            fs::rename(&path, path.replace(".disabled", "")).unwrap();
+        if let Some(enabled) = path.strip_suffix(".disabled") {
+            fs::rename(path.as_ref(), enabled)?;
         }
     }
     Ok(())
@@ -0,0 +1,212 @@
+use crate::Clients;
+use crate::cli::Source;
+#[cfg(feature = "github")]
+use crate::gh_releases::RepoSpec;
+#[cfg(feature = "github")]
+use crate::metadata::Metadata;
+#[cfg(feature = "modrinth")]
+use crate::modrinth_wrapper::modrinth;
+use color_eyre::Result;
+#[cfg(feature = "github")]
+use color_eyre::eyre::ContextCompat;
+use color_eyre::eyre::bail;
+#[cfg(feature = "colored")]
+use colored::Colorize;
+use std::path::Path;
+
+#[cfg(feature = "colored")]
+const GRAY: (u8, u8, u8) = (128, 128, 128);
+
+/// What changed in a mod between the installed version and the one it was updated to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModChangelog {
+    pub name: String,
+    pub source: Source,
+    pub from: String,
+    pub to: String,
+    /// The changelog of every version after `from` up to and including `to`, newest first.
+    pub entries: Vec<ChangelogEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChangelogEntry {
+    pub version: String,
+    pub changelog: String,
+}
+
+impl ModChangelog {
+    /// Collects the changelogs between the mod at `old_path` and its replacement at `new_path`.
+    /// Both files have to exist.
+    #[cfg_attr(
+        not(any(feature = "curseforge", feature = "github")),
+        allow(unused_variables, unreachable_code)
+    )]
+    pub async fn collect(
+        source: &Source,
+        clients: &Clients,
+        old_path: &Path,
+        new_path: &Path,
+    ) -> Result<Self> {
+        let changelog = match source {
+            #[cfg(feature = "modrinth")]
+            Source::Modrinth => modrinth::changelog_between(old_path, new_path).await?,
+            #[cfg(feature = "curseforge")]
+            Source::CurseForge => {
+                clients
+                    .curseforge
+                    .changelog_between(old_path, new_path)
+                    .await?
+            }
+            #[cfg(feature = "github")]
+            Source::Github | Source::Gitea | Source::Gitlab => {
+                let metadata = Metadata::get_all_metadata(old_path.to_path_buf())?;
+                let repo = metadata
+                    .get("repo")
+                    .context("Could not find the repo of the mod")?;
+                let spec = RepoSpec::parse(repo)
+                    .context("Invalid repo, expected <owner>/<repo> or <host>/<owner>/<repo>")?;
+                let forge = clients
+                    .github
+                    .for_repo(&spec, Some(source))
+                    .context("Unknown forge")?;
+                let file_name = |path: &Path| {
+                    path.file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string()
+                };
+                forge
+                    .changelog_between(
+                        &spec.owner,
+                        &spec.repo,
+                        &file_name(old_path),
+                        &file_name(new_path),
+                    )
+                    .await?
+            }
+            Source::Hangar | Source::Maven | Source::Url | Source::Local => {
+                bail!("Collecting changelogs from {} is not supported", source)
+            }
+            #[allow(unreachable_patterns)]
+            _ => bail!(source.disabled_error()),
+        };
+        Ok(changelog)
+    }
+
+    #[cfg(feature = "colored")]
+    pub fn format(&self) -> String {
+        let mut output = format!(
+            "{} {} {} {}\n",
+            self.name.bold(),
+            self.from.truecolor(GRAY.0, GRAY.1, GRAY.2),
+            "->".truecolor(GRAY.0, GRAY.1, GRAY.2),
+            self.to.green()
+        );
+        if self.entries.is_empty() {
+            output.push_str("\tNo changelog published\n");
+        }
+        for entry in &self.entries {
+            output.push_str(&format!("\t{}\n", entry.version.cyan()));
+            for line in entry.changelog.trim().lines() {
+                output.push_str(&format!("\t\t{}\n", line));
+            }
+        }
+        output
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut output = format!("## {} ({} → {})\n\n", self.name, self.from, self.to);
+        if self.entries.is_empty() {
+            output.push_str("_No changelog published._\n\n");
+        }
+        for entry in &self.entries {
+            output.push_str(&format!("### {}\n\n", entry.version));
+            let changelog = entry.changelog.trim();
+            if changelog.is_empty() {
+                output.push_str("_No changelog published._\n\n");
+            } else {
+                output.push_str(changelog);
+                output.push_str("\n\n");
+            }
+        }
+        output
+    }
+}
+
+/// Builds a Markdown report of the changes made by updating to `game_version`, meant to be shared
+/// with players.
+pub fn markdown_report(changelogs: &[ModChangelog], game_version: &str) -> String {
+    let mut output = format!("# Changes for {}\n\n", game_version);
+    if changelogs.is_empty() {
+        output.push_str("No mods were updated.\n");
+    }
+    for changelog in changelogs {
+        output.push_str(&changelog.to_markdown());
+    }
+    output
+}
+
+/// Returns the items of the newest-first `items` after the installed one, up to and including the
+/// target. If the installed item cannot be found, only the target is returned.
+pub fn between<T>(
+    items: &[T],
+    is_installed: impl Fn(&T) -> bool,
+    is_target: impl Fn(&T) -> bool,
+) -> &[T] {
+    let Some(target) = items.iter().position(is_target) else {
+        return &[];
+    };
+    let installed = items[target..]
+        .iter()
+        .position(is_installed)
+        .map_or(target + 1, |installed| target + installed);
+    &items[target..installed]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_between() {
+        let versions = ["1.4", "1.3", "1.2", "1.1", "1.0"];
+        assert_eq!(
+            between(&versions, |v| *v == "1.1", |v| *v == "1.3"),
+            ["1.3", "1.2"]
+        );
+        assert_eq!(
+            between(&versions, |v| *v == "0.9", |v| *v == "1.1"),
+            ["1.1"]
+        );
+        assert!(between(&versions, |v| *v == "1.3", |v| *v == "1.3").is_empty());
+        assert!(between(&versions, |v| *v == "1.0", |v| *v == "2.0").is_empty());
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let changelog = ModChangelog {
+            name: "Sodium".to_string(),
+            source: Source::Modrinth,
+            from: "0.5.0".to_string(),
+            to: "0.6.0".to_string(),
+            entries: vec![
+                ChangelogEntry {
+                    version: "0.6.0".to_string(),
+                    changelog: "- Updated to 1.21\n".to_string(),
+                },
+                ChangelogEntry {
+                    version: "0.5.1".to_string(),
+                    changelog: String::new(),
+                },
+            ],
+        };
+        assert_eq!(
+            markdown_report(&[changelog], "1.21"),
+            "# Changes for 1.21\n\n\
+             ## Sodium (0.5.0 → 0.6.0)\n\n\
+             ### 0.6.0\n\n- Updated to 1.21\n\n\
+             ### 0.5.1\n\n_No changelog published._\n\n"
+        );
+    }
+}
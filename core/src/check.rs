@@ -0,0 +1,279 @@
+use crate::ModLoader;
+use crate::manifest::{self, ModManifest};
+#[cfg(feature = "modrinth")]
+use crate::{calc_sha512, modrinth_wrapper::modrinth::VersionData};
+use itertools::Itertools;
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Error reading the directory: {0}")]
+    IOErr(#[from] std::io::Error),
+    #[cfg(feature = "modrinth")]
+    #[error("Error looking the mods up on Modrinth: {0}")]
+    Modrinth(#[from] crate::modrinth_wrapper::modrinth::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A reason the mods in a directory won't work together.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Issue {
+    /// Several jars contain the same mod, e.g. an old version left behind by an update.
+    Duplicate { id: String, files: Vec<String> },
+    /// A mod declares that it can't be loaded with another one.
+    Incompatible { file: String, other: String },
+    /// A mod declares that it may misbehave with another one.
+    Conflict { file: String, other: String },
+    /// A mod is built for another loader.
+    WrongLoader { file: String, reason: String },
+}
+
+impl Issue {
+    /// Whether the game won't start, rather than possibly misbehave.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Issue::Conflict { .. })
+    }
+
+    pub fn involves(&self, file_name: &str) -> bool {
+        match self {
+            Issue::Duplicate { files, .. } => files.iter().any(|file| file == file_name),
+            Issue::Incompatible { file, other } | Issue::Conflict { file, other } => {
+                file == file_name || other == file_name
+            }
+            Issue::WrongLoader { file, .. } => file == file_name,
+        }
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::Duplicate { id, files } => {
+                write!(f, "{} all contain the mod {}", files.join(", "), id)
+            }
+            Issue::Incompatible { file, other } => {
+                write!(f, "{} is incompatible with {}", file, other)
+            }
+            Issue::Conflict { file, other } => write!(f, "{} may not work with {}", file, other),
+            Issue::WrongLoader { file, reason } => write!(f, "{} is {}", file, reason),
+        }
+    }
+}
+
+/// The jars in `dir`. Disabled jars aren't loaded, so they are left out.
+pub fn jar_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "jar") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The jars of a directory with the manifests in them, by file name.
+#[derive(Debug, Clone, Default)]
+pub struct ModSet {
+    jars: BTreeMap<String, Vec<ModManifest>>,
+}
+
+impl ModSet {
+    /// Reads the manifests of every jar in `dir`. Jars whose manifests can't be read are kept
+    /// without any, so only Modrinth can tell anything about them.
+    pub fn read_dir(dir: &Path) -> Result<Self> {
+        let jars = jar_files(dir)?
+            .into_iter()
+            .map(|path| {
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                let manifests = ModManifest::read_jar(&path).unwrap_or_default();
+                (file_name.to_string(), manifests)
+            })
+            .collect();
+        Ok(Self { jars })
+    }
+
+    pub fn with(mut self, file_name: &str, manifests: Vec<ModManifest>) -> Self {
+        self.jars.insert(file_name.to_string(), manifests);
+        self
+    }
+
+    pub fn file_names(&self) -> impl Iterator<Item = &String> {
+        self.jars.keys()
+    }
+
+    /// Finds jars containing the same mod and mods declaring each other incompatible. With a
+    /// `loader`, also finds mods it can't load, and only looks at the manifests it reads.
+    pub fn issues(&self, loader: Option<&ModLoader>) -> Vec<Issue> {
+        let mut by_id: BTreeMap<&str, Vec<(&str, &ModManifest)>> = BTreeMap::new();
+        for (file, manifests) in &self.jars {
+            for manifest in loaded(manifests, loader) {
+                by_id
+                    .entry(&manifest.id)
+                    .or_default()
+                    .push((file, manifest));
+            }
+        }
+
+        let mut issues = Vec::new();
+        for (id, found) in &by_id {
+            if found.len() > 1 {
+                issues.push(Issue::Duplicate {
+                    id: id.to_string(),
+                    files: found.iter().map(|(file, _)| file.to_string()).collect(),
+                });
+            }
+        }
+        for (file, manifests) in &self.jars {
+            for manifest in loaded(manifests, loader) {
+                let declared = manifest
+                    .breaks
+                    .iter()
+                    .map(|declared| (declared, true))
+                    .chain(manifest.conflicts.iter().map(|declared| (declared, false)));
+                for ((id, range), breaks) in declared {
+                    for (other, other_manifest) in by_id.get(id.as_str()).into_iter().flatten() {
+                        if other == file || !range.matches(&other_manifest.version) {
+                            continue;
+                        }
+                        let (file, other) = (file.clone(), other.to_string());
+                        issues.push(if breaks {
+                            Issue::Incompatible { file, other }
+                        } else {
+                            Issue::Conflict { file, other }
+                        });
+                    }
+                }
+            }
+            if let Some(reason) =
+                loader.and_then(|loader| manifest::loader_issue(manifests, loader))
+            {
+                issues.push(Issue::WrongLoader {
+                    file: file.clone(),
+                    reason,
+                });
+            }
+        }
+        issues.into_iter().unique().collect()
+    }
+}
+
+/// The manifests of a jar `loader` reads, once per mod.
+pub(crate) fn loaded<'a>(
+    manifests: &'a [ModManifest],
+    loader: Option<&ModLoader>,
+) -> Vec<&'a ModManifest> {
+    manifests
+        .iter()
+        .filter(|manifest| loader.is_none_or(|loader| manifest.supports_loader(loader)))
+        .unique_by(|manifest| manifest.id.clone())
+        .collect()
+}
+
+/// Looks the jars of `dir` up on Modrinth and finds those whose version is declared
+/// incompatible with a project that is also in the directory.
+#[cfg(feature = "modrinth")]
+pub async fn modrinth_incompatibilities(dir: &Path, mods: &ModSet) -> Result<Vec<Issue>> {
+    let hashes = mods
+        .file_names()
+        .map(|file| {
+            let hash = calc_sha512(&dir.join(file).to_string_lossy());
+            (file.clone(), hash)
+        })
+        .collect_vec();
+    let versions =
+        VersionData::from_hashes(&hashes.iter().map(|(_, hash)| hash.clone()).collect_vec())
+            .await?;
+    let with_version = hashes
+        .iter()
+        .filter_map(|(file, hash)| Some((file, versions.get(hash)?)))
+        .collect_vec();
+    let by_project: BTreeMap<&str, &String> = with_version
+        .iter()
+        .map(|(file, version)| (version.project_id.as_str(), *file))
+        .collect();
+    let mut issues = Vec::new();
+    for (file, version) in &with_version {
+        for project_id in version.incompatible_projects() {
+            if let Some(other) = by_project.get(project_id.as_str())
+                && other != file
+            {
+                issues.push(Issue::Incompatible {
+                    file: file.to_string(),
+                    other: other.to_string(),
+                });
+            }
+        }
+    }
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_issues() {
+        let fabric = |id: &str, version: &str, extra: &str| {
+            ModManifest::parse_fabric(&format!(
+                r#"{{"id": "{}", "version": "{}"{}}}"#,
+                id, version, extra
+            ))
+            .unwrap()
+        };
+        let mods = ModSet::default()
+            .with("sodium-0.5.jar", vec![fabric("sodium", "0.5.0", "")])
+            .with("sodium-0.6.jar", vec![fabric("sodium", "0.6.0", "")])
+            .with(
+                "optifabric.jar",
+                vec![fabric(
+                    "optifabric",
+                    "1.0",
+                    r#", "breaks": {"sodium": ">=0.6"}, "conflicts": {"iris": "*"}"#,
+                )],
+            )
+            .with("iris.jar", vec![fabric("iris", "1.8", "")])
+            .with(
+                "jei.jar",
+                ModManifest::parse_mods_toml(
+                    "[[mods]]\nmodId = \"jei\"\nversion = \"19.0\"",
+                    ModLoader::Forge,
+                )
+                .unwrap(),
+            )
+            .with("unknown.jar", Vec::new());
+        let issues = mods.issues(Some(&ModLoader::Quilt));
+        assert_eq!(
+            issues,
+            vec![
+                Issue::Duplicate {
+                    id: "sodium".to_string(),
+                    files: vec!["sodium-0.5.jar".to_string(), "sodium-0.6.jar".to_string()],
+                },
+                Issue::WrongLoader {
+                    file: "jei.jar".to_string(),
+                    reason: "built for Forge, not Quilt".to_string(),
+                },
+                Issue::Incompatible {
+                    file: "optifabric.jar".to_string(),
+                    other: "sodium-0.6.jar".to_string(),
+                },
+                Issue::Conflict {
+                    file: "optifabric.jar".to_string(),
+                    other: "iris.jar".to_string(),
+                },
+            ]
+        );
+        assert!(!issues[3].is_error());
+        assert!(issues[2].involves("sodium-0.6.jar"));
+        assert!(!issues[2].involves("sodium-0.5.jar"));
+    }
+}
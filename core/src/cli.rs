@@ -1,10 +1,32 @@
+#[cfg(feature = "cli")]
 use clap::{Parser, Subcommand};
 use itertools::Itertools;
-use std::{fmt::Display, path::PathBuf, sync::LazyLock};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, sync::LazyLock};
 use strum::{EnumIter, IntoEnumIterator};
 
+#[cfg(feature = "cli")]
 use crate::ModLoader;
-pub static SOURCES: LazyLock<Vec<Source>> = LazyLock::new(|| Source::iter().collect_vec());
+#[cfg(feature = "cli")]
+use crate::config::Channel;
+#[cfg(feature = "cli")]
+use crate::content::ContentType;
+#[cfg(feature = "cli")]
+use crate::curseforge_wrapper::{SortField, SortOrder};
+#[cfg(feature = "cli")]
+use crate::modrinth_wrapper::{modrinth::SupportLevel, search::SearchIndex};
+#[cfg(feature = "cli")]
+use std::path::PathBuf;
+/// The sources the TUI can search.
+pub static SOURCES: LazyLock<Vec<Source>> = LazyLock::new(|| {
+    Source::iter()
+        .filter(|source| {
+            source.is_enabled()
+                && !source.is_direct()
+                && !matches!(source, Source::Hangar | Source::Maven)
+        })
+        .collect_vec()
+});
 /// Modder is a tool for managing mods for Minecraft.
 /// It can add mods from Modrinth and Github.
 /// Other features include bulk-updating a directory of mods to a specified version
@@ -17,6 +39,7 @@ pub static SOURCES: LazyLock<Vec<Source>> = LazyLock::new(|| Source::iter().coll
 ///
 ///
 /// Developed by JayanAXHF
+#[cfg(feature = "cli")]
 #[derive(Debug, Parser)]
 pub struct Cli {
     #[command(subcommand)]
@@ -26,85 +49,329 @@ pub struct Cli {
     pub silent: bool,
 }
 
+#[cfg(feature = "cli")]
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     /// Add a mod to the supplied directory (defaults to current directory)
     #[command(arg_required_else_help = true)]
     Add {
-        /// The mod name
+        /// The mod name, repo, or the url or path of a jar
         #[arg(required = true)]
         mod_: String,
-        /// The game version to add this mod for
+        /// The game version to add this mod for. Defaults to the one in the config, or asked for
         #[arg(short, long)]
         version: Option<String>,
         /// Where to download the mod from
         #[arg(short, long)]
         source: Option<Source>,
-        /// Github token for any mods nested in a github repo.
+        /// Github token for any mods nested in a github repo. Defaults to `GITHUB_TOKEN`,
+        /// `GH_TOKEN` or the token in the config or credentials file.
         #[arg(short, long)]
         token: Option<String>,
-        /// Mod Loader
-        #[arg(short, long, default_value_t= ModLoader::Fabric)]
-        loader: ModLoader,
-        /// The directory to update mods in
-        #[arg( default_value_os_t = PathBuf::from("./"))]
-        dir: PathBuf,
+        /// Mod Loader. Defaults to the one in the config, or Fabric
+        #[arg(short, long)]
+        loader: Option<ModLoader>,
+        /// Only download Github release assets matching this glob, or regex if prefixed with
+        /// `regex:`. It is remembered for updates of the mod.
+        #[arg(long)]
+        asset_pattern: Option<String>,
+        /// Consider Github prereleases too
+        #[arg(long)]
+        prereleases: bool,
+        /// The least stable versions to add: release, beta or alpha. Defaults to the one in the
+        /// config, or any
+        #[arg(long)]
+        channel: Option<Channel>,
+        /// Check the url of a jar added from a url for a newer file on `update`
+        #[arg(long)]
+        check_updates: bool,
+        /// The Maven repository to look the artifact up in, instead of the ones in the config
+        #[arg(long)]
+        maven_repo: Option<String>,
+        /// What to add: mods, or resource packs, shaders or datapacks into their folder of the
+        /// instance at <dir>. Guessed from the name of <dir> if not given
+        #[arg(long = "type")]
+        content_type: Option<ContentType>,
+        /// The world to add datapacks to
+        #[arg(long)]
+        world: Option<String>,
+        /// Add every optional dependency without asking
+        #[arg(long, conflicts_with = "no_optional")]
+        with_optional: bool,
+        /// Leave out optional dependencies without asking
+        #[arg(long)]
+        no_optional: bool,
+        /// Keep the jars that were added even if they duplicate or are incompatible with mods
+        /// already in the directory. Without it, only those jars are removed again
+        #[arg(long)]
+        force: bool,
+        /// The directory to add the mod to. Defaults to `mods_dir` in the config or the current
+        /// directory
+        dir: Option<PathBuf>,
     },
     /// Bulk-update a directory of mods to the specified version
-    #[command(arg_required_else_help = true)]
     Update {
-        /// The directory to update mods in
-        #[arg( default_value_os_t = PathBuf::from("./"))]
-        dir: PathBuf,
-        /// The game version to add this mod for
+        /// The directory to update mods in. Defaults to `mods_dir` in the config or the current
+        /// directory
+        dir: Option<PathBuf>,
+        /// The game version to add this mod for. Defaults to the one in the config, or asked for
         #[arg(short, long)]
         version: Option<String>,
+        /// Delete the old file of every updated mod, whichever source updated it
         #[arg(short, long)]
         delete_previous: bool,
-        /// Github token for any mods nested in a github repo.
+        /// Github token for any mods nested in a github repo. Defaults to `GITHUB_TOKEN`,
+        /// `GH_TOKEN` or the token in the config or credentials file.
         #[arg(short, long)]
         token: Option<String>,
-        /// Where to download the mod from
+        /// The source to try first, before the mod's own priority, `sources` in the config or
+        /// every source in turn
         #[arg(short, long)]
         source: Option<Source>,
-        /// Don't check other sources if the mod is not found on <source>
-        #[arg(long, default_value_t = true)]
-        other_sources: bool,
+        /// Don't try other sources if the mod is not found on the first one
+        #[arg(long)]
+        no_fallback: bool,
         #[arg(short, long)]
         loader: Option<ModLoader>,
+        /// Print what changed in every updated mod
+        #[arg(short, long, default_value_t = false)]
+        changelog: bool,
+        /// Write what changed in every updated mod to a Markdown file
+        #[arg(long)]
+        changelog_file: Option<PathBuf>,
+        /// Consider Github prereleases too
+        #[arg(long)]
+        prereleases: bool,
+        /// The least stable versions to update to: release, beta or alpha. Defaults to the one in
+        /// the config, or any
+        #[arg(long)]
+        channel: Option<Channel>,
+        /// The most mods updated at once. Defaults to the one in the config, or all of them
+        #[arg(short = 'j', long)]
+        concurrency: Option<usize>,
+        /// What to update: mods, or resource packs, shaders or datapacks into their folder of the
+        /// instance at <dir>. Guessed from the name of <dir> if not given
+        #[arg(long = "type")]
+        content_type: Option<ContentType>,
+        /// The world to update datapacks in
+        #[arg(long)]
+        world: Option<String>,
     },
     /// Quickly add mods from a curated list to the supplied directory (defaults to current directory)
     QuickAdd {
-        /// The game version to add this mod for
+        /// The game version to add this mod for. Defaults to the one in the config, or asked for
         #[arg(short, long)]
         version: Option<String>,
         /// Find top `limit` mods from Modrinth
         #[arg(short, long, default_value_t = 100)]
         limit: u16,
-        /// The mod loader to use
-        #[arg(short, long, default_value_t = ModLoader::Fabric)]
-        loader: ModLoader,
+        /// The mod loader to use. Defaults to the one in the config, or Fabric
+        #[arg(short, long)]
+        loader: Option<ModLoader>,
+        /// Add every optional dependency without asking
+        #[arg(long, conflicts_with = "no_optional")]
+        with_optional: bool,
+        /// Leave out optional dependencies without asking
+        #[arg(long)]
+        no_optional: bool,
     },
     /// Toggle a mod in the supplied directory (defaults to current directory)
     Toggle {
         /// The game version to add this mod for
         #[arg(short, long)]
         version: Option<String>,
-        /// The directory to toggle mods in
-        #[arg(short, long, default_value_os_t = PathBuf::from("./"))]
-        dir: PathBuf,
+        /// The directory to toggle mods in. Defaults to `mods_dir` in the config or the current
+        /// directory
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
     },
     /// List all the mods in the supplied directory (defaults to current directory)
     List {
-        /// The directory to list mods in
-        #[arg(default_value_os_t = PathBuf::from("./"))]
-        dir: PathBuf,
+        /// The directory to list mods in. Defaults to `mods_dir` in the config or the current
+        /// directory
+        dir: Option<PathBuf>,
         /// Whether to print verbose imformation
         #[arg(short, long, default_value_t = false)]
         verbose: bool,
+        /// What to list: mods, or resource packs, shaders or datapacks into their folder of the
+        /// instance at <dir>. Guessed from the name of <dir> if not given
+        #[arg(long = "type")]
+        content_type: Option<ContentType>,
+        /// The world to list datapacks of
+        #[arg(long)]
+        world: Option<String>,
+    },
+    /// Remove mods from the supplied directory, offering to remove dependencies that are no longer needed
+    #[command(arg_required_else_help = true)]
+    Remove {
+        /// The mods to remove (matched against the file names in the directory)
+        #[arg(required = true)]
+        mods: Vec<String>,
+        /// The directory to remove mods from. Defaults to `mods_dir` in the config or the current
+        /// directory
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
+    },
+    /// Show why a mod is installed, printing the chain of mods that require it
+    #[command(arg_required_else_help = true)]
+    Why {
+        /// The mod to explain (matched against the file names in the directory)
+        #[arg(required = true)]
+        mod_: String,
+        /// The directory the mod is in. Defaults to `mods_dir` in the config or the current
+        /// directory
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
+    },
+    /// Find mods that won't work together: jars containing the same mod, mods declared
+    /// incompatible with each other, and mods for another loader
+    Check {
+        /// The directory to check. Defaults to `mods_dir` in the config or the current directory
+        dir: Option<PathBuf>,
+        /// The loader the mods are for. Defaults to the one in the config, otherwise mods aren't
+        /// checked against a loader
+        #[arg(short, long)]
+        loader: Option<ModLoader>,
+    },
+    /// Check that every mod's dependencies are in the directory, at versions they accept, and
+    /// that the mods support the game version
+    Doctor {
+        /// The directory to check. Defaults to `mods_dir` in the config or the current directory
+        dir: Option<PathBuf>,
+        /// The game version the mods are for. Defaults to the one in the config, otherwise mods
+        /// aren't checked against a game version
+        #[arg(short, long)]
+        version: Option<String>,
+        /// The loader the mods are for. Defaults to the one in the config, otherwise every
+        /// loader's metadata is read
+        #[arg(short, long)]
+        loader: Option<ModLoader>,
+        /// The Java version the game runs on, checked against the mods' `java` dependencies and
+        /// class files. Defaults to the version of the java on PATH
+        #[arg(long)]
+        java: Option<u32>,
+        /// Install the missing dependencies from Modrinth, or else CurseForge
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Search for mods without downloading them
+    Search {
+        /// What to search for
+        #[arg(default_value_t = String::new())]
+        query: String,
+        /// Where to search
+        #[arg(short, long, default_value_t = Source::Modrinth)]
+        source: Source,
+        /// What to search for: mods, resource packs, shaders or datapacks
+        #[arg(long = "type", default_value_t = ContentType::Mod)]
+        content_type: ContentType,
+        /// Only show mods in this category (can be repeated)
+        #[arg(short, long)]
+        category: Vec<String>,
+        /// Only show mods for any of these loaders (can be repeated)
+        #[arg(short, long)]
+        loader: Vec<ModLoader>,
+        /// Only show mods for any of these game versions (can be repeated)
+        #[arg(short, long)]
+        version: Vec<String>,
+        /// Only show mods with this client side support
+        #[arg(long)]
+        client_side: Option<SupportLevel>,
+        /// Only show mods with this server side support
+        #[arg(long)]
+        server_side: Option<SupportLevel>,
+        /// Only show mods under this license (SPDX id, e.g. `mit`)
+        #[arg(long)]
+        license: Option<String>,
+        /// How to order the results
+        #[arg(long, default_value_t = SearchIndex::Relevance)]
+        sort: SearchIndex,
+        /// The CurseForge field to order the results by, overriding `--sort`
+        #[arg(long)]
+        sort_field: Option<SortField>,
+        /// The direction to order CurseForge results in
+        #[arg(long, default_value_t = SortOrder::Desc)]
+        order: SortOrder,
+        /// Only show CurseForge mods in this category
+        #[arg(long)]
+        category_id: Option<u32>,
+        /// Only show CurseForge projects of this class (6 is mods)
+        #[arg(long)]
+        class_id: Option<u32>,
+        /// Only show CurseForge mods by this author
+        #[arg(long)]
+        author_id: Option<u32>,
+        /// The number of results per page
+        #[arg(long, default_value_t = 20)]
+        limit: u16,
+        /// The page of results to show, starting at 1
+        #[arg(short, long, default_value_t = 1)]
+        page: u16,
+        /// Print the results as JSON instead of a table
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Show the details of a mod, including the changelog of its latest version
+    #[command(arg_required_else_help = true)]
+    Info {
+        /// The mod name, or <owner>/<repo> for Github
+        #[arg(required = true)]
+        mod_: String,
+        /// The game version to show the changelog for
+        #[arg(short, long)]
+        version: Option<String>,
+        /// Where to look the mod up
+        #[arg(short, long)]
+        source: Option<Source>,
+        /// Mod Loader
+        #[arg(short, long)]
+        loader: Option<ModLoader>,
+        /// Github token for any mods nested in a github repo. Defaults to `GITHUB_TOKEN`,
+        /// `GH_TOKEN` or the token in the config or credentials file.
+        #[arg(short, long)]
+        token: Option<String>,
+    },
+    /// Pin a mod so that `update` keeps it at its current version
+    #[command(arg_required_else_help = true)]
+    Pin {
+        /// The mod to pin (matched against the file names in the directory)
+        #[arg(required = true)]
+        mod_: String,
+        /// The version the mod is held at. Pinning fails if the installed file isn't that version
+        version: Option<String>,
+        /// The directory the mod is in. Defaults to `mods_dir` in the config or the current
+        /// directory
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
+    },
+    /// Set the sources a mod is updated from, in order, overriding the global priority
+    #[command(arg_required_else_help = true)]
+    Prefer {
+        /// The mod to set the sources of (matched against the file names in the directory)
+        #[arg(required = true)]
+        mod_: String,
+        /// The sources to try, most preferred first. Without any, the mod follows the global
+        /// priority again
+        sources: Vec<Source>,
+        /// The directory the mod is in. Defaults to `mods_dir` in the config or the current
+        /// directory
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
+    },
+    /// Unpin a previously pinned mod
+    #[command(arg_required_else_help = true)]
+    Unpin {
+        /// The mod to unpin (matched against the file names in the directory)
+        #[arg(required = true)]
+        mod_: String,
+        /// The directory the mod is in. Defaults to `mods_dir` in the config or the current
+        /// directory
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
     },
 }
 
+#[cfg(feature = "cli")]
 impl Display for Commands {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let text = match self {
@@ -113,17 +380,70 @@ impl Display for Commands {
             Commands::Add { .. } => "Add".to_string(),
             Commands::Toggle { .. } => "Toggle".to_string(),
             Commands::List { .. } => "List".to_string(),
+            Commands::Remove { .. } => "Remove".to_string(),
+            Commands::Why { .. } => "Why".to_string(),
+            Commands::Check { .. } => "Check".to_string(),
+            Commands::Doctor { .. } => "Doctor".to_string(),
+            Commands::Search { .. } => "Search".to_string(),
+            Commands::Info { .. } => "Info".to_string(),
+            Commands::Pin { .. } => "Pin".to_string(),
+            Commands::Unpin { .. } => "Unpin".to_string(),
+            Commands::Prefer { .. } => "Prefer".to_string(),
         };
         write!(f, "{}", text)
     }
 }
 
-#[derive(Debug, Clone, clap::ValueEnum, PartialEq, Default, Hash, Eq, EnumIter)]
+#[derive(Debug, Clone, PartialEq, Default, Hash, Eq, EnumIter, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
 pub enum Source {
     #[default]
     Modrinth,
     Github,
     CurseForge,
+    /// Gitea and Forgejo instances, such as Codeberg
+    Gitea,
+    Gitlab,
+    /// Hangar, PaperMC's plugin repository
+    Hangar,
+    /// Maven repositories, such as maven.fabricmc.net
+    Maven,
+    /// A jar downloaded straight from a url
+    Url,
+    /// A jar copied from a local file
+    Local,
+}
+
+impl Source {
+    /// Whether the source is a git forge, whose mods are identified by a repo.
+    pub fn is_forge(&self) -> bool {
+        matches!(self, Source::Github | Source::Gitea | Source::Gitlab)
+    }
+    /// Whether the mod was added straight from a url or a file rather than found on a platform.
+    pub fn is_direct(&self) -> bool {
+        matches!(self, Source::Url | Source::Local)
+    }
+    /// Whether this build of modder supports the source, which depends on the crate's features.
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Source::Modrinth => cfg!(feature = "modrinth"),
+            Source::CurseForge => cfg!(feature = "curseforge"),
+            Source::Github | Source::Gitea | Source::Gitlab => cfg!(feature = "github"),
+            Source::Hangar | Source::Maven | Source::Url | Source::Local => true,
+        }
+    }
+    /// The error for using a source this build doesn't support.
+    pub fn disabled_error(&self) -> String {
+        let feature = match self {
+            Source::Gitea | Source::Gitlab => "github".to_string(),
+            source => source.to_string(),
+        };
+        format!(
+            "{} is not supported by this build of modder, enable the `{}` feature",
+            self, feature
+        )
+    }
 }
 
 impl Display for Source {
@@ -132,6 +452,12 @@ impl Display for Source {
             Source::Modrinth => "modrinth".to_string(),
             Source::Github => "github".to_string(),
             Source::CurseForge => "curseforge".to_string(),
+            Source::Gitea => "gitea".to_string(),
+            Source::Gitlab => "gitlab".to_string(),
+            Source::Hangar => "hangar".to_string(),
+            Source::Maven => "maven".to_string(),
+            Source::Url => "url".to_string(),
+            Source::Local => "local".to_string(),
         };
         write!(f, "{}", text)
     }
@@ -143,6 +469,13 @@ impl TryInto<Source> for &str {
         match self.trim().to_lowercase().as_str() {
             "modrinth" => Ok(Source::Modrinth),
             "github" => Ok(Source::Github),
+            "curseforge" | "curse-forge" => Ok(Source::CurseForge),
+            "gitea" | "forgejo" => Ok(Source::Gitea),
+            "gitlab" => Ok(Source::Gitlab),
+            "hangar" => Ok(Source::Hangar),
+            "maven" => Ok(Source::Maven),
+            "url" => Ok(Source::Url),
+            "local" => Ok(Source::Local),
             _ => Err("Invalid source".to_string()),
         }
     }
@@ -0,0 +1,304 @@
+use crate::ModLoader;
+use crate::cli::Source;
+use crate::gh_releases::Forge;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use strum::{Display, EnumIter};
+
+/// Name of modder's config file in the config directory.
+pub const CONFIG_FILE: &str = "config.toml";
+/// Name of the config file that overrides the global one for the directory modder is run in.
+pub const LOCAL_CONFIG_FILE: &str = "modder.toml";
+/// Name of the file API keys and tokens can be kept in, apart from the config, in the config
+/// directory.
+pub const CREDENTIALS_FILE: &str = "credentials.toml";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Error reading the config file: {0}")]
+    IOErr(#[from] std::io::Error),
+    #[error("Error parsing the config file: {0}")]
+    ParseErr(#[from] toml::de::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// modder's user configuration, read from `config.toml` in the config directory and overridden
+/// by `modder.toml` in the current directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub defaults: Defaults,
+    #[serde(default)]
+    pub github: GithubConfig,
+    /// Self-hosted forges by host, e.g. `git.example.com`.
+    #[serde(default)]
+    pub forges: BTreeMap<String, ForgeConfig>,
+    #[serde(default)]
+    pub maven: MavenConfig,
+    #[serde(default)]
+    pub curseforge: CurseForgeConfig,
+}
+
+/// Defaults for the command line flags, which override them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Defaults {
+    /// The game version to add and update mods for, like `--version`.
+    pub game_version: Option<String>,
+    /// The mod loader, like `--loader`.
+    pub loader: Option<ModLoader>,
+    /// The directory commands work in when none is given.
+    pub mods_dir: Option<PathBuf>,
+    /// The sources to update mods from, most preferred first. Every source is tried in turn if
+    /// empty.
+    #[serde(default)]
+    pub sources: Vec<Source>,
+    /// The least stable versions to install, like `--channel`.
+    pub channel: Option<Channel>,
+    /// The most mods updated at once, like `--concurrency`.
+    pub concurrency: Option<usize>,
+}
+
+impl Defaults {
+    /// The directory to work in: `dir` if given, else `mods_dir`, else the current directory.
+    pub fn dir(&self, dir: Option<PathBuf>) -> PathBuf {
+        dir.or_else(|| self.mods_dir.clone())
+            .unwrap_or_else(|| PathBuf::from("./"))
+    }
+
+    /// The mod loader to use: `loader` if given, else the configured one, else Fabric.
+    pub fn loader(&self, loader: Option<ModLoader>) -> ModLoader {
+        loader.or_else(|| self.loader.clone()).unwrap_or_default()
+    }
+}
+
+/// How stable the versions installed have to be. Each channel includes the more stable ones.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Display,
+    EnumIter,
+    Serialize,
+    Deserialize,
+)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Release,
+    Beta,
+    Alpha,
+}
+
+impl Channel {
+    /// Whether a version of `version_type`, as Modrinth names them, is on the channel. Unknown
+    /// types are taken to be releases.
+    pub fn allows(&self, version_type: &str) -> bool {
+        let channel = match version_type.to_lowercase().as_str() {
+            "beta" => Channel::Beta,
+            "alpha" => Channel::Alpha,
+            _ => Channel::Release,
+        };
+        channel <= *self
+    }
+
+    /// Whether Github prereleases are on the channel.
+    pub fn prereleases(&self) -> bool {
+        *self != Channel::Release
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GithubConfig {
+    /// Token used when neither `--token`, `GITHUB_TOKEN` nor `GH_TOKEN` are set.
+    pub token: Option<String>,
+    /// Whether to consider prereleases when looking for a mod's release.
+    #[serde(default)]
+    pub prereleases: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForgeConfig {
+    /// The forge running on the host, for hosts whose name doesn't tell.
+    pub forge: Option<Forge>,
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MavenConfig {
+    /// Repositories Maven artifacts are looked up in, in order. Defaults to
+    /// [`crate::maven::DEFAULT_REPOSITORIES`].
+    #[serde(default)]
+    pub repositories: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurseForgeConfig {
+    /// API key used when `CURSEFORGE_API_KEY` isn't set.
+    pub api_key: Option<String>,
+}
+
+/// API keys and tokens read from `credentials.toml` in the config directory, for those who'd
+/// rather not keep them in the config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Credentials {
+    pub curseforge_api_key: Option<String>,
+    pub github_token: Option<String>,
+}
+
+impl Credentials {
+    /// Loads the credentials file, or no credentials if there is none.
+    pub fn load() -> Result<Self> {
+        match Config::dir() {
+            Some(dir) => Self::load_from(&dir.join(CREDENTIALS_FILE)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+}
+
+impl Config {
+    /// The directory modder's config lives in, e.g. `~/.config/modder` on Linux.
+    pub fn dir() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "modder").map(|dirs| dirs.config_dir().to_path_buf())
+    }
+
+    /// Loads the config file overridden by the one in the current directory, or the defaults if
+    /// there are none.
+    pub fn load() -> Result<Self> {
+        let global = Self::dir().map(|dir| dir.join(CONFIG_FILE));
+        Self::load_layered(global.as_deref(), Path::new(LOCAL_CONFIG_FILE))
+    }
+
+    /// Loads the config at `global`, with every setting in the one at `local` taking precedence.
+    /// Tables are merged, so a local file only needs the settings it changes.
+    pub fn load_layered(global: Option<&Path>, local: &Path) -> Result<Self> {
+        let mut table = match global {
+            Some(global) => read_table(global)?,
+            None => toml::Table::new(),
+        };
+        merge_tables(&mut table, read_table(local)?);
+        Ok(table.try_into()?)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+}
+
+/// Reads a TOML file into a table, which is empty if there is no file.
+fn read_table(path: &Path) -> Result<toml::Table> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+    Ok(fs::read_to_string(path)?.parse()?)
+}
+
+/// Merges `overrides` into `base`, recursing into tables both have.
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge_tables(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_load_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDENTIALS_FILE);
+        assert_eq!(
+            Credentials::load_from(&path).unwrap(),
+            Credentials::default()
+        );
+        fs::write(&path, "curseforge_api_key = \"$2a$10$abc\"\n").unwrap();
+        assert_eq!(
+            Credentials::load_from(&path).unwrap(),
+            Credentials {
+                curseforge_api_key: Some("$2a$10$abc".to_string()),
+                github_token: None,
+            }
+        );
+        fs::write(&path, "[curseforge]\napi_key = \"key\"\n").unwrap();
+        assert!(Credentials::load_from(&path).is_err());
+    }
+    #[test]
+    fn test_load_layered() {
+        let dir = tempfile::tempdir().unwrap();
+        let global = dir.path().join(CONFIG_FILE);
+        let local = dir.path().join(LOCAL_CONFIG_FILE);
+        assert_eq!(
+            Config::load_layered(Some(&global), &local).unwrap(),
+            Config::default()
+        );
+        fs::write(
+            &global,
+            "[defaults]\ngame_version = \"1.21.4\"\nloader = \"fabric\"\n\
+             sources = [\"modrinth\", \"curseforge\"]\n\n[github]\ntoken = \"ghp_abc\"\n",
+        )
+        .unwrap();
+        fs::write(
+            &local,
+            "[defaults]\nloader = \"neoforge\"\nchannel = \"beta\"\nconcurrency = 4\n",
+        )
+        .unwrap();
+        let config = Config::load_layered(Some(&global), &local).unwrap();
+        assert_eq!(
+            config.defaults,
+            Defaults {
+                game_version: Some("1.21.4".to_string()),
+                loader: Some(ModLoader::NeoForge),
+                mods_dir: None,
+                sources: vec![Source::Modrinth, Source::CurseForge],
+                channel: Some(Channel::Beta),
+                concurrency: Some(4),
+            }
+        );
+        assert_eq!(config.github.token.as_deref(), Some("ghp_abc"));
+        fs::write(&local, "[defaults]\nloader = \"fabirc\"\n").unwrap();
+        assert!(Config::load_layered(Some(&global), &local).is_err());
+    }
+
+    #[test]
+    fn test_channel_allows() {
+        assert!(Channel::Release.allows("release"));
+        assert!(!Channel::Release.allows("beta"));
+        assert!(Channel::Beta.allows("BETA"));
+        assert!(!Channel::Beta.allows("alpha"));
+        assert!(Channel::Alpha.allows("release"));
+    }
+}
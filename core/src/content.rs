@@ -0,0 +1,180 @@
+use crate::ModLoader;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use strum::{Display, EnumIter, IntoEnumIterator};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Datapacks are installed into a world, pass it with --world")]
+    NoWorld,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// The kinds of projects modder installs, each into its own folder of an instance.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Display, EnumIter, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ContentType {
+    #[default]
+    Mod,
+    #[cfg_attr(feature = "cli", value(alias = "resourcepack", alias = "texture-pack"))]
+    ResourcePack,
+    #[cfg_attr(feature = "cli", value(alias = "shaderpack"))]
+    Shader,
+    Datapack,
+}
+
+impl ContentType {
+    /// The project type Modrinth files this content under.
+    pub fn modrinth_project_type(&self) -> &'static str {
+        match self {
+            ContentType::Mod => "mod",
+            ContentType::ResourcePack => "resourcepack",
+            ContentType::Shader => "shader",
+            ContentType::Datapack => "datapack",
+        }
+    }
+
+    /// The class CurseForge files this content under.
+    pub fn curseforge_class_id(&self) -> u32 {
+        match self {
+            ContentType::Mod => 6,
+            ContentType::ResourcePack => 12,
+            ContentType::Shader => 6552,
+            ContentType::Datapack => 6945,
+        }
+    }
+
+    /// The loaders to look versions up for on Modrinth. Packs aren't built for a mod loader:
+    /// resource packs are published for `minecraft`, datapacks for `datapack` and shaders for
+    /// whichever shader mod runs them, so any of those will do.
+    pub fn modrinth_loaders(&self, loader: &ModLoader) -> Vec<String> {
+        match self {
+            ContentType::Mod if *loader == ModLoader::Any => Vec::new(),
+            ContentType::Mod => vec![loader.to_string().to_lowercase()],
+            ContentType::ResourcePack => vec!["minecraft".to_string()],
+            ContentType::Shader => Vec::new(),
+            ContentType::Datapack => vec!["datapack".to_string()],
+        }
+    }
+
+    /// The folder of an instance the content goes into.
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            ContentType::Mod => "mods",
+            ContentType::ResourcePack => "resourcepacks",
+            ContentType::Shader => "shaderpacks",
+            ContentType::Datapack => "datapacks",
+        }
+    }
+
+    /// The content a folder holds, judged by its name. Anything else is taken to be a mods
+    /// folder.
+    pub fn from_dir(dir: &Path) -> Self {
+        let name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase());
+        ContentType::iter()
+            .find(|content_type| name.as_deref() == Some(content_type.dir_name()))
+            .unwrap_or_default()
+    }
+
+    /// Resolves the folder to work on: with a content type other than mods, `dir` is the
+    /// instance and the content's folder inside it is used, unless `dir` already is that folder.
+    /// Without a content type, it is guessed from `dir`.
+    pub fn resolve(
+        content_type: Option<ContentType>,
+        dir: &Path,
+        world: Option<&str>,
+    ) -> Result<(ContentType, PathBuf)> {
+        let Some(content_type) = content_type else {
+            return Ok((ContentType::from_dir(dir), dir.to_path_buf()));
+        };
+        if content_type == ContentType::Mod || ContentType::from_dir(dir) == content_type {
+            return Ok((content_type, dir.to_path_buf()));
+        }
+        let dir = match content_type {
+            ContentType::Datapack => dir
+                .join("saves")
+                .join(world.ok_or(Error::NoWorld)?)
+                .join(content_type.dir_name()),
+            _ => dir.join(content_type.dir_name()),
+        };
+        Ok((content_type, dir))
+    }
+
+    /// Whether a file (enabled or disabled) is of this content: mods are jars, packs are zips.
+    pub fn matches_file(&self, file_name: &str) -> bool {
+        let file_name = file_name.to_lowercase();
+        let file_name = file_name.strip_suffix(".disabled").unwrap_or(&file_name);
+        match self {
+            ContentType::Mod => file_name.ends_with(".jar"),
+            _ => file_name.ends_with(".zip"),
+        }
+    }
+
+    /// Whether the content is loaded by a mod loader, and so needs one to be looked up.
+    pub fn is_mod(&self) -> bool {
+        *self == ContentType::Mod
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_resolve() {
+        let instance = Path::new(".minecraft");
+        assert_eq!(
+            ContentType::resolve(None, Path::new("./"), None).unwrap(),
+            (ContentType::Mod, PathBuf::from("./"))
+        );
+        assert_eq!(
+            ContentType::resolve(None, &instance.join("shaderpacks"), None).unwrap(),
+            (ContentType::Shader, instance.join("shaderpacks"))
+        );
+        assert_eq!(
+            ContentType::resolve(Some(ContentType::ResourcePack), instance, None).unwrap(),
+            (ContentType::ResourcePack, instance.join("resourcepacks"))
+        );
+        assert_eq!(
+            ContentType::resolve(
+                Some(ContentType::ResourcePack),
+                &instance.join("resourcepacks"),
+                None
+            )
+            .unwrap(),
+            (ContentType::ResourcePack, instance.join("resourcepacks"))
+        );
+        assert_eq!(
+            ContentType::resolve(Some(ContentType::Datapack), instance, Some("New World")).unwrap(),
+            (
+                ContentType::Datapack,
+                instance.join("saves/New World/datapacks")
+            )
+        );
+        assert!(ContentType::resolve(Some(ContentType::Datapack), instance, None).is_err());
+    }
+
+    #[test]
+    fn test_matches_file() {
+        assert!(ContentType::Mod.matches_file("sodium.jar.disabled"));
+        assert!(!ContentType::Mod.matches_file("faithful.zip"));
+        assert!(ContentType::ResourcePack.matches_file("Faithful 32x.ZIP"));
+        assert!(!ContentType::Shader.matches_file("iris.jar"));
+        assert_eq!(
+            ContentType::Shader.modrinth_loaders(&ModLoader::Fabric),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            ContentType::Mod.modrinth_loaders(&ModLoader::NeoForge),
+            vec!["neoforge"]
+        );
+    }
+}
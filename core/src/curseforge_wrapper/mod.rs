@@ -1,39 +1,62 @@
 mod file_utils;
 mod hash;
+mod search;
 mod structs;
-use crate::ModLoader;
-use color_eyre::eyre::Context;
+use crate::changelog::{self, ChangelogEntry, ModChangelog};
+use crate::cli::Source;
+use crate::config::{Config, Credentials};
+use crate::content::ContentType;
+use crate::info::{ProjectInfo, strip_html};
+use crate::resolver::{Candidate, DependencyKind, Requirement, VersionProvider};
+use crate::{Link, ModLoader, UrlBuilder};
 pub use file_utils::get_jar_contents;
 pub use hash::*;
+use itertools::Itertools;
 use percent_encoding::percent_decode;
 use reqwest::{
     Method,
     header::{HeaderMap, HeaderName, HeaderValue},
 };
+pub use search::*;
 use serde_json::json;
-use std::{fs, path::PathBuf, sync::LazyLock};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 pub use structs::*;
-use tracing::debug;
-use url::Url;
+use tracing::{debug, warn};
 
 type Result<T> = color_eyre::Result<T, CurseForgeError>;
 pub const GAME_ID: u32 = 432;
+/// The class Bukkit, Spigot and Paper plugins are filed under.
+pub const BUKKIT_PLUGINS_CLASS_ID: u32 = 5;
 pub const BASE_URL: &str = "https://api.curseforge.com/v1";
-pub const API_KEY: &str = env!("CURSEFORGE_API_KEY");
-pub static HEADERS: LazyLock<HeaderMap> = LazyLock::new(|| {
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        HeaderName::from_static("x-api-key"),
-        HeaderValue::from_str(API_KEY)
-            .context("Invalid API key")
-            .unwrap(),
-    );
-    headers.insert(
-        HeaderName::from_static("accept"),
-        HeaderValue::from_static("application/json"),
-    );
-    headers
-});
+/// The environment variable the API key is read from.
+pub const API_KEY_VAR: &str = "CURSEFORGE_API_KEY";
+
+/// The CurseForge API key from `CURSEFORGE_API_KEY`, the config file or the credentials file, in
+/// that order.
+pub fn api_key_from_env() -> Option<String> {
+    let config = || {
+        Config::load()
+            .inspect_err(|err| warn!("Ignoring the config file: {}", err))
+            .ok()?
+            .curseforge
+            .api_key
+    };
+    let credentials = || {
+        Credentials::load()
+            .inspect_err(|err| warn!("Ignoring the credentials file: {}", err))
+            .ok()?
+            .curseforge_api_key
+    };
+    env::var(API_KEY_VAR)
+        .ok()
+        .filter(|key| !key.trim().is_empty())
+        .or_else(config)
+        .or_else(credentials)
+        .filter(|key| !key.trim().is_empty())
+}
 
 pub trait AsModIdVec {
     fn as_mod_id_vec(&self) -> Vec<u32>;
@@ -63,6 +86,28 @@ impl CurseForgeAPI {
             api_key,
         }
     }
+    /// A client using the key found by [`api_key_from_env`]. Without one, every request fails
+    /// with [`CurseForgeError::NoApiKey`], so commands not touching CurseForge still work.
+    pub fn from_env() -> Self {
+        Self::new(api_key_from_env().unwrap_or_default())
+    }
+    /// The headers every request is sent with, including the API key.
+    fn headers(&self) -> Result<HeaderMap> {
+        if self.api_key.trim().is_empty() {
+            return Err(CurseForgeError::NoApiKey);
+        }
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("x-api-key"),
+            HeaderValue::from_str(self.api_key.trim())
+                .map_err(|_| CurseForgeError::InvalidApiKey)?,
+        );
+        headers.insert(
+            HeaderName::from_static("accept"),
+            HeaderValue::from_static("application/json"),
+        );
+        Ok(headers)
+    }
     pub async fn search_mods(
         &self,
         game_version: &str,
@@ -70,28 +115,36 @@ impl CurseForgeAPI {
         search: &str,
         page_size: u32,
     ) -> Result<Vec<Mod>> {
-        let params = [
-            ("gameId", GAME_ID.to_string()),
-            ("index", 0.to_string()),
-            ("searchFilter", search.to_string()),
-            ("gameVersion", game_version.to_string()),
-            ("pageSize", page_size.to_string()),
-            ("sortField", 6.to_string()),
-            ("gameFlavors[0]", loader.as_num().to_string()),
-            ("sortOrder", "desc".to_string()),
-        ];
-        let params_str = params
-            .iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect::<Vec<String>>()
-            .join("&");
-        let url = format!("{}/mods/search?{params_str}", BASE_URL);
-        debug!(url = ?url);
-        let headers = HEADERS.clone();
+        let options = SearchOptions::new(search)
+            .game_version(game_version)
+            .loader(loader)
+            .page_size(page_size);
+        Ok(self.search(&options).await?.data)
+    }
+    /// Like [`CurseForgeAPI::search_mods`], but for projects of `content_type`.
+    pub async fn search_projects(
+        &self,
+        game_version: &str,
+        loader: ModLoader,
+        content_type: ContentType,
+        search: &str,
+        page_size: u32,
+    ) -> Result<Vec<Mod>> {
+        let options = SearchOptions::new(search)
+            .game_version(game_version)
+            .loader(loader)
+            .content_type(content_type)
+            .page_size(page_size);
+        Ok(self.search(&options).await?.data)
+    }
+    /// Searches for mods, returning one page of results along with the pagination info.
+    pub async fn search(&self, options: &SearchOptions) -> Result<Root> {
+        let url = options.to_url();
+        debug!(url = %url);
         let response = self
             .client
-            .request(Method::GET, Url::parse(&url)?)
-            .headers(headers)
+            .request(Method::GET, url.build()?)
+            .headers(self.headers()?)
             .send()
             .await?;
         let response = response.error_for_status()?;
@@ -99,7 +152,7 @@ impl CurseForgeAPI {
         debug!(body = ?body);
         let root: Root = serde_json::from_str(&body)?;
         debug!(root_data = ?root.data);
-        Ok(root.data)
+        Ok(root)
     }
     pub async fn get_mods<T>(&self, mod_ids: T) -> Result<Vec<Mod>>
     where
@@ -110,15 +163,15 @@ impl CurseForgeAPI {
             "modIds": mod_ids,
             "filterPcOnly": true,
         });
-        let url = format!("{}/mods", BASE_URL);
-        let mut headers = HEADERS.clone();
+        let url = UrlBuilder::new(BASE_URL, "/mods");
+        let mut headers = self.headers()?;
         headers.insert(
             HeaderName::from_static("content-type"),
             HeaderValue::from_static("application/json"),
         );
         let response = self
             .client
-            .request(Method::POST, Url::parse(&url)?)
+            .request(Method::POST, url.build()?)
             .headers(headers)
             .body(serde_json::to_string(&body)?)
             .send()
@@ -134,22 +187,17 @@ impl CurseForgeAPI {
         game_version: &str,
         mod_loader: ModLoader,
     ) -> Result<Vec<File>> {
-        let params = [
-            ("index", 0.to_string()),
-            ("gameVersion", game_version.to_string()),
-            ("pageSize", 1.to_string()),
-            ("modLoaderType", mod_loader.as_num().to_string()),
-        ];
-        let params_str = params
-            .iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect::<Vec<String>>()
-            .join("&");
-        let url = format!("{BASE_URL}/mods/{mod_id}/files?{params_str}");
+        let url = UrlBuilder::new(BASE_URL, "/mods")
+            .segment(mod_id)
+            .segment("files")
+            .param("index", 0)
+            .param("gameVersion", game_version)
+            .param("pageSize", 1)
+            .param("modLoaderType", mod_loader.as_num());
         let response = self
             .client
-            .request(Method::GET, Url::parse(&url)?)
-            .headers(HEADERS.clone())
+            .request(Method::GET, url.build()?)
+            .headers(self.headers()?)
             .send()
             .await?;
         let response = response.error_for_status()?;
@@ -157,15 +205,17 @@ impl CurseForgeAPI {
         let root = serde_json::from_str::<FileSearchRoot>(&body)?;
         Ok(root.data)
     }
-    pub async fn download_mod(&self, mod_id: u32, file_id: u32, dir: PathBuf) -> Result<()> {
-        let url = format!(
-            "{}/mods/{}/files/{}/download-url",
-            BASE_URL, mod_id, file_id
-        );
+    /// Downloads a file of a mod into `dir`, returning the path it was written to.
+    pub async fn download_mod(&self, mod_id: u32, file_id: u32, dir: PathBuf) -> Result<PathBuf> {
+        let url = UrlBuilder::new(BASE_URL, "/mods")
+            .segment(mod_id)
+            .segment("files")
+            .segment(file_id)
+            .segment("download-url");
         let response = self
             .client
-            .request(Method::GET, Url::parse(&url)?)
-            .headers(HEADERS.clone())
+            .request(Method::GET, url.build()?)
+            .headers(self.headers()?)
             .send()
             .await?;
         let response = response.error_for_status()?;
@@ -173,20 +223,25 @@ impl CurseForgeAPI {
         let json = serde_json::from_str::<DownloadFile>(&body)?;
         let url = json.data;
         let file_data = reqwest::get(url).await?;
-        let file_name = file_data.url().path_segments().unwrap().last().unwrap();
+        let file_name = file_data
+            .url()
+            .path_segments()
+            .unwrap()
+            .next_back()
+            .unwrap();
         let file_name = percent_decode(file_name.as_bytes()).decode_utf8_lossy();
         let path = dir.join(file_name.to_string());
         fs::create_dir_all(path.parent().unwrap())?;
         fs::write(&path, file_data.bytes().await?)?;
-        Ok(())
+        Ok(path)
     }
     pub async fn get_version_from_file(&self, file: PathBuf) -> Result<File> {
         let f = file.clone();
         let f_name = f.file_name().unwrap().to_str().unwrap();
         let contents = get_jar_contents(&file)?;
         let fingerprint = MurmurHash2::hash(&contents);
-        let url = format!("{BASE_URL}/fingerprints/{GAME_ID}");
-        let mut headers = HEADERS.clone();
+        let url = UrlBuilder::new(BASE_URL, "/fingerprints").segment(GAME_ID);
+        let mut headers = self.headers()?;
         headers.insert(
             HeaderName::from_static("content-type"),
             HeaderValue::from_static("application/json"),
@@ -198,7 +253,7 @@ impl CurseForgeAPI {
         let body = serde_json::to_string(&body)?;
         let response = self
             .client
-            .request(Method::POST, Url::parse(&url)?)
+            .request(Method::POST, url.build()?)
             .headers(headers)
             .body(body)
             .send()
@@ -220,8 +275,8 @@ impl CurseForgeAPI {
         let f_name = f.file_name().unwrap().to_str().unwrap();
         let contents = get_jar_contents(&file)?;
         let fingerprint = MurmurHash2::hash(&contents);
-        let url = format!("{BASE_URL}/fingerprints/{GAME_ID}");
-        let mut headers = HEADERS.clone();
+        let url = UrlBuilder::new(BASE_URL, "/fingerprints").segment(GAME_ID);
+        let mut headers = self.headers()?;
         headers.insert(
             HeaderName::from_static("content-type"),
             HeaderValue::from_static("application/json"),
@@ -233,7 +288,7 @@ impl CurseForgeAPI {
         let body = serde_json::to_string(&body)?;
         let response = self
             .client
-            .request(Method::POST, Url::parse(&url)?)
+            .request(Method::POST, url.build()?)
             .headers(headers)
             .body(body)
             .send()
@@ -251,6 +306,190 @@ impl CurseForgeAPI {
         let mod_ = self.get_mods(mod_id).await?;
         mod_.first().cloned().ok_or(CurseForgeError::NoModFound)
     }
+    /// Gets the changelog of a file as plain text.
+    pub async fn get_file_changelog(&self, mod_id: u32, file_id: u32) -> Result<String> {
+        let url = UrlBuilder::new(BASE_URL, "/mods")
+            .segment(mod_id)
+            .segment("files")
+            .segment(file_id)
+            .segment("changelog");
+        let response = self
+            .client
+            .request(Method::GET, url.build()?)
+            .headers(self.headers()?)
+            .send()
+            .await?;
+        let response = response.error_for_status()?;
+        let body = response.text().await?;
+        let changelog: StringResponse = serde_json::from_str(&body)?;
+        Ok(strip_html(&changelog.data))
+    }
+    /// Gets the files of a mod for every game version, newest first, `page_size` at a time until
+    /// a page contains a file matching `until` or there are no pages left.
+    pub async fn get_all_mod_files(
+        &self,
+        mod_id: u32,
+        page_size: u32,
+        until: impl Fn(&File) -> bool,
+    ) -> Result<Vec<File>> {
+        let mut files = Vec::new();
+        let mut index = 0;
+        loop {
+            let url = UrlBuilder::new(BASE_URL, "/mods")
+                .segment(mod_id)
+                .segment("files")
+                .param("index", index)
+                .param("pageSize", page_size);
+            let response = self
+                .client
+                .request(Method::GET, url.build()?)
+                .headers(self.headers()?)
+                .send()
+                .await?;
+            let response = response.error_for_status()?;
+            let body = response.text().await?;
+            let page = serde_json::from_str::<FileSearchRoot>(&body)?;
+            let found = page.data.iter().any(&until);
+            index += page.data.len() as u32;
+            files.extend(page.data);
+            if found
+                || !page
+                    .pagination
+                    .is_some_and(|pagination| pagination.has_more())
+            {
+                break;
+            }
+        }
+        files.sort_by_key(|file| std::cmp::Reverse(file.id));
+        Ok(files)
+    }
+    /// Collects the changelogs of the files between the mod at `old_path` and the one at
+    /// `new_path`, skipping files for other loaders.
+    pub async fn changelog_between(
+        &self,
+        old_path: &Path,
+        new_path: &Path,
+    ) -> Result<ModChangelog> {
+        let mod_ = self.get_mod_from_file(new_path.to_path_buf()).await?;
+        let old = self.get_version_from_file(old_path.to_path_buf()).await?;
+        let new = self.get_version_from_file(new_path.to_path_buf()).await?;
+        // CurseForge lists the loaders of a file among its game versions
+        let loaders = new
+            .game_versions
+            .iter()
+            .filter(|version| ModLoader::from_name(version).is_some())
+            .collect_vec();
+        let files = self
+            .get_all_mod_files(mod_.id, 50, |file| file.id == old.id)
+            .await?
+            .into_iter()
+            .filter(|file| {
+                file.id == old.id
+                    || loaders.is_empty()
+                    || file
+                        .game_versions
+                        .iter()
+                        .any(|version| loaders.contains(&version))
+            })
+            .collect_vec();
+        let mut entries = Vec::new();
+        for file in changelog::between(&files, |file| file.id == old.id, |file| file.id == new.id) {
+            entries.push(ChangelogEntry {
+                version: file.display_name.clone(),
+                changelog: self
+                    .get_file_changelog(mod_.id, file.id)
+                    .await
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(ModChangelog {
+            name: mod_.name,
+            source: Source::CurseForge,
+            from: old.display_name,
+            to: new.display_name,
+            entries,
+        })
+    }
+    /// Gets the details of a mod, with the changelog of its newest file for `game_version` and
+    /// `loader`.
+    pub async fn get_project_info(
+        &self,
+        mod_id: u32,
+        game_version: Option<&str>,
+        loader: Option<ModLoader>,
+    ) -> Result<ProjectInfo> {
+        let mod_ = self.get_mods(mod_id).await?;
+        let mod_ = mod_.first().cloned().ok_or(CurseForgeError::NoModFound)?;
+        let loader = loader.filter(|loader| *loader != ModLoader::Any);
+        let file_index = mod_.latest_files_indexes.iter().find(|index| {
+            game_version.is_none_or(|game_version| index.game_version == game_version)
+                && loader
+                    .as_ref()
+                    .is_none_or(|loader| index.mod_loader == Some(loader.as_num() as u32))
+        });
+        let (version, changelog) = match file_index {
+            Some(index) => (
+                Some(index.filename.clone()),
+                self.get_file_changelog(mod_.id, index.file_id).await.ok(),
+            ),
+            None => (None, None),
+        };
+        let game_versions = mod_
+            .latest_files_indexes
+            .iter()
+            .map(|index| index.game_version.clone())
+            .unique()
+            .collect();
+        let loaders = mod_
+            .latest_files_indexes
+            .iter()
+            .filter_map(|index| loader_from_num(index.mod_loader?))
+            .unique()
+            .map(|loader| loader.to_string())
+            .collect();
+        let links = [
+            ("Page", &mod_.links.website_url),
+            ("Issues", &mod_.links.issues_url),
+            ("Source", &mod_.links.source_url),
+            ("Wiki", &mod_.links.wiki_url),
+        ]
+        .into_iter()
+        .filter_map(|(text, url)| Some(Link::new(text.to_string(), url.clone()?)))
+        .collect();
+        Ok(ProjectInfo {
+            source: Source::CurseForge,
+            id: mod_.id.to_string(),
+            title: mod_.name,
+            summary: mod_.summary,
+            authors: mod_.authors.into_iter().map(|author| author.name).collect(),
+            license: None,
+            client_side: None,
+            server_side: None,
+            game_versions,
+            loaders,
+            downloads: mod_.download_count as u64,
+            links,
+            version,
+            changelog,
+        })
+    }
+    /// Gets a file of a mod, with its dependencies.
+    pub async fn get_file(&self, mod_id: u32, file_id: u32) -> Result<File> {
+        let url = UrlBuilder::new(BASE_URL, "/mods")
+            .segment(mod_id)
+            .segment("files")
+            .segment(file_id);
+        let file = self
+            .client
+            .get(url.build()?)
+            .headers(self.headers()?)
+            .send()
+            .await?;
+        let file = file.error_for_status()?;
+        let body = file.text().await?;
+        let file: GetModFileResponse = serde_json::from_str(&body)?;
+        Ok(file.data)
+    }
     pub async fn get_dependencies(&self, mod_id: u32, version: &str) -> Result<Vec<Mod>> {
         let mod_ = self.get_mods(mod_id).await?;
         let mod_ = mod_.first().cloned().ok_or(CurseForgeError::NoModFound)?;
@@ -260,15 +499,12 @@ impl CurseForgeAPI {
             .find(|file| file.game_version == version)
             .cloned()
             .ok_or(CurseForgeError::NoGameVersionFound(version.to_string()))?;
-        let url = format!("{}/mods/{}/files/{}", BASE_URL, mod_id, file_index.file_id);
-        let file = self.client.get(url).headers(HEADERS.clone()).send().await?;
-        let file = file.error_for_status()?;
-        let body = file.text().await?;
-        let file: GetModFileResponse = serde_json::from_str(&body)?;
-        let file = file.data;
-
-        let dep_ids = file.dependencies;
-        let mut deps = Vec::with_capacity(dep_ids.len());
+        let file = self.get_file(mod_id, file_index.file_id).await?;
+        // Only the mods the file can't run without
+        let dep_ids = file.dependencies.into_iter().filter(|dep| {
+            DependencyKind::from_curseforge(dep.relation_type) == Some(DependencyKind::Required)
+        });
+        let mut deps = Vec::new();
         for dep in dep_ids {
             let mod_ = self.get_mods(dep.mod_id).await?;
             let mod_ = mod_.first().cloned().ok_or(CurseForgeError::NoModFound)?;
@@ -278,13 +514,98 @@ impl CurseForgeAPI {
     }
 }
 
+/// Looks up CurseForge files for [`crate::resolver::Resolver`].
+#[derive(Clone)]
+pub struct CurseForgeProvider {
+    pub api: CurseForgeAPI,
+    pub game_version: String,
+    pub loader: ModLoader,
+    pub content_type: ContentType,
+}
+
+impl CurseForgeProvider {
+    /// The newest file of `mod_` for the game version and, for mods, the loader.
+    fn latest_file(&self, mod_: &Mod) -> Option<u32> {
+        let loader = Some(self.loader.as_num() as u32).filter(|&loader| loader != 0);
+        mod_.latest_files_indexes
+            .iter()
+            .filter(|index| index.game_version == self.game_version)
+            .find(|index| {
+                !self.content_type.is_mod()
+                    || loader.is_none()
+                    || index
+                        .mod_loader
+                        .is_none_or(|mod_loader| Some(mod_loader) == loader)
+            })
+            .map(|index| index.file_id)
+    }
+}
+
+impl VersionProvider for CurseForgeProvider {
+    fn source(&self) -> Source {
+        Source::CurseForge
+    }
+
+    async fn find(&self, requirement: &Requirement) -> color_eyre::Result<Option<Candidate>> {
+        let Some(mod_id) = requirement
+            .project_id
+            .as_deref()
+            .and_then(|id| id.parse::<u32>().ok())
+        else {
+            return Ok(None);
+        };
+        let file_id = match requirement.version_id.as_deref() {
+            Some(file_id) => file_id.parse().ok(),
+            None => {
+                let mods = self.api.get_mods(mod_id).await?;
+                mods.first().and_then(|mod_| self.latest_file(mod_))
+            }
+        };
+        let Some(file_id) = file_id else {
+            return Ok(None);
+        };
+        let file = self.api.get_file(mod_id, file_id).await?;
+        let dependencies = file
+            .dependencies
+            .iter()
+            .filter(|_| self.content_type.is_mod())
+            .filter_map(|dependency| {
+                Some(Requirement {
+                    project_id: Some(dependency.mod_id.to_string()),
+                    version_id: None,
+                    kind: DependencyKind::from_curseforge(dependency.relation_type)?,
+                })
+            })
+            .collect();
+        Ok(Some(Candidate {
+            project_id: mod_id.to_string(),
+            version_id: file.id.to_string(),
+            file_name: file.file_name,
+            url: file.download_url,
+            dependencies,
+        }))
+    }
+
+    async fn download(&self, candidate: &Candidate, dir: &Path) -> color_eyre::Result<PathBuf> {
+        let path = self
+            .api
+            .download_mod(
+                candidate.project_id.parse()?,
+                candidate.version_id.parse()?,
+                dir.to_path_buf(),
+            )
+            .await?;
+        Ok(path)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
     #[tokio::test]
     async fn test_search_mods() {
-        let api = CurseForgeAPI::new(API_KEY.to_string());
+        let api = CurseForgeAPI::from_env();
         let loader = ModLoader::Fabric;
         let mods = api
             .search_mods("1.21.4", loader, "Carpet", 10)
@@ -302,20 +623,21 @@ mod tests {
                 .create_panic_handler()(panic_info);
         }));
 
-        let api = CurseForgeAPI::new(API_KEY.to_string());
+        let api = CurseForgeAPI::from_env();
         let mods = api
             .get_mods(&[349239u32, 349240u32] as &[u32])
             .await
             .unwrap();
         assert_eq!(!mods.is_empty(), true);
     }
+    #[cfg(feature = "prompts")]
     #[tokio::test]
     async fn full_test() -> Result<()> {
         color_eyre::install()?;
         let search = "carpet";
         let loader = ModLoader::Fabric;
         let v = "1.21.4";
-        let api = CurseForgeAPI::new(API_KEY.to_string());
+        let api = CurseForgeAPI::from_env();
         let mods = api.search_mods(v, loader, search, 10).await.unwrap();
         println!("{:#?}", mods);
         let prompt = inquire::MultiSelect::new("Select mods", mods);
@@ -336,7 +658,7 @@ mod tests {
                 .verbosity(better_panic::Verbosity::Full)
                 .create_panic_handler()(panic_info);
         }));
-        let api = CurseForgeAPI::new(API_KEY.to_string());
+        let api = CurseForgeAPI::from_env();
         // 447673 --> Sodium Extra
         let deps = api.get_dependencies(447673, "1.21.4").await.unwrap();
         assert_eq!(!deps.is_empty(), true);
@@ -344,7 +666,7 @@ mod tests {
     #[tokio::test]
     async fn test_fingerprint_specific_jar() {
         color_eyre::install().unwrap();
-        let api = CurseForgeAPI::new(API_KEY.to_string());
+        let api = CurseForgeAPI::from_env();
         let jar_path = PathBuf::from(
             "/Users/jayansunil/Dev/rust/modder/tui/test/createaddition-1.19.2-1.2.3.jar",
         );
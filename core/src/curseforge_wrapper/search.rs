@@ -0,0 +1,250 @@
+use super::{AsNum, BASE_URL, BUKKIT_PLUGINS_CLASS_ID, GAME_ID};
+use crate::content::ContentType;
+#[cfg(feature = "modrinth")]
+use crate::modrinth_wrapper::search::SearchIndex;
+use crate::{ModLoader, UrlBuilder};
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// The largest `index + pageSize` CurseForge accepts for a search.
+pub const MAX_SEARCH_RESULTS: u32 = 10_000;
+
+/// The fields CurseForge can sort search results by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum SortField {
+    Featured,
+    Popularity,
+    LastUpdated,
+    Name,
+    Author,
+    #[default]
+    TotalDownloads,
+    Category,
+    GameVersion,
+    EarlyAccess,
+    FeaturedReleased,
+    ReleasedDate,
+    Rating,
+}
+
+impl AsNum for SortField {
+    fn as_num(&self) -> u8 {
+        match self {
+            SortField::Featured => 1,
+            SortField::Popularity => 2,
+            SortField::LastUpdated => 3,
+            SortField::Name => 4,
+            SortField::Author => 5,
+            SortField::TotalDownloads => 6,
+            SortField::Category => 7,
+            SortField::GameVersion => 8,
+            SortField::EarlyAccess => 9,
+            SortField::FeaturedReleased => 10,
+            SortField::ReleasedDate => 11,
+            SortField::Rating => 12,
+        }
+    }
+}
+
+#[cfg(feature = "modrinth")]
+impl From<SearchIndex> for SortField {
+    fn from(index: SearchIndex) -> Self {
+        match index {
+            SearchIndex::Relevance => SortField::Popularity,
+            SearchIndex::Downloads => SortField::TotalDownloads,
+            SearchIndex::Follows => SortField::Rating,
+            SearchIndex::Newest => SortField::ReleasedDate,
+            SearchIndex::Updated => SortField::LastUpdated,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// The filters, ordering and page of a CurseForge mod search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchOptions {
+    search: String,
+    game_version: Option<String>,
+    loader: Option<ModLoader>,
+    category_id: Option<u32>,
+    class_id: Option<u32>,
+    author_id: Option<u32>,
+    sort_field: SortField,
+    sort_order: SortOrder,
+    index: u32,
+    page_size: u32,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            search: String::new(),
+            game_version: None,
+            loader: None,
+            category_id: None,
+            class_id: None,
+            author_id: None,
+            sort_field: SortField::default(),
+            sort_order: SortOrder::default(),
+            index: 0,
+            page_size: 50,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// A search for mods matching `search`, most downloaded first.
+    pub fn new(search: &str) -> Self {
+        Self {
+            search: search.to_string(),
+            ..Default::default()
+        }
+    }
+    pub fn game_version(mut self, game_version: &str) -> Self {
+        self.game_version = Some(game_version.to_string()).filter(|version| !version.is_empty());
+        self
+    }
+    pub fn loader(mut self, loader: ModLoader) -> Self {
+        self.loader = Some(loader).filter(|loader| *loader != ModLoader::Any);
+        self
+    }
+    pub fn category_id(mut self, category_id: u32) -> Self {
+        self.category_id = Some(category_id);
+        self
+    }
+    /// Searches within a class, e.g. `6` for mods or `12` for resource packs.
+    pub fn class_id(mut self, class_id: u32) -> Self {
+        self.class_id = Some(class_id);
+        self
+    }
+    /// Searches the class of `content_type`. Packs have no loader, so any set one is dropped.
+    pub fn content_type(mut self, content_type: ContentType) -> Self {
+        self.class_id = Some(content_type.curseforge_class_id());
+        if !content_type.is_mod() {
+            self.loader = None;
+        }
+        self
+    }
+    pub fn author_id(mut self, author_id: u32) -> Self {
+        self.author_id = Some(author_id);
+        self
+    }
+    pub fn sort_field(mut self, sort_field: SortField) -> Self {
+        self.sort_field = sort_field;
+        self
+    }
+    pub fn sort_order(mut self, sort_order: SortOrder) -> Self {
+        self.sort_order = sort_order;
+        self
+    }
+    /// The number of results per page, at most 50.
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size.clamp(1, 50);
+        self
+    }
+    /// Skips the first `index` results.
+    pub fn index(mut self, index: u32) -> Self {
+        self.index = index;
+        self
+    }
+    /// Shows the 0-based `page` of results, `page_size` results per page.
+    pub fn page(self, page: u32) -> Self {
+        let index = page.saturating_mul(self.page_size);
+        self.index(index)
+    }
+    pub fn get_page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    pub fn to_url(&self) -> UrlBuilder {
+        // CurseForge rejects searches reaching past its result limit
+        let page_size = self
+            .page_size
+            .min(MAX_SEARCH_RESULTS.saturating_sub(self.index))
+            .max(1);
+        let mut url = UrlBuilder::new(BASE_URL, "/mods/search")
+            .param("gameId", GAME_ID)
+            .param("index", self.index)
+            .param("searchFilter", &self.search)
+            .param("pageSize", page_size)
+            .param("sortField", self.sort_field.as_num())
+            .param("sortOrder", self.sort_order);
+        if let Some(game_version) = &self.game_version {
+            url = url.param("gameVersion", game_version);
+        }
+        if let Some(loader) = self
+            .loader
+            .as_ref()
+            .filter(|loader| !loader.is_plugin_loader())
+        {
+            url = url.param("gameFlavors[0]", loader.as_num());
+        }
+        if let Some(category_id) = self.category_id {
+            url = url.param("categoryId", category_id);
+        }
+        let plugins = self
+            .loader
+            .as_ref()
+            .is_some_and(ModLoader::is_plugin_loader);
+        if let Some(class_id) = self.class_id.or(plugins.then_some(BUKKIT_PLUGINS_CLASS_ID)) {
+            url = url.param("classId", class_id);
+        }
+        if let Some(author_id) = self.author_id {
+            url = url.param("authorId", author_id);
+        }
+        url
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_search_options_url() {
+        let url = SearchOptions::new("jei")
+            .game_version("1.21.1")
+            .loader(ModLoader::NeoForge)
+            .class_id(6)
+            .sort_field(SortField::LastUpdated)
+            .sort_order(SortOrder::Asc)
+            .page_size(20)
+            .page(2)
+            .to_url();
+        assert_eq!(
+            url.to_string(),
+            "https://api.curseforge.com/v1/mods/search?gameId=432&index=40&searchFilter=jei\
+             &pageSize=20&sortField=3&sortOrder=asc&gameVersion=1.21.1&gameFlavors%5B0%5D=6&classId=6"
+        );
+        // Plugins have no loader on CurseForge, only a class
+        let url = SearchOptions::new("luckperms")
+            .loader(ModLoader::Paper)
+            .to_url();
+        assert_eq!(
+            url.to_string(),
+            "https://api.curseforge.com/v1/mods/search?gameId=432&index=0&searchFilter=luckperms\
+             &pageSize=50&sortField=6&sortOrder=desc&classId=5"
+        );
+        let url = SearchOptions::new("faithful")
+            .game_version("1.21")
+            .loader(ModLoader::Fabric)
+            .content_type(ContentType::ResourcePack)
+            .to_url();
+        assert_eq!(
+            url.to_string(),
+            "https://api.curseforge.com/v1/mods/search?gameId=432&index=0&searchFilter=faithful\
+             &pageSize=50&sortField=6&sortOrder=desc&gameVersion=1.21&classId=12"
+        );
+    }
+}
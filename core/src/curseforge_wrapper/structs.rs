@@ -1,12 +1,21 @@
+use super::MAX_SEARCH_RESULTS;
 use crate::ModLoader;
 use serde::Deserialize;
 use std::fmt::Display;
+use strum::IntoEnumIterator;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum CurseForgeError {
     #[error("Invalid response from CurseForge")]
     InvalidResponse,
+    #[error(
+        "No CurseForge API key found. Set CURSEFORGE_API_KEY, `api_key` in the [curseforge] \
+         section of the config file or `curseforge_api_key` in the credentials file"
+    )]
+    NoApiKey,
+    #[error("The CurseForge API key is not a valid header value")]
+    InvalidApiKey,
     #[error("JSON Parsing error: {0}")]
     JsonParsingError(#[from] serde_json::Error),
     #[error("HTTP Error: {0}")]
@@ -199,7 +208,7 @@ pub struct FileIndex {
     pub mod_loader: Option<u32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Pagination {
     pub index: u32,
@@ -208,6 +217,14 @@ pub struct Pagination {
     pub total_count: u32,
 }
 
+impl Pagination {
+    /// Whether there are results after this page that CurseForge is willing to return.
+    pub fn has_more(&self) -> bool {
+        let next = self.index + self.result_count;
+        self.result_count > 0 && next < self.total_count && next < MAX_SEARCH_RESULTS
+    }
+}
+
 pub struct ModBuilder {
     pub game_version: String,
     pub search: String,
@@ -253,6 +270,11 @@ pub struct DownloadFile {
     pub data: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct StringResponse {
+    pub data: String,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct GetModFileResponse {
     pub data: File,
@@ -283,11 +305,21 @@ impl AsNum for ModLoader {
             ModLoader::Fabric => 4,
             ModLoader::Quilt => 5,
             ModLoader::NeoForge => 6,
-            ModLoader::Any => 0,
+            // CurseForge files plugins under the Bukkit Plugins class instead of a loader
+            ModLoader::Paper
+            | ModLoader::Spigot
+            | ModLoader::Bukkit
+            | ModLoader::Velocity
+            | ModLoader::Any => 0,
         }
     }
 }
 
+/// The inverse of [`AsNum::as_num`] for the mod loader ids CurseForge returns.
+pub fn loader_from_num(num: u32) -> Option<ModLoader> {
+    ModLoader::iter().find(|loader| loader.as_num() != 0 && loader.as_num() as u32 == num)
+}
+
 impl Display for Mod {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.name)
@@ -0,0 +1,212 @@
+use crate::cli::Source;
+use crate::content::ContentType;
+use crate::metadata::{DirMetadata, Origin};
+use crate::{calc_sha512, record_install, safe_file_name};
+use percent_encoding::percent_decode_str;
+use reqwest::StatusCode;
+use reqwest::header::{
+    CONTENT_DISPOSITION, ETAG, HeaderMap, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Error sending the request: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Error reading or writing the mod: {0}")]
+    IOErr(#[from] std::io::Error),
+    #[error("Error recording where the mod came from: {0}")]
+    Metadata(#[from] crate::metadata::Error),
+    #[error("{0} is not a jar")]
+    NotAJar(String),
+    #[error("{0} is not a zip, which {1}s come as")]
+    NotAZip(String, ContentType),
+    #[error("Could not tell the file name of {0}")]
+    NoFileName(String),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Downloads the jar, or the pack if `dir` is a pack folder, at `url` into `dir`, recording the
+/// url so `update` can check it for a newer file if `check_updates` is set.
+pub async fn add_from_url(url: &str, dir: &Path, check_updates: bool) -> Result<PathBuf> {
+    let response = reqwest::get(url).await?.error_for_status()?;
+    let path = save_response(url, response, dir, check_updates).await?;
+    record_install(&path, Source::Url, url, None);
+    Ok(path)
+}
+
+/// Copies the jar, or the pack if `dir` is a pack folder, at `file` into `dir`, recording where
+/// it came from.
+pub fn add_from_file(file: &Path, dir: &Path) -> Result<PathBuf> {
+    let file = file.canonicalize()?;
+    let path = copy_file(&file, dir)?;
+    record_install(&path, Source::Local, &file.to_string_lossy(), None);
+    Ok(path)
+}
+
+/// Checks the origin of the mod at `path` for a newer file, downloading or copying it next to
+/// the old one. Returns `None` if the mod is up to date or its origin isn't checked for updates.
+pub async fn update(path: &Path, origin: &Origin) -> Result<Option<PathBuf>> {
+    let dir = path.parent().unwrap_or(Path::new("./"));
+    if !origin.location.starts_with("http://") && !origin.location.starts_with("https://") {
+        return update_file(Path::new(&origin.location), dir, origin);
+    }
+    if !origin.check_updates {
+        return Ok(None);
+    }
+    let mut request = reqwest::Client::new().get(&origin.location);
+    if let Some(etag) = &origin.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &origin.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+    let response = request.send().await?.error_for_status()?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    let path = save_response(&origin.location, response, dir, true).await?;
+    if calc_sha512(&path.to_string_lossy()) == origin.sha512 {
+        return Ok(None);
+    }
+    info!("{} changed since it was added", origin.location);
+    Ok(Some(path))
+}
+
+/// Copies the local origin of a mod again if it changed since it was added.
+fn update_file(file: &Path, dir: &Path, origin: &Origin) -> Result<Option<PathBuf>> {
+    if !file.is_file() || calc_sha512(&file.to_string_lossy()) == origin.sha512 {
+        return Ok(None);
+    }
+    info!("{} changed since it was added", file.display());
+    copy_file(file, dir).map(Some)
+}
+
+fn copy_file(file: &Path, dir: &Path) -> Result<PathBuf> {
+    let location = file.to_string_lossy().to_string();
+    let file_name = file
+        .file_name()
+        .ok_or_else(|| Error::NoFileName(location.clone()))?
+        .to_string_lossy()
+        .to_string();
+    let contents = fs::read(file)?;
+    check_archive(&file_name, &contents, dir)?;
+    let path = dir.join(&file_name);
+    if path != file {
+        fs::write(&path, &contents)?;
+    }
+    let origin = Origin {
+        location,
+        sha512: calc_sha512(&path.to_string_lossy()),
+        ..Default::default()
+    };
+    DirMetadata::modify(dir, |metadata| metadata.set_origin(&file_name, origin))?;
+    Ok(path)
+}
+
+async fn save_response(
+    url: &str,
+    response: reqwest::Response,
+    dir: &Path,
+    check_updates: bool,
+) -> Result<PathBuf> {
+    let headers = response.headers().clone();
+    let file_name = file_name(url, &headers).ok_or_else(|| Error::NoFileName(url.to_string()))?;
+    let contents = response.bytes().await?;
+    check_archive(&file_name, &contents, dir)?;
+    let path = dir.join(&file_name);
+    fs::write(&path, &contents)?;
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    };
+    let origin = Origin {
+        location: url.to_string(),
+        sha512: calc_sha512(&path.to_string_lossy()),
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+        check_updates,
+    };
+    DirMetadata::modify(dir, |metadata| metadata.set_origin(&file_name, origin))?;
+    Ok(path)
+}
+
+/// Checks that a file is what `dir` holds: mods are jars, packs are zips.
+fn check_archive(file_name: &str, contents: &[u8], dir: &Path) -> Result<()> {
+    let content_type = ContentType::from_dir(dir);
+    if content_type.matches_file(file_name) && zip::ZipArchive::new(Cursor::new(contents)).is_ok() {
+        return Ok(());
+    }
+    Err(match content_type {
+        ContentType::Mod => Error::NotAJar(file_name.to_string()),
+        content_type => Error::NotAZip(file_name.to_string(), content_type),
+    })
+}
+
+/// The name to save a download as, from its `Content-Disposition` header or else the last
+/// segment of the url.
+fn file_name(url: &str, headers: &HeaderMap) -> Option<String> {
+    let from_header = headers
+        .get(CONTENT_DISPOSITION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value.split(';').map(str::trim).find_map(|param| {
+                if let Some(name) = param.strip_prefix("filename*=") {
+                    // e.g. filename*=UTF-8''my%20mod.jar
+                    let name = name.rsplit("''").next()?;
+                    return Some(percent_decode_str(name).decode_utf8_lossy().to_string());
+                }
+                let name = param.strip_prefix("filename=")?;
+                Some(name.trim_matches('"').to_string())
+            })
+        });
+    let name = from_header.or_else(|| {
+        let url = url::Url::parse(url).ok()?;
+        let segment = url.path_segments()?.next_back()?;
+        Some(percent_decode_str(segment).decode_utf8_lossy().to_string())
+    })?;
+    // Never let a server pick a path outside the mods directory
+    safe_file_name(&name).map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_file_name() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            file_name(
+                "https://ci.example.com/job/mod/lastBuild/mod%201.0.jar",
+                &headers
+            ),
+            Some("mod 1.0.jar".to_string())
+        );
+        headers.insert(
+            CONTENT_DISPOSITION,
+            r#"attachment; filename="../mod-2.0.jar""#.parse().unwrap(),
+        );
+        assert_eq!(
+            file_name("https://ci.example.com/download?id=1", &headers),
+            Some("mod-2.0.jar".to_string())
+        );
+        headers.insert(
+            CONTENT_DISPOSITION,
+            "attachment; filename*=UTF-8''mod%203.0.jar"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            file_name("https://ci.example.com/download?id=1", &headers),
+            Some("mod 3.0.jar".to_string())
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::ModLoader;
+use crate::check::{self, loaded};
+use crate::manifest::{ModManifest, VersionRange, compare_versions};
+use itertools::Itertools;
+use std::{cmp::Ordering, collections::BTreeMap, fmt::Display, path::Path};
+
+/// Dependencies on the game, a loader or Java rather than on a mod. The `java` dependency is
+/// checked by [`crate::java::JavaReport`] along with the class files of the jars.
+const PLATFORM_IDS: [&str; 8] = [
+    "minecraft",
+    "java",
+    "fabricloader",
+    "fabric-loader",
+    "quilt_loader",
+    "forge",
+    "neoforge",
+    "javafml",
+];
+
+/// A dependency a mod declares that isn't satisfied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// No jar in the directory contains the dependency.
+    Missing {
+        file: String,
+        dependency: String,
+        range: VersionRange,
+    },
+    /// The dependency is there, but at a version outside the range.
+    OutOfRange {
+        file: String,
+        dependency: String,
+        range: VersionRange,
+        found: String,
+    },
+    /// The mod doesn't support the game version.
+    GameVersion {
+        file: String,
+        range: VersionRange,
+        game_version: String,
+    },
+}
+
+impl Problem {
+    /// The mod id of the dependency to install, if it is missing.
+    pub fn missing(&self) -> Option<&str> {
+        match self {
+            Problem::Missing { dependency, .. } => Some(dependency),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Missing {
+                file,
+                dependency,
+                range,
+            } if range.is_any() => write!(f, "{} requires {}, which is missing", file, dependency),
+            Problem::Missing {
+                file,
+                dependency,
+                range,
+            } => write!(
+                f,
+                "{} requires {} {}, which is missing",
+                file, dependency, range
+            ),
+            Problem::OutOfRange {
+                file,
+                dependency,
+                range,
+                found,
+            } => write!(
+                f,
+                "{} requires {} {}, but {} is installed",
+                file, dependency, range, found
+            ),
+            Problem::GameVersion {
+                file,
+                range,
+                game_version,
+            } => write!(
+                f,
+                "{} requires Minecraft {}, not {}",
+                file, range, game_version
+            ),
+        }
+    }
+}
+
+/// Reads the manifests of every jar in `dir`, including those of the jars nested in them.
+pub fn read_dir(dir: &Path) -> Result<Vec<(String, Vec<ModManifest>)>, check::Error> {
+    let jars = check::jar_files(dir)?
+        .into_iter()
+        .map(|path| {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            let manifests = ModManifest::read_jar_nested(&path).unwrap_or_default();
+            (file_name.to_string(), manifests)
+        })
+        .collect();
+    Ok(jars)
+}
+
+/// Checks the declared dependencies of every mod in `jars` against the other mods and, if given,
+/// the game version. With a `loader`, only the manifests it reads are looked at.
+pub fn diagnose(
+    jars: &[(String, Vec<ModManifest>)],
+    game_version: Option<&str>,
+    loader: Option<&ModLoader>,
+) -> Vec<Problem> {
+    // The newest version of each mod id that is present
+    let mut present: BTreeMap<&str, &str> = BTreeMap::new();
+    for (_, manifests) in jars {
+        for manifest in loaded(manifests, loader) {
+            for id in std::iter::once(&manifest.id).chain(&manifest.provides) {
+                let version = present.entry(id).or_insert(&manifest.version);
+                if compare_versions(&manifest.version, version) == Ordering::Greater {
+                    *version = &manifest.version;
+                }
+            }
+        }
+    }
+
+    let mut problems = Vec::new();
+    for (file, manifests) in jars {
+        for manifest in loaded(manifests, loader) {
+            for (dependency, range) in &manifest.depends {
+                let file = file.clone();
+                let problem = match (dependency.as_str(), game_version) {
+                    ("minecraft", Some(game_version)) if !range.matches(game_version) => {
+                        Problem::GameVersion {
+                            file,
+                            range: range.clone(),
+                            game_version: game_version.to_string(),
+                        }
+                    }
+                    (id, _) if PLATFORM_IDS.contains(&id) => continue,
+                    (id, _) => match present.get(id) {
+                        None => Problem::Missing {
+                            file,
+                            dependency: dependency.clone(),
+                            range: range.clone(),
+                        },
+                        Some(found) if !range.matches(found) => Problem::OutOfRange {
+                            file,
+                            dependency: dependency.clone(),
+                            range: range.clone(),
+                            found: found.to_string(),
+                        },
+                        Some(_) => continue,
+                    },
+                };
+                if !problems.contains(&problem) {
+                    problems.push(problem);
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// The mod ids of the missing dependencies, once each.
+pub fn missing(problems: &[Problem]) -> Vec<String> {
+    problems
+        .iter()
+        .filter_map(Problem::missing)
+        .unique()
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_diagnose() {
+        let fabric = |contents: &str| vec![ModManifest::parse_fabric(contents).unwrap()];
+        let jars = vec![
+            (
+                "sodium.jar".to_string(),
+                fabric(
+                    r#"{"id": "sodium", "version": "0.6.0",
+                        "depends": {"minecraft": "~1.21", "fabricloader": ">=0.16",
+                                    "fabric-api": ">=0.90"}}"#,
+                ),
+            ),
+            (
+                "iris.jar".to_string(),
+                fabric(
+                    r#"{"id": "iris", "version": "1.8.0",
+                        "depends": {"sodium": "0.6.x", "fabric-resource-loader-v0": "*",
+                                    "indium": "*"}}"#,
+                ),
+            ),
+            (
+                "fabric-api.jar".to_string(),
+                [
+                    fabric(r#"{"id": "fabric-api", "version": "0.80.0", "provides": ["fabric"]}"#),
+                    fabric(r#"{"id": "fabric-resource-loader-v0", "version": "1.0.0"}"#),
+                ]
+                .concat(),
+            ),
+        ];
+        let problems = diagnose(&jars, Some("1.20.1"), Some(&ModLoader::Fabric));
+        assert_eq!(
+            problems.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "sodium.jar requires fabric-api >=0.90, but 0.80.0 is installed",
+                "sodium.jar requires Minecraft ~1.21, not 1.20.1",
+                "iris.jar requires indium, which is missing",
+            ]
+        );
+        assert_eq!(missing(&problems), ["indium"]);
+        assert!(diagnose(&jars, None, Some(&ModLoader::Forge)).is_empty());
+    }
+}
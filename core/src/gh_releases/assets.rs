@@ -0,0 +1,323 @@
+#[cfg(feature = "github")]
+use super::structs::{Release, ReleaseAsset};
+use crate::ModLoader;
+use regex::Regex;
+use std::fmt::Display;
+
+/// Jars published next to the mod that are never the mod itself.
+const EXCLUDED_SUFFIXES: [&str; 3] = ["-sources", "-dev", "-javadoc"];
+/// The most rejections listed when no asset matches.
+const MAX_EXPLAINED: usize = 10;
+
+/// A user-supplied pattern that asset names have to match. Patterns starting with `regex:` are
+/// regular expressions, anything else is a glob where `*` and `?` are wildcards.
+#[derive(Debug, Clone)]
+pub struct AssetPattern {
+    source: String,
+    regex: Regex,
+}
+
+impl AssetPattern {
+    pub fn parse(pattern: &str) -> Result<Self, regex::Error> {
+        let regex = match pattern.strip_prefix("regex:") {
+            Some(regex) => Regex::new(regex)?,
+            None => {
+                let glob = pattern
+                    .split('*')
+                    .map(|part| {
+                        part.split('?')
+                            .map(regex::escape)
+                            .collect::<Vec<_>>()
+                            .join(".")
+                    })
+                    .collect::<Vec<_>>()
+                    .join(".*");
+                Regex::new(&format!("(?i)^{}$", glob))?
+            }
+        };
+        Ok(Self {
+            source: pattern.to_string(),
+            regex,
+        })
+    }
+    pub fn is_match(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+impl Display for AssetPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// An asset that was skipped, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub release: String,
+    pub asset: String,
+    pub reason: String,
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}): {}", self.asset, self.release, self.reason)
+    }
+}
+
+/// Picks the release asset for a game version and loader.
+#[derive(Debug, Clone)]
+pub struct AssetSelector {
+    pub game_version: String,
+    pub loader: ModLoader,
+    pub pattern: Option<AssetPattern>,
+}
+
+impl AssetSelector {
+    pub fn new(game_version: &str, loader: ModLoader) -> Self {
+        Self {
+            game_version: game_version.to_string(),
+            loader,
+            pattern: None,
+        }
+    }
+    pub fn pattern(mut self, pattern: Option<AssetPattern>) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    /// Scores an asset, higher being a better match, or explains why it doesn't match.
+    pub fn check(&self, name: &str) -> Result<u8, String> {
+        let lower = name.to_lowercase();
+        let Some(stem) = lower.strip_suffix(".jar") else {
+            return Err("not a jar".to_string());
+        };
+        if let Some(suffix) = EXCLUDED_SUFFIXES
+            .iter()
+            .find(|suffix| stem.ends_with(*suffix))
+        {
+            return Err(format!("{} jar", suffix.trim_start_matches('-')));
+        }
+        if let Some(pattern) = &self.pattern
+            && !pattern.is_match(name)
+        {
+            return Err(format!("does not match the pattern `{}`", pattern));
+        }
+        if !contains_version(&lower, &self.game_version) {
+            return Err(format!("not for {}", self.game_version));
+        }
+        let loaders = loaders_in_name(&lower);
+        if self.loader == ModLoader::Any || loaders.contains(&self.loader) {
+            return Ok(2);
+        }
+        if loaders.is_empty() {
+            // Projects that only target one loader often don't name it
+            return Ok(1);
+        }
+        if loaders.iter().any(|loader| self.loader.loads(loader)) {
+            // e.g. Quilt loads Fabric mods
+            return Ok(0);
+        }
+        let loaders = loaders
+            .iter()
+            .map(|loader| loader.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        Err(format!("for {}, not {}", loaders, self.loader))
+    }
+
+    /// Whether any asset of `release` matches.
+    #[cfg(feature = "github")]
+    pub fn matches_release(&self, release: &Release) -> bool {
+        release
+            .assets
+            .iter()
+            .any(|asset| self.check(&asset.name).is_ok())
+    }
+
+    /// Picks the best asset of the newest release that has a matching one, collecting the
+    /// reasons every asset before it was skipped.
+    #[cfg(feature = "github")]
+    pub fn select<'a>(
+        &self,
+        releases: &'a [Release],
+    ) -> (Option<&'a ReleaseAsset>, Vec<Rejection>) {
+        let mut rejections = Vec::new();
+        for release in releases {
+            if let Some(asset) = self.best_asset(release, &mut rejections) {
+                return (Some(asset), rejections);
+            }
+        }
+        (None, rejections)
+    }
+
+    /// The best asset of every release that has a matching one, newest release first, paired
+    /// with the name of its release.
+    #[cfg(feature = "github")]
+    pub fn candidates<'a>(
+        &self,
+        releases: &'a [Release],
+    ) -> (Vec<(String, &'a ReleaseAsset)>, Vec<Rejection>) {
+        let mut rejections = Vec::new();
+        let candidates = releases
+            .iter()
+            .filter_map(|release| {
+                let asset = self.best_asset(release, &mut rejections)?;
+                Some((release_name(release), asset))
+            })
+            .collect();
+        (candidates, rejections)
+    }
+
+    #[cfg(feature = "github")]
+    fn best_asset<'a>(
+        &self,
+        release: &'a Release,
+        rejections: &mut Vec<Rejection>,
+    ) -> Option<&'a ReleaseAsset> {
+        let mut best: Option<(u8, &ReleaseAsset)> = None;
+        for asset in &release.assets {
+            match self.check(&asset.name) {
+                Ok(score) if best.is_none_or(|(best, _)| score > best) => {
+                    best = Some((score, asset))
+                }
+                Ok(_) => {}
+                Err(reason) => rejections.push(Rejection {
+                    release: release_name(release),
+                    asset: asset.name.clone(),
+                    reason,
+                }),
+            }
+        }
+        best.map(|(_, asset)| asset)
+    }
+}
+
+#[cfg(feature = "github")]
+fn release_name(release: &Release) -> String {
+    release
+        .name
+        .clone()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| release.tag_name.clone())
+}
+
+/// Formats the reasons assets were skipped, for error messages.
+pub fn explain_rejections(rejections: &[Rejection]) -> String {
+    if rejections.is_empty() {
+        return "the releases have no assets".to_string();
+    }
+    let mut lines = rejections
+        .iter()
+        .take(MAX_EXPLAINED)
+        .map(|rejection| format!("\t{}", rejection))
+        .collect::<Vec<_>>();
+    if rejections.len() > MAX_EXPLAINED {
+        lines.push(format!(
+            "\t...and {} more",
+            rejections.len() - MAX_EXPLAINED
+        ));
+    }
+    lines.join("\n")
+}
+
+/// The loader an asset name is for, if it names exactly one.
+pub fn loader_from_name(name: &str) -> Option<ModLoader> {
+    match &loaders_in_name(&name.to_lowercase())[..] {
+        [loader] => Some(loader.clone()),
+        _ => None,
+    }
+}
+
+/// The loaders an asset name mentions, going by the usual naming conventions.
+fn loaders_in_name(name: &str) -> Vec<ModLoader> {
+    let mut loaders = Vec::new();
+    for token in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let loader = match token {
+            "fabric" => ModLoader::Fabric,
+            "quilt" | "quiltmc" => ModLoader::Quilt,
+            "neoforge" | "neo" => ModLoader::NeoForge,
+            "forge" | "mcforge" => ModLoader::Forge,
+            "paper" | "paperspigot" => ModLoader::Paper,
+            "spigot" => ModLoader::Spigot,
+            "bukkit" => ModLoader::Bukkit,
+            "velocity" => ModLoader::Velocity,
+            _ => continue,
+        };
+        if !loaders.contains(&loader) {
+            loaders.push(loader);
+        }
+    }
+    loaders
+}
+
+/// Whether `name` contains `version` as a whole version, so that `1.21` doesn't match `1.21.4`.
+pub fn contains_version(name: &str, version: &str) -> bool {
+    name.match_indices(version).any(|(i, _)| {
+        let before = name[..i].chars().next_back();
+        let mut after = name[i + version.len()..].chars();
+        let before_ok = before.is_none_or(|c| !c.is_ascii_digit() && c != '.');
+        let after_ok = match after.next() {
+            Some(c) if c.is_ascii_digit() => false,
+            Some('.') => after.next().is_none_or(|c| !c.is_ascii_digit()),
+            _ => true,
+        };
+        before_ok && after_ok
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_check_asset() {
+        let selector = AssetSelector::new("1.21", ModLoader::NeoForge);
+        assert_eq!(selector.check("mymod-neoforge-1.21-2.0.jar"), Ok(2));
+        assert_eq!(selector.check("mymod-1.21-2.0.jar"), Ok(1));
+        assert_eq!(
+            selector.check("mymod-forge-1.21-2.0.jar"),
+            Err("for Forge, not NeoForge".to_string())
+        );
+        assert_eq!(
+            selector.check("mymod-neoforge-1.21-2.0-sources.jar"),
+            Err("sources jar".to_string())
+        );
+        assert_eq!(
+            selector.check("mymod-neoforge-1.21.4-2.0.jar"),
+            Err("not for 1.21".to_string())
+        );
+        assert_eq!(
+            selector.check("mymod-1.21.zip"),
+            Err("not a jar".to_string())
+        );
+        let quilt = AssetSelector::new("1.21", ModLoader::Quilt);
+        assert_eq!(quilt.check("mymod-fabric-1.21.jar"), Ok(0));
+        let paper = AssetSelector::new("1.21", ModLoader::Paper);
+        assert_eq!(paper.check("LuckPerms-Bukkit-1.21.jar"), Ok(0));
+        assert_eq!(
+            paper.check("LuckPerms-Velocity-1.21.jar"),
+            Err("for Velocity, not Paper".to_string())
+        );
+    }
+
+    #[test]
+    fn test_asset_pattern() {
+        let glob = AssetPattern::parse("sodium-fabric-*+mc1.21.?.jar").unwrap();
+        assert!(glob.is_match("sodium-fabric-0.6.0+mc1.21.4.jar"));
+        assert!(!glob.is_match("sodium-fabric-0.6.0+mc1.21.4-sources.jar"));
+        let regex = AssetPattern::parse(r"regex:^lithium-.*\.jar$").unwrap();
+        assert!(regex.is_match("lithium-fabric-0.14.jar"));
+        assert!(!regex.is_match("sodium.jar"));
+    }
+
+    #[test]
+    fn test_contains_version() {
+        assert!(contains_version("mod-1.21.jar", "1.21"));
+        assert!(contains_version("mod-mc1.21-1.0.jar", "1.21"));
+        assert!(!contains_version("mod-1.21.4.jar", "1.21"));
+        assert!(!contains_version("mod-11.21.jar", "1.21"));
+        assert!(contains_version("mod-1.21.4+build.jar", "1.21.4"));
+    }
+}
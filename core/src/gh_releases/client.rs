@@ -0,0 +1,599 @@
+use super::*;
+use crate::changelog::{self, ChangelogEntry, ModChangelog};
+use crate::cli::Source;
+use crate::config::{Config, Credentials};
+use crate::info::ProjectInfo;
+use crate::manifest::{self, ModManifest};
+use crate::{Link, UrlBuilder};
+use itertools::Itertools;
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use strum::IntoEnumIterator;
+use tracing::{debug, warn};
+
+/// The most release assets downloaded looking for one that fits.
+const MAX_DOWNLOADED_CANDIDATES: usize = 5;
+/// The most releases GitHub and GitLab return per page.
+const RELEASES_PER_PAGE: u8 = 100;
+/// The most releases Gitea returns per page, unless configured otherwise.
+const GITEA_RELEASES_PER_PAGE: u8 = 50;
+/// The most pages of releases fetched looking for a matching one.
+pub const MAX_RELEASE_PAGES: usize = 10;
+
+#[derive(Default, Clone)]
+pub struct GHReleasesAPI {
+    pub client: reqwest::Client,
+    pub token: Option<Box<str>>,
+    /// Whether prereleases are returned along with full releases. Drafts never are.
+    pub prereleases: bool,
+    /// The forge the releases are fetched from, GitHub by default.
+    pub forge: Forge,
+    /// The host of the forge, `None` for github.com.
+    pub host: Option<String>,
+}
+
+impl GHReleasesAPI {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            token: None,
+            prereleases: false,
+            forge: Forge::Github,
+            host: None,
+        }
+    }
+    /// Creates a client authenticated with `token`, falling back to `GITHUB_TOKEN`, `GH_TOKEN`,
+    /// the token in the config file and the one in the credentials file, and honoring the
+    /// config's prerelease setting and channel.
+    pub fn from_env(token: Option<String>) -> Self {
+        let config = Config::load().unwrap_or_else(|err| {
+            warn!("Ignoring the config file: {}", err);
+            Config::default()
+        });
+        let mut api = Self::new();
+        let token = token
+            .or_else(|| env::var("GITHUB_TOKEN").ok())
+            .or_else(|| env::var("GH_TOKEN").ok())
+            .or(config.github.token)
+            .or_else(|| {
+                Credentials::load()
+                    .inspect_err(|err| warn!("Ignoring the credentials file: {}", err))
+                    .ok()?
+                    .github_token
+            })
+            .filter(|token| !token.trim().is_empty());
+        if let Some(token) = token {
+            api.token(token);
+        }
+        let channel = config.defaults.channel.unwrap_or_default();
+        api.prereleases(config.github.prereleases || channel.prereleases());
+        api
+    }
+    /// A client for the releases of `forge` on `host`, keeping the prerelease setting. The token
+    /// is only kept for github.com, other hosts get theirs from the forge's environment variable
+    /// (e.g. `GITEA_TOKEN`) or the host's entry in the config file.
+    pub fn for_forge(&self, forge: Forge, host: Option<&str>) -> Self {
+        let host = host
+            .map(str::to_lowercase)
+            .filter(|host| host != GITHUB_HOST);
+        let mut api = Self {
+            client: self.client.clone(),
+            token: None,
+            prereleases: self.prereleases,
+            forge,
+            host: host.clone(),
+        };
+        match host {
+            None if forge == Forge::Github => api.token = self.token.clone(),
+            _ => {
+                let config = Config::load().unwrap_or_default();
+                let token = env::var(forge.token_var(host.as_deref()))
+                    .ok()
+                    .or_else(|| host.and_then(|host| config.forges.get(&host)?.token.clone()));
+                if let Some(token) = token.filter(|token| !token.trim().is_empty()) {
+                    api.token(token);
+                }
+            }
+        }
+        api
+    }
+    /// A client for the forge hosting `spec`, or `None` if the forge can't be told from its host.
+    pub fn for_repo(&self, spec: &RepoSpec, source: Option<&Source>) -> Option<Self> {
+        let forge = match source.and_then(Forge::from_source) {
+            Some(forge) => forge,
+            None => spec.detect_forge(&Config::load().unwrap_or_default())?,
+        };
+        Some(self.for_forge(forge, spec.host.as_deref()))
+    }
+    /// Authenticates with `token`, ignoring the whitespace around it, e.g. a trailing newline.
+    pub fn token(&mut self, token: String) {
+        self.token = Some(token.trim().into());
+    }
+    /// The API url of a repo.
+    fn repo_url(&self, owner: &str, repo: &str) -> UrlBuilder {
+        let base = self.forge.api_base(self.host.as_deref());
+        match self.forge {
+            Forge::Github | Forge::Gitea => UrlBuilder::new(&base, "/repos")
+                .segment(owner)
+                .segment(repo),
+            // GitLab identifies projects by their url-encoded path
+            Forge::Gitlab => {
+                UrlBuilder::new(&base, "/projects").segment(format!("{}/{}", owner, repo))
+            }
+        }
+    }
+    pub fn prereleases(&mut self, prereleases: bool) {
+        self.prereleases = prereleases;
+    }
+    /// Gets the newest page of releases.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_releases(&self, owner: &str, repo: &str) -> Result<Vec<structs::Release>> {
+        self.find_releases(owner, repo, |_| true).await
+    }
+    /// Gets releases newest first, following the pagination until one matches `is_match` or
+    /// [`MAX_RELEASE_PAGES`] pages were fetched. Returns every release fetched.
+    #[tracing::instrument(level = "info", skip(self, is_match))]
+    pub async fn find_releases(
+        &self,
+        owner: &str,
+        repo: &str,
+        is_match: impl Fn(&structs::Release) -> bool,
+    ) -> Result<Vec<structs::Release>> {
+        let mut next = Some(self.releases_url(owner, repo));
+        let mut releases = Vec::new();
+        for _ in 0..MAX_RELEASE_PAGES {
+            let Some(url) = next.take() else {
+                break;
+            };
+            let page;
+            (page, next) = self.releases_page(url).await?;
+            let found = page.iter().any(&is_match);
+            releases.extend(page);
+            if found {
+                break;
+            }
+        }
+        if releases.is_empty() {
+            return Err(Error::NoReleases);
+        }
+
+        Ok(releases)
+    }
+    /// Downloads the newest asset whose jar declares support for the game version and loader of
+    /// `selector` into `dir`, like [`download_mod_from_release`]. Pages of releases are fetched
+    /// until an asset is picked or [`MAX_RELEASE_PAGES`] pages were fetched, so older releases
+    /// are still tried when the newest matching ones turn out not to fit.
+    #[tracing::instrument(level = "info", skip(self, selector))]
+    pub async fn download_mod(
+        &self,
+        owner: &str,
+        repo: &str,
+        selector: &AssetSelector,
+        dir: &Path,
+        source: &Source,
+    ) -> Result<PathBuf> {
+        let spec = RepoSpec {
+            host: self.host.clone(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+        }
+        .to_string();
+        let temp_dir = tempfile::tempdir()?;
+        let mut downloads = 0;
+        let mut rejections = Vec::new();
+        let mut any_releases = false;
+        let mut next = Some(self.releases_url(owner, repo));
+        for _ in 0..MAX_RELEASE_PAGES {
+            let Some(url) = next.take() else {
+                break;
+            };
+            let page;
+            (page, next) = self.releases_page(url).await?;
+            any_releases |= !page.is_empty();
+            let picked = pick_candidate(
+                &page,
+                selector,
+                temp_dir.path(),
+                dir,
+                source,
+                &spec,
+                &mut downloads,
+                &mut rejections,
+            )
+            .await?;
+            if let Some(path) = picked {
+                return Ok(path);
+            }
+            if downloads >= MAX_DOWNLOADED_CANDIDATES {
+                break;
+            }
+        }
+        if !any_releases {
+            return Err(Error::NoReleases);
+        }
+        Err(Error::NoMatchingAsset(explain_rejections(&rejections)))
+    }
+    /// The url of the first page of releases.
+    fn releases_url(&self, owner: &str, repo: &str) -> String {
+        let url = self.repo_url(owner, repo).segment("releases");
+        let url = match self.forge {
+            Forge::Gitea => url.param("limit", GITEA_RELEASES_PER_PAGE),
+            Forge::Github | Forge::Gitlab => url.param("per_page", RELEASES_PER_PAGE),
+        };
+        url.to_string()
+    }
+    /// Gets a page of releases, leaving out drafts and unwanted prereleases, with the url of the
+    /// next page.
+    async fn releases_page(&self, url: String) -> Result<(Vec<structs::Release>, Option<String>)> {
+        let response = self.get(url).await?;
+        let next = next_page(response.headers());
+        let text = response.text().await?;
+        let page: Vec<structs::Release> = match self.forge {
+            Forge::Github | Forge::Gitea => serde_json::from_str(&text)?,
+            Forge::Gitlab => serde_json::from_str::<Vec<structs::GitlabRelease>>(&text)?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        };
+        let page = page
+            .into_iter()
+            .filter(|release| !release.draft && (self.prereleases || !release.prerelease))
+            .collect_vec();
+        Ok((page, next))
+    }
+    /// Gets the details of a repo, with the changelog of the newest release that has an asset for
+    /// `game_version`.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_project_info(
+        &self,
+        owner: &str,
+        repo: &str,
+        game_version: Option<&str>,
+    ) -> Result<ProjectInfo> {
+        let mut url = self.repo_url(owner, repo);
+        if self.forge == Forge::Gitlab {
+            url = url.param("license", true);
+        }
+        let response = self.get(url).await?;
+        let text = response.text().await?;
+        let repository: structs::Repository = match self.forge {
+            Forge::Github | Forge::Gitea => serde_json::from_str(&text)?,
+            Forge::Gitlab => serde_json::from_str::<structs::GitlabProject>(&text)?.into(),
+        };
+        let has_game_version = |release: &structs::Release| {
+            game_version.is_none_or(|game_version| {
+                release
+                    .assets
+                    .iter()
+                    .any(|asset| asset.name.contains(game_version))
+            })
+        };
+        let releases = self
+            .find_releases(owner, repo, has_game_version)
+            .await
+            .unwrap_or_default();
+        let release = releases
+            .iter()
+            .find(|release| has_game_version(release))
+            .or(releases.first());
+        let asset_names = releases
+            .iter()
+            .flat_map(|release| release.assets.iter().map(|asset| asset.name.to_lowercase()))
+            .collect_vec();
+        let loaders = crate::ModLoader::iter()
+            .filter(|loader| *loader != crate::ModLoader::Any)
+            .map(|loader| loader.to_string())
+            .filter(|loader| {
+                asset_names
+                    .iter()
+                    .any(|name| name.contains(&loader.to_lowercase()))
+            })
+            .collect();
+        let mut links = vec![Link::new(
+            "Page".to_string(),
+            repository.html_url.to_string(),
+        )];
+        if let Some(homepage) = repository.homepage.filter(|homepage| !homepage.is_empty()) {
+            links.push(Link::new("Homepage".to_string(), homepage));
+        }
+        Ok(ProjectInfo {
+            source: self.forge.source(),
+            id: RepoSpec {
+                host: self.host.clone(),
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+            }
+            .to_string(),
+            title: repository.name,
+            summary: repository.description.unwrap_or_default(),
+            authors: vec![repository.owner.login],
+            license: repository.license.map(|license| license.name),
+            client_side: None,
+            server_side: None,
+            game_versions: Vec::new(),
+            loaders,
+            downloads: releases
+                .iter()
+                .flat_map(|release| &release.assets)
+                .map(|asset| asset.download_count as u64)
+                .sum(),
+            links,
+            version: release.map(|release| {
+                release
+                    .name
+                    .clone()
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| release.tag_name.clone())
+            }),
+            changelog: release
+                .and_then(|release| release.body.clone())
+                .filter(|body| !body.trim().is_empty()),
+        })
+    }
+    /// Collects the bodies of the releases between the one containing `old_asset` and the one
+    /// containing `new_asset`.
+    pub async fn changelog_between(
+        &self,
+        owner: &str,
+        repo: &str,
+        old_asset: &str,
+        new_asset: &str,
+    ) -> Result<ModChangelog> {
+        let has_asset = |release: &structs::Release, name: &str| {
+            release.assets.iter().any(|asset| asset.name == name)
+        };
+        let releases = self
+            .find_releases(owner, repo, |release| has_asset(release, old_asset))
+            .await?;
+        let release_name = |release: &structs::Release| {
+            release
+                .name
+                .clone()
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| release.tag_name.clone())
+        };
+        let name_of = |asset: &str| {
+            releases
+                .iter()
+                .find(|release| has_asset(release, asset))
+                .map(release_name)
+                .unwrap_or_else(|| asset.to_string())
+        };
+        let entries = changelog::between(
+            &releases,
+            |release| has_asset(release, old_asset),
+            |release| has_asset(release, new_asset),
+        )
+        .iter()
+        .map(|release| ChangelogEntry {
+            version: release_name(release),
+            changelog: release.body.clone().unwrap_or_default(),
+        })
+        .collect();
+        Ok(ModChangelog {
+            name: repo.to_string(),
+            source: self.forge.source(),
+            from: name_of(old_asset),
+            to: name_of(new_asset),
+            entries,
+        })
+    }
+    async fn get(&self, url: impl Display) -> Result<reqwest::Response> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        let response = self.client.get(url.to_string());
+        if let Some(token) = self.token.as_ref() {
+            let (name, value) = match self.forge {
+                Forge::Github => (reqwest::header::AUTHORIZATION, format!("Bearer {}", token)),
+                Forge::Gitea => (reqwest::header::AUTHORIZATION, format!("token {}", token)),
+                Forge::Gitlab => (
+                    reqwest::header::HeaderName::from_static("private-token"),
+                    token.to_string(),
+                ),
+            };
+            let value = reqwest::header::HeaderValue::from_str(&value).map_err(|_| {
+                Error::AuthFailed("the token isn't a valid header value".to_string())
+            })?;
+            headers.insert(name, value);
+        }
+        headers.insert(
+            reqwest::header::USER_AGENT,
+            reqwest::header::HeaderValue::from_static("modder-rs"),
+        );
+        let response = response.headers(headers).send().await?;
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        let remaining = header("x-ratelimit-remaining");
+        debug!(remaining = ?remaining, "GitHub rate limit");
+        let status = response.status().as_u16();
+        if (status == 403 || status == 429) && remaining.as_deref() == Some("0") {
+            let reset = header("x-ratelimit-reset")
+                .and_then(|reset| reset.parse::<i64>().ok())
+                .and_then(|reset| chrono::DateTime::from_timestamp(reset, 0))
+                .map(|reset| {
+                    reset
+                        .with_timezone(&chrono::Local)
+                        .format("%H:%M:%S")
+                        .to_string()
+                })
+                .unwrap_or_else(|| "an unknown time".to_string());
+            return Err(Error::RateLimited {
+                limit: header("x-ratelimit-limit").unwrap_or_else(|| "?".to_string()),
+                reset,
+            });
+        }
+        match response.error_for_status() {
+            Ok(response) => Ok(response),
+            Err(e) => {
+                let code = e.status().unwrap().as_u16();
+                if code == 401 || code == 403 {
+                    Err(Error::AuthFailed(e.to_string()))
+                } else {
+                    Err(Error::Reqwest(e))
+                }
+            }
+        }
+    }
+}
+
+/// The url of the next page from a `Link` header, like
+/// `<https://api.github.com/...&page=2>; rel="next", <...>; rel="last"`.
+fn next_page(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|link| {
+        let (url, params) = link.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == r#"rel="next""#)
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+/// Picks the asset to download from `releases`, newest release first.
+pub async fn get_mod_from_release(
+    releases: &[structs::Release],
+    selector: &AssetSelector,
+) -> Result<structs::ReleaseAsset> {
+    let (asset, rejections) = selector.select(releases);
+    for rejection in &rejections {
+        debug!("Skipping {}", rejection);
+    }
+    match asset {
+        Some(asset) => Ok(asset.clone()),
+        None => Err(Error::NoMatchingAsset(explain_rejections(&rejections))),
+    }
+}
+
+/// Downloads the newest asset whose jar declares support for the game version and loader of
+/// `selector` into `dir`. Asset names can be misleading, so every candidate is downloaded and its
+/// `fabric.mod.json` or `mods.toml` checked, falling back to older releases until one fits.
+pub async fn download_mod_from_release(
+    releases: &[structs::Release],
+    selector: &AssetSelector,
+    dir: &Path,
+    source: &Source,
+    repo: &str,
+) -> Result<PathBuf> {
+    let temp_dir = tempfile::tempdir()?;
+    let mut rejections = Vec::new();
+    let picked = pick_candidate(
+        releases,
+        selector,
+        temp_dir.path(),
+        dir,
+        source,
+        repo,
+        &mut 0,
+        &mut rejections,
+    )
+    .await?;
+    picked.ok_or_else(|| Error::NoMatchingAsset(explain_rejections(&rejections)))
+}
+
+/// Downloads the candidates of `releases` into `temp_dir` one by one and copies the first whose
+/// jar fits `selector` into `dir`. Stops once `downloads` reaches [`MAX_DOWNLOADED_CANDIDATES`].
+#[allow(clippy::too_many_arguments)]
+async fn pick_candidate(
+    releases: &[structs::Release],
+    selector: &AssetSelector,
+    temp_dir: &Path,
+    dir: &Path,
+    source: &Source,
+    repo: &str,
+    downloads: &mut usize,
+    rejections: &mut Vec<Rejection>,
+) -> Result<Option<PathBuf>> {
+    let (candidates, skipped) = selector.candidates(releases);
+    rejections.extend(skipped);
+    for (release, asset) in candidates {
+        if *downloads >= MAX_DOWNLOADED_CANDIDATES {
+            break;
+        }
+        *downloads += 1;
+        let temp_path = temp_dir.join(&asset.name);
+        asset
+            .download(temp_path.clone(), source.clone(), repo.to_string())
+            .await?;
+        let issue = match ModManifest::read_jar(&temp_path) {
+            Ok(manifests) => {
+                manifest::compatibility_issue(&manifests, &selector.game_version, &selector.loader)
+            }
+            Err(err) => {
+                debug!("Could not read the mod metadata of {}: {}", asset.name, err);
+                None
+            }
+        };
+        if let Some(reason) = issue {
+            debug!("Skipping {} ({}): {}", asset.name, release, reason);
+            rejections.push(Rejection {
+                release,
+                asset: asset.name.clone(),
+                reason,
+            });
+            continue;
+        }
+        let path = dir.join(&asset.name);
+        fs::copy(&temp_path, &path)?;
+        return Ok(Some(path));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_page() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            reqwest::header::LINK,
+            r#"<https://api.github.com/repositories/1/releases?per_page=100&page=2>; rel="next", <https://api.github.com/repositories/1/releases?per_page=100&page=5>; rel="last""#
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            next_page(&headers).as_deref(),
+            Some("https://api.github.com/repositories/1/releases?per_page=100&page=2")
+        );
+        headers.insert(
+            reqwest::header::LINK,
+            r#"<https://api.github.com/repositories/1/releases?page=1>; rel="prev""#
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(next_page(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_token() {
+        let mut api = GHReleasesAPI::new();
+        api.token("ghp_abc\n".to_string());
+        assert_eq!(api.token.as_deref(), Some("ghp_abc"));
+        // Rejected before anything is sent
+        api.token("ghp_a\u{7f}bc".to_string());
+        let err = api.get("https://api.github.com").await.unwrap_err();
+        assert!(matches!(err, Error::AuthFailed(_)), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_get_mod_from_release() {
+        let gh_api = GHReleasesAPI::new();
+        let releases = gh_api.get_releases("fabricmc", "fabric").await.unwrap();
+        let selector = AssetSelector::new("1.21.4", crate::ModLoader::Fabric);
+        let r1_21_4 = get_mod_from_release(&releases, &selector).await;
+        println!("{:#?}", r1_21_4);
+        assert!(r1_21_4.is_ok());
+    }
+}
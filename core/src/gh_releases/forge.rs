@@ -0,0 +1,217 @@
+use crate::cli::Source;
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// The host releases are fetched from when a repo doesn't name one.
+pub const GITHUB_HOST: &str = "github.com";
+
+/// The git forges whose releases mods can be downloaded from. Gitea also covers Forgejo and
+/// Codeberg, which share its API.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Forge {
+    #[default]
+    Github,
+    Gitea,
+    Gitlab,
+}
+
+impl Forge {
+    pub fn from_source(source: &Source) -> Option<Self> {
+        match source {
+            Source::Github => Some(Forge::Github),
+            Source::Gitea => Some(Forge::Gitea),
+            Source::Gitlab => Some(Forge::Gitlab),
+            _ => None,
+        }
+    }
+
+    pub fn source(&self) -> Source {
+        match self {
+            Forge::Github => Source::Github,
+            Forge::Gitea => Source::Gitea,
+            Forge::Gitlab => Source::Gitlab,
+        }
+    }
+
+    /// Guesses the forge running on `host`, going by the config and then the well-known hosts
+    /// and naming conventions.
+    pub fn detect(host: &str, config: &Config) -> Option<Self> {
+        let host = host.to_lowercase();
+        if let Some(forge) = config.forges.get(&host).and_then(|forge| forge.forge) {
+            return Some(forge);
+        }
+        if host == GITHUB_HOST {
+            Some(Forge::Github)
+        } else if host == "codeberg.org" || host.contains("gitea") || host.contains("forgejo") {
+            Some(Forge::Gitea)
+        } else if host.contains("gitlab") {
+            Some(Forge::Gitlab)
+        } else {
+            None
+        }
+    }
+
+    /// The root of the forge's REST API on `host`. GitHub Enterprise serves it under `/api/v3`.
+    pub fn api_base(&self, host: Option<&str>) -> String {
+        match (self, host) {
+            (Forge::Github, None) => "https://api.github.com".to_string(),
+            (Forge::Github, Some(host)) if host.eq_ignore_ascii_case(GITHUB_HOST) => {
+                "https://api.github.com".to_string()
+            }
+            (Forge::Github, Some(host)) => format!("https://{}/api/v3", host),
+            (Forge::Gitea, host) => format!("https://{}/api/v1", host.unwrap_or("codeberg.org")),
+            (Forge::Gitlab, host) => format!("https://{}/api/v4", host.unwrap_or("gitlab.com")),
+        }
+    }
+
+    /// The environment variable holding a token for the forge.
+    pub fn token_var(&self, host: Option<&str>) -> &'static str {
+        match self {
+            Forge::Github if host.is_some_and(|host| !host.eq_ignore_ascii_case(GITHUB_HOST)) => {
+                "GH_ENTERPRISE_TOKEN"
+            }
+            Forge::Github => "GITHUB_TOKEN",
+            Forge::Gitea => "GITEA_TOKEN",
+            Forge::Gitlab => "GITLAB_TOKEN",
+        }
+    }
+}
+
+impl Display for Forge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Forge::Github => "GitHub",
+            Forge::Gitea => "Gitea",
+            Forge::Gitlab => "GitLab",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// A repo on a forge, written `owner/repo` for GitHub or `host/owner/repo` for any forge. GitLab
+/// owners can span several groups, as in `gitlab.com/group/subgroup/repo`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RepoSpec {
+    pub host: Option<String>,
+    pub owner: String,
+    pub repo: String,
+}
+
+impl RepoSpec {
+    /// Parses a spec, also accepting repo urls like `https://codeberg.org/owner/repo.git`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        let spec = spec
+            .strip_prefix("https://")
+            .or_else(|| spec.strip_prefix("http://"))
+            .unwrap_or(spec)
+            .trim_end_matches('/');
+        let spec = spec.strip_suffix(".git").unwrap_or(spec);
+        let parts = spec
+            .split('/')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>();
+        let (host, parts) = match parts.first() {
+            Some(first) if parts.len() >= 3 && (first.contains('.') || first.contains(':')) => {
+                (Some(first.to_lowercase()), &parts[1..])
+            }
+            _ => (None, &parts[..]),
+        };
+        let [owner @ .., repo] = parts else {
+            return None;
+        };
+        if owner.is_empty() {
+            return None;
+        }
+        // Repos on github.com are kept in the short form
+        let host = host.filter(|host| host != GITHUB_HOST);
+        Some(Self {
+            host,
+            owner: owner.join("/"),
+            repo: repo.to_string(),
+        })
+    }
+
+    /// The repo's page in a browser.
+    pub fn web_url(&self) -> String {
+        format!(
+            "https://{}/{}/{}",
+            self.host.as_deref().unwrap_or(GITHUB_HOST),
+            self.owner,
+            self.repo
+        )
+    }
+
+    /// The forge hosting the repo, if it can be told from the host.
+    pub fn detect_forge(&self, config: &Config) -> Option<Forge> {
+        match &self.host {
+            Some(host) => Forge::detect(host, config),
+            None => Some(Forge::Github),
+        }
+    }
+}
+
+impl Display for RepoSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.host {
+            Some(host) => write!(f, "{}/{}/{}", host, self.owner, self.repo),
+            None => write!(f, "{}/{}", self.owner, self.repo),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_repo_spec() {
+        let spec = |host: Option<&str>, owner: &str, repo: &str| RepoSpec {
+            host: host.map(String::from),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+        };
+        assert_eq!(
+            RepoSpec::parse("CaffeineMC/sodium"),
+            Some(spec(None, "CaffeineMC", "sodium"))
+        );
+        assert_eq!(
+            RepoSpec::parse("github.com/CaffeineMC/sodium"),
+            Some(spec(None, "CaffeineMC", "sodium"))
+        );
+        assert_eq!(
+            RepoSpec::parse("https://codeberg.org/owner/mod.git"),
+            Some(spec(Some("codeberg.org"), "owner", "mod"))
+        );
+        assert_eq!(
+            RepoSpec::parse("gitlab.example.com:8443/group/sub/mod"),
+            Some(spec(Some("gitlab.example.com:8443"), "group/sub", "mod"))
+        );
+        assert_eq!(RepoSpec::parse("sodium"), None);
+        assert_eq!(
+            RepoSpec::parse("codeberg.org/owner/mod")
+                .unwrap()
+                .to_string(),
+            "codeberg.org/owner/mod"
+        );
+    }
+
+    #[test]
+    fn test_detect_forge() {
+        let config = Config::default();
+        assert_eq!(Forge::detect("codeberg.org", &config), Some(Forge::Gitea));
+        assert_eq!(
+            Forge::detect("gitlab.example.com", &config),
+            Some(Forge::Gitlab)
+        );
+        assert_eq!(Forge::detect("git.example.com", &config), None);
+        assert_eq!(
+            Forge::Github.api_base(Some("github.example.com")),
+            "https://github.example.com/api/v3"
+        );
+    }
+}
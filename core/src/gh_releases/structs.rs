@@ -9,25 +9,30 @@ use super::Error;
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// A release as returned by GitHub and Gitea/Forgejo, which share the format. GitLab releases are
+/// converted from [`GitlabRelease`]. Fields only GitHub sends are optional.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Release {
-    pub url: Url,
-    pub html_url: Url,
-    pub assets_url: Url,
-    pub upload_url: String,
+    pub url: Option<Url>,
+    pub html_url: Option<Url>,
+    pub assets_url: Option<Url>,
+    pub upload_url: Option<String>,
     pub tarball_url: Option<Url>,
     pub zipball_url: Option<Url>,
-    pub id: u64,
-    pub node_id: String,
+    pub id: Option<u64>,
+    pub node_id: Option<String>,
     pub tag_name: String,
-    pub target_commitish: String,
+    pub target_commitish: Option<String>,
     pub name: Option<String>,
     pub body: Option<String>,
+    #[serde(default)]
     pub draft: bool,
+    #[serde(default)]
     pub prerelease: bool,
-    pub created_at: DateTime<Utc>,
+    pub created_at: Option<DateTime<Utc>>,
     pub published_at: Option<DateTime<Utc>>,
-    pub author: User,
+    pub author: Option<User>,
+    #[serde(default)]
     pub assets: Vec<ReleaseAsset>,
     pub body_html: Option<String>,
     pub body_text: Option<String>,
@@ -37,46 +42,72 @@ pub struct Release {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Repository {
+    pub name: String,
+    pub full_name: String,
+    pub owner: User,
+    pub html_url: Url,
+    pub description: Option<String>,
+    /// Gitea calls the homepage `website`.
+    #[serde(alias = "website")]
+    pub homepage: Option<String>,
+    pub license: Option<License>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct License {
+    pub key: String,
+    pub name: String,
+    pub spdx_id: Option<String>,
+}
+
+/// A user or organization. Fields only GitHub sends are optional.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct User {
     pub name: Option<String>,
     pub email: Option<String>,
     pub login: String,
-    pub id: u64,
-    pub node_id: String,
-    pub avatar_url: Url,
+    pub id: Option<u64>,
+    pub node_id: Option<String>,
+    pub avatar_url: Option<Url>,
     pub gravatar_id: Option<String>,
-    pub url: Url,
-    pub html_url: Url,
-    pub followers_url: Url,
-    pub following_url: String,
-    pub gists_url: String,
+    pub url: Option<Url>,
+    pub html_url: Option<Url>,
+    pub followers_url: Option<Url>,
+    pub following_url: Option<String>,
+    pub gists_url: Option<String>,
     pub starred_url: Option<String>,
-    pub subscriptions_url: Url,
-    pub organizations_url: Url,
-    pub repos_url: Url,
-    pub events_url: String,
-    pub received_events_url: Url,
-    pub r#type: String,
+    pub subscriptions_url: Option<Url>,
+    pub organizations_url: Option<Url>,
+    pub repos_url: Option<Url>,
+    pub events_url: Option<String>,
+    pub received_events_url: Option<Url>,
+    pub r#type: Option<String>,
+    #[serde(default)]
     pub site_admin: bool,
     pub starred_at: Option<String>,
-    pub user_view_type: String,
+    pub user_view_type: Option<String>,
 }
 
+/// A file attached to a release. Fields only GitHub sends are optional.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ReleaseAsset {
-    pub url: Url,
+    pub url: Option<Url>,
     pub browser_download_url: Url,
-    pub id: u64,
-    pub node_id: String,
+    pub id: Option<u64>,
+    pub node_id: Option<String>,
     pub name: String,
     pub label: Option<String>,
-    pub state: AssetState,
-    pub content_type: String,
+    pub state: Option<AssetState>,
+    pub content_type: Option<String>,
+    #[serde(default)]
     pub size: i64,
+    /// The `sha256:` digest of the asset, only sent by GitHub.
     pub digest: Option<String>,
+    #[serde(default)]
     pub download_count: i64,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
     pub uploader: Option<User>,
 }
 
@@ -103,17 +134,145 @@ pub struct ReactionRollup {
     pub rocket: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitlabRelease {
+    pub name: Option<String>,
+    pub tag_name: String,
+    pub description: Option<String>,
+    pub released_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub upcoming_release: bool,
+    #[serde(rename = "_links")]
+    pub links: Option<GitlabReleaseLinks>,
+    pub assets: GitlabAssets,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitlabReleaseLinks {
+    #[serde(rename = "self")]
+    pub self_: Option<Url>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitlabAssets {
+    #[serde(default)]
+    pub links: Vec<GitlabAssetLink>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitlabAssetLink {
+    pub name: String,
+    pub url: Url,
+    pub direct_asset_url: Option<Url>,
+}
+
+impl From<GitlabRelease> for Release {
+    fn from(release: GitlabRelease) -> Self {
+        Self {
+            url: None,
+            html_url: release.links.and_then(|links| links.self_),
+            assets_url: None,
+            upload_url: None,
+            tarball_url: None,
+            zipball_url: None,
+            id: None,
+            node_id: None,
+            tag_name: release.tag_name,
+            target_commitish: None,
+            name: release.name,
+            body: release.description,
+            draft: false,
+            // GitLab has no prereleases, only releases scheduled for the future
+            prerelease: release.upcoming_release,
+            created_at: None,
+            published_at: release.released_at,
+            author: None,
+            assets: release
+                .assets
+                .links
+                .into_iter()
+                .map(|link| ReleaseAsset {
+                    url: None,
+                    browser_download_url: link.direct_asset_url.unwrap_or(link.url),
+                    id: None,
+                    node_id: None,
+                    name: link.name,
+                    label: None,
+                    state: None,
+                    content_type: None,
+                    size: 0,
+                    digest: None,
+                    download_count: 0,
+                    created_at: None,
+                    updated_at: None,
+                    uploader: None,
+                })
+                .collect(),
+            body_html: None,
+            body_text: None,
+            mentions_count: None,
+            discussion_url: None,
+            reactions: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitlabProject {
+    pub name: String,
+    pub path_with_namespace: String,
+    pub web_url: Url,
+    pub description: Option<String>,
+    pub namespace: GitlabNamespace,
+    pub license: Option<GitlabLicense>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitlabNamespace {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitlabLicense {
+    pub key: String,
+    pub name: String,
+}
+
+impl From<GitlabProject> for Repository {
+    fn from(project: GitlabProject) -> Self {
+        Self {
+            name: project.name,
+            full_name: project.path_with_namespace,
+            owner: User {
+                login: project.namespace.path,
+                name: Some(project.namespace.name),
+                ..Default::default()
+            },
+            html_url: project.web_url,
+            description: project.description,
+            homepage: None,
+            license: project.license.map(|license| License {
+                key: license.key,
+                name: license.name,
+                spdx_id: None,
+            }),
+        }
+    }
+}
+
 impl ReleaseAsset {
     pub fn get_download_url(&self) -> Option<Url> {
         Some(self.browser_download_url.clone())
     }
-    pub async fn download(&self, path: PathBuf, repo: String) -> Result<()> {
+    /// Downloads the asset to `path`, recording `source` and `repo` in the jar for later updates.
+    pub async fn download(&self, path: PathBuf, source: Source, repo: String) -> Result<()> {
         let url = self.get_download_url().expect("Asset has no download url");
-        let file_content = reqwest::get(url.clone()).await.unwrap();
-        fs::write(&path, file_content.bytes().await.unwrap())?;
+        let file_content = reqwest::get(url.clone()).await?.error_for_status()?;
+        fs::write(&path, file_content.bytes().await?)?;
         let handle = tokio::spawn(async move {
             // Adds metadata to the file for later use with `update` option
-            Metadata::add_metadata(path.clone(), Source::Github, "repo", &repo).unwrap();
+            Metadata::add_metadata(path.clone(), source, "repo", &repo).unwrap();
         });
         handle.await.unwrap();
         Ok(())
@@ -0,0 +1,290 @@
+mod structs;
+use crate::cli::Source;
+use crate::info::ProjectInfo;
+use crate::{Link, ModLoader, UrlBuilder, record_install, safe_file_name};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+pub use structs::*;
+use tracing::{debug, warn};
+
+type Result<T> = std::result::Result<T, HangarError>;
+pub const API_URL: &str = "https://hangar.papermc.io/api/v1";
+pub const WEB_URL: &str = "https://hangar.papermc.io";
+
+/// Client for Hangar, PaperMC's plugin repository.
+#[derive(Clone, Default)]
+pub struct HangarAPI {
+    pub client: reqwest::Client,
+}
+
+impl HangarAPI {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Searches for plugins, optionally only those for `platform` and `game_version`.
+    pub async fn search(
+        &self,
+        query: &str,
+        platform: Option<Platform>,
+        game_version: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Paginated<Project>> {
+        let mut url = UrlBuilder::new(API_URL, "/projects")
+            .param("query", query)
+            .param("limit", limit)
+            .param("offset", offset);
+        if let Some(platform) = platform {
+            url = url.param("platform", platform);
+        }
+        if let Some(game_version) = game_version {
+            url = url.param("version", game_version);
+        }
+        self.get(url).await
+    }
+    /// Gets a project by its slug. `owner/slug` is accepted too.
+    pub async fn get_project(&self, project: &str) -> Result<Project> {
+        let url = UrlBuilder::new(API_URL, "/projects").segment(slug(project));
+        self.get(url).await
+    }
+    /// The versions of `project` for `platform` and `game_version`, newest first.
+    pub async fn get_versions(
+        &self,
+        project: &str,
+        platform: Platform,
+        game_version: Option<&str>,
+    ) -> Result<Vec<Version>> {
+        let mut url = UrlBuilder::new(API_URL, "/projects")
+            .segment(slug(project))
+            .segment("versions")
+            .param("limit", 25)
+            .param("offset", 0)
+            .param("platform", platform);
+        if let Some(game_version) = game_version {
+            url = url.param("platformVersion", game_version);
+        }
+        let versions: Paginated<Version> = self.get(url).await?;
+        Ok(versions.result)
+    }
+    /// Downloads the newest version of `project` for `game_version` and `loader` into `dir`.
+    pub async fn download(
+        &self,
+        project: &str,
+        game_version: &str,
+        loader: &ModLoader,
+        dir: &Path,
+    ) -> Result<PathBuf> {
+        let (path, project) = self.fetch(project, game_version, loader, dir, None).await?;
+        let path = path.ok_or_else(|| {
+            HangarError::NoVersionFound(project.full_slug(), game_version.to_string())
+        })?;
+        record_install(&path, Source::Hangar, &project.full_slug(), None);
+        Ok(path)
+    }
+    /// Updates the plugin at `path` to the newest version of `project` for `game_version` and
+    /// `loader`, returning `path` itself if that version is already installed.
+    pub async fn update(
+        &self,
+        project: &str,
+        game_version: &str,
+        loader: &ModLoader,
+        path: &Path,
+    ) -> Result<PathBuf> {
+        let dir = path.parent().unwrap_or(Path::new("./"));
+        let installed = path.file_name().map(|name| name.to_string_lossy());
+        let (new_path, _) = self
+            .fetch(project, game_version, loader, dir, installed.as_deref())
+            .await?;
+        Ok(new_path.unwrap_or_else(|| path.to_path_buf()))
+    }
+    /// Downloads the newest version of `project` into `dir`, unless its file is `installed`.
+    async fn fetch(
+        &self,
+        project: &str,
+        game_version: &str,
+        loader: &ModLoader,
+        dir: &Path,
+        installed: Option<&str>,
+    ) -> Result<(Option<PathBuf>, Project)> {
+        let platform =
+            Platform::from_loader(loader).ok_or(HangarError::UnsupportedLoader(loader.clone()))?;
+        let project = self.get_project(project).await?;
+        let versions = self
+            .get_versions(&project.namespace.slug, platform, Some(game_version))
+            .await?;
+        let Some((version, download)) = versions.iter().find_map(|version| {
+            let download = version.downloads.get(&platform)?;
+            Some((version, download))
+        }) else {
+            return Err(HangarError::NoVersionFound(
+                project.full_slug(),
+                game_version.to_string(),
+            ));
+        };
+        let Some(url) = download
+            .download_url
+            .as_ref()
+            .or(download.external_url.as_ref())
+        else {
+            return Err(HangarError::NoVersionFound(
+                project.full_slug(),
+                game_version.to_string(),
+            ));
+        };
+        let file_name = match &download.file_info {
+            // The name comes from the API, so it mustn't lead outside `dir`
+            Some(file_info) => safe_file_name(&file_info.name)
+                .map(String::from)
+                .ok_or_else(|| {
+                    HangarError::InvalidFileName(project.name.clone(), file_info.name.clone())
+                })?,
+            None => {
+                // External files can't be verified and don't always have a usable name
+                warn!(
+                    "{} is hosted at {}, skipping verification",
+                    project.name, url
+                );
+                url.rsplit('/')
+                    .next()
+                    .filter(|name| name.ends_with(".jar"))
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("{}-{}.jar", project.namespace.slug, version.name))
+            }
+        };
+        if installed == Some(file_name.as_str()) {
+            debug!("{} {} is already installed", project.name, version.name);
+            return Ok((None, project));
+        }
+        debug!("Downloading {} {} from {}", project.name, version.name, url);
+        let contents = self.client.get(url).send().await?.error_for_status()?;
+        let contents = contents.bytes().await?;
+        if let Some(expected) = download
+            .file_info
+            .as_ref()
+            .and_then(|file_info| file_info.sha256_hash.as_ref())
+        {
+            let actual = hex::encode(hmac_sha256::Hash::hash(&contents));
+            if !expected.eq_ignore_ascii_case(&actual) {
+                return Err(HangarError::ChecksumMismatch {
+                    file: file_name,
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+        let path = dir.join(file_name);
+        fs::write(&path, &contents)?;
+        Ok((Some(path), project))
+    }
+    pub async fn get_project_info(
+        &self,
+        project: &str,
+        game_version: Option<&str>,
+        loader: Option<ModLoader>,
+    ) -> Result<ProjectInfo> {
+        let project = self.get_project(project).await?;
+        let platform = loader
+            .as_ref()
+            .and_then(Platform::from_loader)
+            .unwrap_or(Platform::Paper);
+        let versions = self
+            .get_versions(&project.namespace.slug, platform, game_version)
+            .await?;
+        let newest = versions.first();
+        let mut links = vec![Link::new(
+            "Page".to_string(),
+            format!("{}/{}", WEB_URL, project.full_slug()),
+        )];
+        let license = project
+            .settings
+            .as_ref()
+            .and_then(|settings| settings.license.clone());
+        if let Some(url) = license.as_ref().and_then(|license| license.url.clone()) {
+            links.push(Link::new("License".to_string(), url));
+        }
+        Ok(ProjectInfo {
+            source: Source::Hangar,
+            id: project.full_slug(),
+            title: project.name.clone(),
+            summary: project.description.clone().unwrap_or_default(),
+            authors: vec![project.namespace.owner.clone()],
+            license: license.and_then(|license| license.name.or(license.type_)),
+            game_versions: newest
+                .and_then(|version| version.platform_dependencies.get(&platform).cloned())
+                .unwrap_or_default(),
+            loaders: newest
+                .map(|version| version.downloads.keys().map(Platform::to_string).collect())
+                .unwrap_or_default(),
+            downloads: project.stats.downloads,
+            links,
+            version: newest.map(|version| version.name.clone()),
+            changelog: newest.and_then(|version| version.description.clone()),
+            ..Default::default()
+        })
+    }
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: UrlBuilder) -> Result<T> {
+        debug!(url = %url);
+        let response = self
+            .client
+            .get(url.to_string())
+            .header("accept", "application/json")
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(serde_json::from_str(&response.text().await?)?)
+    }
+}
+
+/// The slug of a project given as `owner/slug` or `slug`.
+fn slug(project: &str) -> &str {
+    project.rsplit('/').next().unwrap_or(project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_versions() {
+        let body = r#"{
+            "pagination": {"limit": 25, "offset": 0, "count": 1},
+            "result": [{
+                "name": "5.4.137",
+                "createdAt": "2024-07-01T12:00:00Z",
+                "description": "Fixes",
+                "downloads": {
+                    "PAPER": {
+                        "fileInfo": {"name": "LuckPerms-Bukkit-5.4.137.jar", "sizeBytes": 10, "sha256Hash": "ab"},
+                        "externalUrl": null,
+                        "downloadUrl": "https://hangarcdn.papermc.io/plugins/LuckPerms/5.4.137/PAPER/LuckPerms-Bukkit-5.4.137.jar"
+                    }
+                },
+                "platformDependencies": {"PAPER": ["1.8-1.21.4"]}
+            }]
+        }"#;
+        let versions: Paginated<Version> = serde_json::from_str(body).unwrap();
+        let version = &versions.result[0];
+        assert_eq!(version.name, "5.4.137");
+        assert_eq!(
+            version.downloads[&Platform::Paper]
+                .file_info
+                .as_ref()
+                .unwrap()
+                .name,
+            "LuckPerms-Bukkit-5.4.137.jar"
+        );
+        assert_eq!(
+            version.platform_dependencies[&Platform::Paper],
+            vec!["1.8-1.21.4"]
+        );
+        assert_eq!(slug("PaperMC/LuckPerms"), "LuckPerms");
+        assert_eq!(
+            Platform::from_loader(&ModLoader::Bukkit),
+            Some(Platform::Paper)
+        );
+        assert_eq!(Platform::from_loader(&ModLoader::Fabric), None);
+    }
+}
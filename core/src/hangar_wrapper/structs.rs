@@ -0,0 +1,142 @@
+use crate::ModLoader;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::Display;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HangarError {
+    #[error("JSON Parsing error: {0}")]
+    JsonParsingError(#[from] serde_json::Error),
+    #[error("HTTP Error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("IO Error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Hangar has no plugins for {0}")]
+    UnsupportedLoader(ModLoader),
+    #[error("No version of {0} found for {1}")]
+    NoVersionFound(String, String),
+    #[error("Hangar named the file of {0} {1:?}, which can't be saved")]
+    InvalidFileName(String, String),
+    #[error("Checksum mismatch for {file}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// The platforms plugins on Hangar are published for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum Platform {
+    Paper,
+    Waterfall,
+    Velocity,
+}
+
+impl Platform {
+    /// The platform whose plugins `loader` runs. Paper runs Spigot and Bukkit plugins, so those
+    /// are looked up as Paper plugins.
+    pub fn from_loader(loader: &ModLoader) -> Option<Self> {
+        match loader {
+            ModLoader::Paper | ModLoader::Spigot | ModLoader::Bukkit | ModLoader::Any => {
+                Some(Platform::Paper)
+            }
+            ModLoader::Velocity => Some(Platform::Velocity),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Paginated<T> {
+    pub pagination: Pagination,
+    pub result: Vec<T>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Pagination {
+    pub limit: u32,
+    pub offset: u32,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub id: u64,
+    pub name: String,
+    pub namespace: Namespace,
+    pub stats: ProjectStats,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    pub last_updated: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub settings: Option<ProjectSettings>,
+}
+
+impl Project {
+    /// `owner/slug`, as the project is recorded and shown.
+    pub fn full_slug(&self) -> String {
+        format!("{}/{}", self.namespace.owner, self.namespace.slug)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Namespace {
+    pub owner: String,
+    pub slug: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectStats {
+    pub downloads: u64,
+    #[serde(default)]
+    pub stars: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    pub license: Option<License>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct License {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Version {
+    pub name: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub downloads: HashMap<Platform, PlatformDownload>,
+    /// The game versions the version supports per platform, e.g. `1.20-1.20.4` or `1.21`.
+    #[serde(default)]
+    pub platform_dependencies: HashMap<Platform, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlatformDownload {
+    pub file_info: Option<FileInfo>,
+    /// Set when the file is hosted outside of Hangar.
+    pub external_url: Option<String>,
+    pub download_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileInfo {
+    pub name: String,
+    pub size_bytes: u64,
+    pub sha256_hash: Option<String>,
+}
@@ -0,0 +1,218 @@
+use crate::cli::Source;
+#[cfg(feature = "github")]
+use crate::gh_releases::RepoSpec;
+use crate::hangar_wrapper::HangarAPI;
+use crate::maven;
+#[cfg(feature = "modrinth")]
+use crate::modrinth_wrapper::modrinth;
+use crate::{Clients, Link, ModLoader};
+use color_eyre::Result;
+use color_eyre::eyre::bail;
+#[cfg(feature = "colored")]
+use colored::Colorize;
+
+#[cfg(feature = "colored")]
+const GRAY: (u8, u8, u8) = (128, 128, 128);
+
+/// Everything modder knows about a project, independent of the source it comes from.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectInfo {
+    pub source: Source,
+    /// The Modrinth project id, CurseForge mod id or GitHub repo of the project.
+    pub id: String,
+    pub title: String,
+    pub summary: String,
+    pub authors: Vec<String>,
+    pub license: Option<String>,
+    pub client_side: Option<String>,
+    pub server_side: Option<String>,
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
+    pub downloads: u64,
+    pub links: Vec<Link>,
+    /// The version the changelog belongs to.
+    pub version: Option<String>,
+    pub changelog: Option<String>,
+}
+
+impl ProjectInfo {
+    /// Fetches the details of the project `id` from `source`. If `game_version` is given, the
+    /// changelog is taken from the newest version for that game version, otherwise from the
+    /// newest version overall.
+    #[cfg_attr(not(feature = "curseforge"), allow(unused_variables))]
+    pub async fn fetch(
+        source: &Source,
+        id: &str,
+        game_version: Option<&str>,
+        loader: Option<ModLoader>,
+        clients: &Clients,
+    ) -> Result<Self> {
+        let info = match source {
+            #[cfg(feature = "modrinth")]
+            Source::Modrinth => modrinth::get_project_info(id, game_version, loader).await?,
+            #[cfg(feature = "curseforge")]
+            Source::CurseForge => {
+                let Ok(mod_id) = id.parse() else {
+                    bail!("Invalid CurseForge mod id {}", id);
+                };
+                clients
+                    .curseforge
+                    .get_project_info(mod_id, game_version, loader)
+                    .await?
+            }
+            #[cfg(feature = "github")]
+            Source::Github | Source::Gitea | Source::Gitlab => {
+                let Some(spec) = RepoSpec::parse(id) else {
+                    bail!(
+                        "Invalid repo {}, expected <owner>/<repo> or <host>/<owner>/<repo>",
+                        id
+                    );
+                };
+                let Some(forge) = clients.github.for_repo(&spec, Some(source)) else {
+                    bail!("Unknown forge for {}", id);
+                };
+                forge
+                    .get_project_info(&spec.owner, &spec.repo, game_version)
+                    .await?
+            }
+            Source::Hangar => {
+                HangarAPI::new()
+                    .get_project_info(id, game_version, loader)
+                    .await?
+            }
+            Source::Maven => {
+                let Some(coordinate) = maven::Coordinate::parse(id) else {
+                    bail!("Invalid coordinate {}, expected <group>:<artifact>", id);
+                };
+                maven::MavenAPI::from_config(None)
+                    .get_project_info(&coordinate, game_version)
+                    .await?
+            }
+            Source::Url | Source::Local => {
+                bail!("Mods added from a {} have no project page", source)
+            }
+            #[allow(unreachable_patterns)]
+            _ => bail!(source.disabled_error()),
+        };
+        Ok(info)
+    }
+
+    #[cfg(feature = "colored")]
+    pub fn format(&self) -> String {
+        let mut output = String::new();
+        output.push_str(&format!(
+            "{} {}\n",
+            self.title.bold(),
+            self.id.truecolor(GRAY.0, GRAY.1, GRAY.2)
+        ));
+        if !self.summary.is_empty() {
+            output.push_str(&format!("\t{}\n", self.summary));
+        }
+        output.push_str(&format!("\tSource: {}\n", self.source));
+        if !self.authors.is_empty() {
+            output.push_str(&format!("\tAuthors: {}\n", self.authors.join(", ")));
+        }
+        output.push_str(&format!(
+            "\tLicense: {}\n",
+            self.license.as_deref().unwrap_or("-")
+        ));
+        if self.client_side.is_some() || self.server_side.is_some() {
+            output.push_str(&format!(
+                "\tClient side: {}\n\tServer side: {}\n",
+                self.client_side.as_deref().unwrap_or("-"),
+                self.server_side.as_deref().unwrap_or("-")
+            ));
+        }
+        if !self.game_versions.is_empty() {
+            output.push_str(&format!(
+                "\tGame versions: {}\n",
+                self.game_versions.join(", ").green()
+            ));
+        }
+        if !self.loaders.is_empty() {
+            output.push_str(&format!("\tLoaders: {}\n", self.loaders.join(", ").cyan()));
+        }
+        output.push_str(&format!("\tDownloads: {}\n", self.downloads));
+        for link in &self.links {
+            output.push_str(&format!(
+                "\t{}: {}\n",
+                link.text,
+                Link::new(link.url.clone(), link.url.clone())
+                    .to_string()
+                    .blue()
+            ));
+        }
+        if let Some(changelog) = &self.changelog {
+            output.push_str(&format!(
+                "\n{} {}\n",
+                "Changelog".bold(),
+                self.version.as_deref().unwrap_or_default()
+            ));
+            for line in changelog.trim().lines() {
+                output.push_str(&format!("\t{}\n", line));
+            }
+        }
+        output
+    }
+}
+
+/// Turns the HTML changelogs CurseForge returns into plain text.
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut tag = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                tag.clear();
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                let name = tag
+                    .trim_start_matches('/')
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_lowercase();
+                match name.as_str() {
+                    "br" | "p" | "div" | "h1" | "h2" | "h3" | "h4" | "tr" => text.push('\n'),
+                    "li" if !tag.starts_with('/') => text.push_str("\n- "),
+                    _ => {}
+                }
+            }
+            _ if in_tag => tag.push(c),
+            _ => text.push(c),
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    let mut lines = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && lines.last().is_none_or(|last: &&str| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_strip_html() {
+        let html = "<p>Fixed a crash &amp; a dupe</p><ul><li>Updated to 1.21</li><li>Added <b>config</b></li></ul>";
+        assert_eq!(
+            strip_html(html),
+            "Fixed a crash & a dupe\n\n- Updated to 1.21\n- Added config"
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use crate::check;
+use crate::manifest::{ModManifest, VersionRange};
+use std::{fmt::Display, fs::File, io::Read, path::Path, process::Command};
+
+/// The oldest Java version Minecraft runs on.
+const OLDEST_JAVA: u32 = 8;
+/// The newest Java version declared ranges are checked against. A range still matching it has
+/// no upper bound.
+const NEWEST_JAVA: u32 = 25;
+/// Class files of Java 1.1 have major version 45, and every release adds one.
+const MAJOR_VERSION_OFFSET: u16 = 44;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Error reading the jar file: {0}")]
+    IOErr(#[from] std::io::Error),
+    #[error("Error unarchiving the jar file: {0}")]
+    Unzip(#[from] zip::result::ZipError),
+    #[error("Error listing the jars: {0}")]
+    Check(#[from] check::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// The Java versions a jar runs on.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct JavaRequirement {
+    /// The Java version its newest class files were compiled for.
+    pub class_version: Option<u32>,
+    /// The Java versions its manifest declares, i.e. Fabric's `depends.java`.
+    pub declared: Option<VersionRange>,
+}
+
+impl JavaRequirement {
+    pub fn read_jar(path: &Path) -> Result<Self> {
+        let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+        let mut class_version = None;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            // Multi-release jars keep classes for newer Java versions in `META-INF/versions`
+            if !file.name().ends_with(".class") || file.name().starts_with("META-INF/") {
+                continue;
+            }
+            let mut header = [0; 8];
+            if file.read_exact(&mut header).is_err() || header[..4] != [0xCA, 0xFE, 0xBA, 0xBE] {
+                continue;
+            }
+            let major = u16::from_be_bytes([header[6], header[7]]);
+            let version = major.saturating_sub(MAJOR_VERSION_OFFSET) as u32;
+            class_version = class_version.max(Some(version));
+        }
+        let declared = ModManifest::read_jar(path)
+            .unwrap_or_default()
+            .into_iter()
+            .find_map(|manifest| manifest.depends.get("java").cloned());
+        Ok(Self {
+            class_version,
+            declared,
+        })
+    }
+
+    /// The oldest Java version the jar runs on, if anything tells.
+    pub fn min(&self) -> Option<u32> {
+        let declared = self
+            .declared
+            .as_ref()
+            .filter(|range| !range.is_any())
+            .and_then(|range| (OLDEST_JAVA..=NEWEST_JAVA).find(|&java| matches(range, java)));
+        self.class_version.max(declared)
+    }
+
+    /// The newest Java version the jar runs on, if its manifest limits it.
+    pub fn max(&self) -> Option<u32> {
+        let range = self.declared.as_ref()?;
+        if matches(range, NEWEST_JAVA) {
+            return None;
+        }
+        (OLDEST_JAVA..=NEWEST_JAVA)
+            .rev()
+            .find(|&java| matches(range, java))
+    }
+}
+
+/// Whether `range` accepts `java`, which may be written `1.8` for Java 8 and older.
+fn matches(range: &VersionRange, java: u32) -> bool {
+    range.matches(&java.to_string()) || java <= 8 && range.matches(&format!("1.{}", java))
+}
+
+/// Reads the Java requirement of every jar in `dir`. Jars that can't be read are left out.
+pub fn read_dir(dir: &Path) -> Result<Vec<(String, JavaRequirement)>> {
+    let jars = check::jar_files(dir)?
+        .into_iter()
+        .filter_map(|path| {
+            let requirement = JavaRequirement::read_jar(&path).ok()?;
+            let file_name = path.file_name()?.to_string_lossy().to_string();
+            Some((file_name, requirement))
+        })
+        .collect();
+    Ok(jars)
+}
+
+/// The Java versions all mods of a directory run on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JavaReport {
+    /// The oldest Java version all mods run on, and the mod needing it.
+    pub min: Option<(u32, String)>,
+    /// The newest Java version all mods run on, and the mod limiting it.
+    pub max: Option<(u32, String)>,
+}
+
+impl JavaReport {
+    pub fn new(jars: &[(String, JavaRequirement)]) -> Self {
+        let min = jars
+            .iter()
+            .filter_map(|(file, requirement)| Some((requirement.min()?, file.clone())))
+            .max_by_key(|(java, _)| *java);
+        let max = jars
+            .iter()
+            .filter_map(|(file, requirement)| Some((requirement.max()?, file.clone())))
+            .min_by_key(|(java, _)| *java);
+        Self { min, max }
+    }
+
+    /// Explains why no Java version runs every mod, or why the one `installed` returns doesn't.
+    /// Finding the installed version out can mean running `java`, so `installed` is only called
+    /// if there is something to check it against. That is the case as soon as a jar has class
+    /// files, so only folders without any mods skip it.
+    pub fn warnings(&self, installed: impl FnOnce() -> Option<u32>) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.min.is_none() && self.max.is_none() {
+            return warnings;
+        }
+        if let (Some((min, min_file)), Some((max, max_file))) = (&self.min, &self.max)
+            && min > max
+        {
+            warnings.push(format!(
+                "{} needs Java {} or newer, but {} runs on Java {} at most",
+                min_file, min, max_file, max
+            ));
+        }
+        if let Some(installed) = installed() {
+            if let Some((min, file)) = &self.min
+                && installed < *min
+            {
+                warnings.push(format!(
+                    "The java on PATH is Java {}, but {} needs Java {} or newer",
+                    installed, file, min
+                ));
+            }
+            if let Some((max, file)) = &self.max
+                && installed > *max
+            {
+                warnings.push(format!(
+                    "The java on PATH is Java {}, but {} runs on Java {} at most",
+                    installed, file, max
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+impl Display for JavaReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.min, &self.max) {
+            (Some((min, _)), Some((max, _))) if min == max => write!(f, "Java {}", min),
+            (Some((min, min_file)), Some((max, max_file))) if min > max => write!(
+                f,
+                "no Java version ({} needs ≥{}, {} allows ≤{})",
+                min_file, min, max_file, max
+            ),
+            (Some((min, _)), Some((max, _))) => write!(f, "Java {} to {}", min, max),
+            (Some((min, _)), None) => write!(f, "Java {} or newer", min),
+            (None, Some((max, _))) => write!(f, "Java {} or older", max),
+            (None, None) => write!(f, "any Java version"),
+        }
+    }
+}
+
+/// The version of the `java` on PATH, or `None` if there is none.
+pub fn installed_version() -> Option<u32> {
+    let output = Command::new("java").arg("-version").output().ok()?;
+    // `java -version` prints to stderr
+    parse_version_output(&String::from_utf8_lossy(&output.stderr))
+}
+
+/// Reads the major version from `java -version`'s output, e.g. `openjdk version "21.0.2"` or
+/// `java version "1.8.0_392"`.
+fn parse_version_output(output: &str) -> Option<u32> {
+    let version = output.split('"').nth(1)?;
+    let mut parts = version.split(['.', '_', '-', '+']);
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_java_report() {
+        let requirement = |class_version: Option<u32>, declared: Option<&str>| JavaRequirement {
+            class_version,
+            declared: declared.map(VersionRange::parse_fabric),
+        };
+        assert_eq!(requirement(Some(17), Some(">=21")).min(), Some(21));
+        assert_eq!(requirement(Some(17), Some(">=1.8")).min(), Some(17));
+        assert_eq!(requirement(None, Some("<=17")).max(), Some(17));
+        assert_eq!(requirement(None, Some("*")).min(), None);
+
+        let jars = vec![
+            ("sodium.jar".to_string(), requirement(Some(21), None)),
+            ("old.jar".to_string(), requirement(Some(8), Some("<=17"))),
+            (
+                "lithium.jar".to_string(),
+                requirement(Some(17), Some(">=17")),
+            ),
+        ];
+        let report = JavaReport::new(&jars);
+        assert_eq!(
+            report.to_string(),
+            "no Java version (sodium.jar needs ≥21, old.jar allows ≤17)"
+        );
+        assert_eq!(JavaReport::new(&jars[1..]).to_string(), "Java 17");
+        assert_eq!(
+            report.warnings(|| Some(17)),
+            [
+                "sodium.jar needs Java 21 or newer, but old.jar runs on Java 17 at most",
+                "The java on PATH is Java 17, but sodium.jar needs Java 21 or newer",
+            ]
+        );
+        assert!(JavaReport::new(&jars[1..]).warnings(|| Some(17)).is_empty());
+        assert!(
+            JavaReport::default()
+                .warnings(|| unreachable!("no mod limits the Java version"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_read_jar() {
+        use std::io::Write;
+        let class = |major: u8| [0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, major];
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let files: [(&str, &[u8]); 4] = [
+            (
+                "fabric.mod.json",
+                br#"{"id": "sodium", "version": "0.6.0", "depends": {"java": ">=17"}}"#,
+            ),
+            ("net/caffeinemc/Sodium.class", &class(65)),
+            ("net/caffeinemc/Old.class", &class(52)),
+            (
+                "META-INF/versions/25/net/caffeinemc/Sodium.class",
+                &class(69),
+            ),
+        ];
+        for (name, contents) in files {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sodium.jar");
+        std::fs::write(&path, zip.finish().unwrap().into_inner()).unwrap();
+
+        let requirement = JavaRequirement::read_jar(&path).unwrap();
+        assert_eq!(requirement.class_version, Some(21));
+        assert_eq!(requirement.min(), Some(21));
+        assert_eq!(requirement.max(), None);
+    }
+
+    #[test]
+    fn test_parse_version_output() {
+        assert_eq!(
+            parse_version_output("openjdk version \"21.0.2\" 2024-01-16\nOpenJDK Runtime"),
+            Some(21)
+        );
+        assert_eq!(
+            parse_version_output("java version \"1.8.0_392\"\nJava(TM) SE Runtime"),
+            Some(8)
+        );
+        assert_eq!(parse_version_output("openjdk version \"17-ea\""), Some(17));
+        assert_eq!(parse_version_output("bash: java: command not found"), None);
+    }
+}
@@ -1,28 +1,54 @@
 #![allow(dead_code)]
+pub mod changelog;
+pub mod check;
 pub mod cli;
+pub mod config;
+pub mod content;
+#[cfg(feature = "curseforge")]
 pub mod curseforge_wrapper;
+pub mod direct;
+pub mod doctor;
 pub mod gh_releases;
+pub mod hangar_wrapper;
+pub mod info;
+pub mod java;
+pub mod manifest;
+pub mod maven;
 pub mod metadata;
+#[cfg(feature = "modrinth")]
 pub mod modrinth_wrapper;
-use clap::ValueEnum;
+pub mod resolver;
+use changelog::ModChangelog;
 use cli::Source;
 use color_eyre::Result;
 use color_eyre::eyre::bail;
+#[cfg(feature = "github")]
+use color_eyre::eyre::eyre;
+use config::Channel;
+use content::ContentType;
+#[cfg(feature = "curseforge")]
 use curseforge_wrapper::{CurseForgeAPI, CurseForgeMod};
+#[cfg(feature = "github")]
 use gh_releases::{Error, GHReleasesAPI};
 use hmac_sha512::Hash;
 use itertools::Itertools;
+#[cfg(feature = "github")]
 use metadata::Metadata;
+use metadata::{DirMetadata, Origin};
+#[cfg(feature = "modrinth")]
 use modrinth_wrapper::modrinth;
-use serde::Deserialize;
-use std::collections::HashSet;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
 use std::fmt;
-use std::hash::RandomState;
 use std::sync::{Arc, LazyLock};
-use std::{env, path::PathBuf};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
 use std::{fmt::Display, fs, io::Read};
 use strum::{Display, EnumIter, IntoEnumIterator};
+use tokio::sync::Semaphore;
 use tokio::task::JoinHandle;
 use tracing::{self, error, info};
 
@@ -75,134 +101,343 @@ pub fn calc_sha512(filename: &str) -> String {
     hex::encode(hash)
 }
 
+/// The clients of the sources that keep settings or credentials, shared by everything looking
+/// mods up. Sources left out of the build have no client.
+#[derive(Clone)]
+pub struct Clients {
+    #[cfg(feature = "github")]
+    pub github: GHReleasesAPI,
+    #[cfg(feature = "curseforge")]
+    pub curseforge: CurseForgeAPI,
+}
+
+impl Clients {
+    /// Sets the clients up from the environment and the config files, with `github_token` taking
+    /// precedence over the GitHub token found there.
+    pub fn from_env(github_token: Option<String>) -> Self {
+        #[cfg(not(feature = "github"))]
+        let _ = github_token;
+        Self {
+            #[cfg(feature = "github")]
+            github: GHReleasesAPI::from_env(github_token),
+            #[cfg(feature = "curseforge")]
+            curseforge: CurseForgeAPI::from_env(),
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn update_dir(
-    github: &mut GHReleasesAPI,
-    curseforge: CurseForgeAPI,
+    clients: &Clients,
     dir: &str,
     new_version: &str,
     del_prev: bool,
     prefix: &str,
     source: Option<Source>,
-    no_other_sources: bool,
+    priority: &[Source],
+    fallback: bool,
     loader: Option<ModLoader>,
-) -> Result<()> {
+    channel: Option<Channel>,
+    concurrency: Option<usize>,
+    collect_changelogs: bool,
+) -> Result<Vec<ModChangelog>> {
     let mut handles = Vec::new();
-    let github = Arc::new(github.clone());
-    let curseforge = Arc::new(curseforge.clone());
-    let source = source.clone().unwrap_or(Source::Modrinth);
+    let clients = Arc::new(clients.clone());
+    let permits = Arc::new(Semaphore::new(
+        concurrency.unwrap_or(Semaphore::MAX_PERMITS).max(1),
+    ));
+    let dir_metadata = DirMetadata::load(Path::new(dir))?;
+    // Packs aren't built for a loader, so they are updated whatever the loader
+    let content_type = ContentType::from_dir(Path::new(dir));
+    let loader = loader.filter(|_| content_type.is_mod());
+    let mut held = Vec::new();
     for entry in fs::read_dir(dir).unwrap() {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if let Some(pin) = dir_metadata.get_pin(&file_name) {
+            match &pin.version {
+                Some(version) => info!("Holding {} at {} (pinned)", file_name, version),
+                None => info!("Holding {} (pinned)", file_name),
+            }
+            held.push(file_name);
+            continue;
+        }
         let new_version = new_version.to_string();
         let loader = loader.clone();
         let prefix = prefix.to_string();
-        let source = source.clone();
-        let github = github.clone();
-        let curseforge = curseforge.clone();
-        let handle: JoinHandle<Result<()>> = tokio::spawn(async move {
-            let entry = entry.unwrap();
+        let sources = source_order(
+            source.as_ref(),
+            dir_metadata.get_sources(&file_name).unwrap_or(priority),
+            fallback,
+            content_type,
+        );
+        let clients = clients.clone();
+        let dir = dir.to_string();
+        let origin = dir_metadata.get_origin(&file_name).cloned();
+        let artifact = dir_metadata.get_artifact(&file_name).cloned();
+        let permits = permits.clone();
+        let handle: JoinHandle<Result<Option<ModChangelog>>> = tokio::spawn(async move {
+            let _permit = permits.acquire().await?;
             let path = entry.path();
-            if path.is_file() && path.extension().unwrap_or(OsStr::new("")) == "jar" {
+            let mut changelog = None;
+            let is_jar = path.is_file()
+                && path.extension().unwrap_or(OsStr::new("")) == "jar"
+                && content_type.is_mod();
+            let is_pack = path.is_file()
+                && !content_type.is_mod()
+                && content_type
+                    .matches_file(&path.file_name().unwrap_or_default().to_string_lossy());
+            // Mods added from a url, file or Maven repository can only come from there again
+            if let Some(origin) = origin {
+                if is_jar || is_pack {
+                    update_from_origin(Path::new(&dir), &path, &origin, del_prev).await;
+                }
+                return Ok(None);
+            }
+            if let Some(artifact) = artifact {
+                if is_jar {
+                    match maven::update(&path, &artifact, &new_version, loader).await {
+                        Ok(Some(new_path)) => finish_update(
+                            Path::new(&dir),
+                            &path,
+                            &new_path,
+                            Source::Maven,
+                            del_prev,
+                        ),
+                        Ok(None) => info!("{:?} is up to date", path),
+                        Err(err) => error!("Failed to update {:?} from Maven: {}", path, err),
+                    }
+                }
+                return Ok(None);
+            }
+            if is_jar || is_pack {
                 info!("Updating {:?}", path);
-                let success: Result<()> = match source {
-                    Source::Modrinth => modrinth::update_from_file(
-                        path.to_str().unwrap(),
+                for (i, source) in sources.iter().enumerate() {
+                    if i > 0 {
+                        info!("Trying to update {} with {}", path.display(), source);
+                    }
+                    let success = update_file(
+                        source,
+                        &clients,
+                        &path,
                         &new_version,
                         &prefix,
                         loader.clone(),
+                        channel,
                     )
-                    .await
-                    .map_err(|err| err.into()),
-                    Source::Github => {
-                        update_file_github(
-                            (*github).clone(),
-                            path.to_str().unwrap(),
-                            &new_version,
-                            &prefix,
-                        )
-                        .await
-                    }
-                    Source::CurseForge => {
-                        update_file_curseforge(
-                            (*curseforge).clone(),
-                            path.to_str().unwrap(),
-                            &new_version,
-                            &prefix,
-                        )
-                        .await
-                    }
-                };
-                if success.is_err() && no_other_sources {
-                    let mut set = HashSet::<Source, RandomState>::from_iter(Source::iter());
-                    set.remove(&source);
-                    for source in set {
-                        let loader = loader.clone();
-                        info!(
-                            "Trying to update {} with {}",
-                            path.to_str().unwrap(),
-                            source
-                        );
-                        let success: Result<()> = match source {
-                            Source::Modrinth => modrinth::update_from_file(
-                                path.to_str().unwrap(),
-                                &new_version,
-                                &prefix,
-                                loader,
-                            )
-                            .await
-                            .map_err(|err| err.into()),
-                            Source::Github => {
-                                update_file_github(
-                                    (*github).clone(),
-                                    path.to_str().unwrap(),
-                                    &new_version,
-                                    &prefix,
-                                )
-                                .await
+                    .await;
+                    match success {
+                        Ok(new_path) => {
+                            if i > 0 {
+                                info!("Successfully updated {} with {}", path.display(), source);
                             }
-                            Source::CurseForge => {
-                                update_file_curseforge(
-                                    (*curseforge).clone(),
-                                    path.to_str().unwrap(),
-                                    &new_version,
-                                    &prefix,
-                                )
-                                .await
-                            }
-                        };
-                        match success {
-                            Ok(_) => {
-                                info!(
-                                    "Successfully updated {} with {}",
-                                    path.to_str().unwrap(),
-                                    source
-                                );
-                                if del_prev && path.ends_with(entry.file_name()) {
-                                    fs::remove_file(path).unwrap();
-                                }
-
-                                break;
-                            }
-                            Err(err) => {
-                                error!(
-                                    "Failed to update {} with {}: {err}",
-                                    path.to_str().unwrap(),
-                                    source
-                                );
-                                continue;
+                            if collect_changelogs {
+                                changelog =
+                                    collect_changelog(source, &clients, &path, &new_path).await;
                             }
+                            finish_update(
+                                Path::new(&dir),
+                                &path,
+                                &new_path,
+                                source.clone(),
+                                del_prev,
+                            );
+                            break;
+                        }
+                        Err(err) => {
+                            error!("Failed to update {} with {}: {err}", path.display(), source)
                         }
                     }
                 }
             }
 
-            Ok(())
+            Ok(changelog)
         });
         handles.push(handle);
     }
+    let mut changelogs = Vec::new();
     for handle in handles {
-        handle.await??;
+        changelogs.extend(handle.await??);
+    }
+    if !held.is_empty() {
+        info!("Held {} pinned mod(s): {}", held.len(), held.join(", "));
+    }
+    changelogs.sort_by_key(|changelog| changelog.name.to_lowercase());
+    Ok(changelogs)
+}
+
+/// The sources to update a mod from, in the order they are tried: `source` first if given, then
+/// `priority`, or every source if that is empty. Sources that can't update the content are left
+/// out, and without `fallback` only the first one is kept.
+pub fn source_order(
+    source: Option<&Source>,
+    priority: &[Source],
+    fallback: bool,
+    content_type: ContentType,
+) -> Vec<Source> {
+    let priority = if priority.is_empty() {
+        Source::iter().collect_vec()
+    } else {
+        priority.to_vec()
+    };
+    let sources = source
+        .cloned()
+        .into_iter()
+        .chain(priority)
+        .filter(|source| !source.is_direct() && source.is_enabled())
+        // Only Modrinth and CurseForge host packs
+        .filter(|source| {
+            content_type.is_mod() || matches!(source, Source::Modrinth | Source::CurseForge)
+        })
+        .unique();
+    if fallback {
+        sources.collect()
+    } else {
+        sources.take(1).collect()
+    }
+}
+
+/// Updates a mod added from a url or a local file from where it came from. Failures are only
+/// logged, like those of the other sources.
+async fn update_from_origin(dir: &Path, path: &Path, origin: &Origin, del_prev: bool) {
+    if !origin.check_updates && origin.location.starts_with("http") {
+        info!(
+            "Skipping {:?}, added from {} without --check-updates",
+            path, origin.location
+        );
+        return;
+    }
+    info!("Checking {} for a newer {:?}", origin.location, path);
+    match direct::update(path, origin).await {
+        Ok(Some(new_path)) => finish_update(dir, path, &new_path, origin.source(), del_prev),
+        Ok(None) => info!("{:?} is up to date", path),
+        Err(err) => error!(
+            "Failed to update {:?} from {}: {}",
+            path, origin.location, err
+        ),
+    }
+}
+
+/// Collects the changelog of an update, skipping mods that were already up to date. Failures are
+/// only logged, since the update itself went through.
+async fn collect_changelog(
+    source: &Source,
+    clients: &Clients,
+    old_path: &Path,
+    new_path: &Path,
+) -> Option<ModChangelog> {
+    match ModChangelog::collect(source, clients, old_path, new_path).await {
+        Ok(changelog) if changelog.from == changelog.to => None,
+        Ok(changelog) => Some(changelog),
+        Err(err) => {
+            error!("Failed to collect the changelog of {:?}: {}", old_path, err);
+            None
+        }
+    }
+}
+
+/// Updates the mod at `path` using `source`, returning the path of the downloaded file.
+#[cfg_attr(
+    not(all(feature = "modrinth", feature = "curseforge", feature = "github")),
+    allow(unused_variables)
+)]
+async fn update_file(
+    source: &Source,
+    clients: &Clients,
+    path: &Path,
+    new_version: &str,
+    prefix: &str,
+    loader: Option<ModLoader>,
+    channel: Option<Channel>,
+) -> Result<PathBuf> {
+    let filename = path.to_str().unwrap();
+    match source {
+        #[cfg(feature = "modrinth")]
+        Source::Modrinth => {
+            modrinth::update_from_file(filename, new_version, prefix, loader, channel)
+                .await
+                .map_err(|err| err.into())
+        }
+        #[cfg(feature = "github")]
+        Source::Github | Source::Gitea | Source::Gitlab => {
+            update_file_github(
+                clients.github.clone(),
+                source,
+                filename,
+                new_version,
+                prefix,
+                loader,
+            )
+            .await
+        }
+        #[cfg(feature = "curseforge")]
+        Source::CurseForge => {
+            update_file_curseforge(clients.curseforge.clone(), filename, new_version, prefix).await
+        }
+        Source::Hangar => update_file_hangar(path, new_version, loader).await,
+        Source::Maven | Source::Url | Source::Local => bail!(
+            "{} can only be updated from where it was added from",
+            path.display()
+        ),
+        #[allow(unreachable_patterns)]
+        _ => bail!(source.disabled_error()),
+    }
+}
+
+/// Carries the directory metadata of `old_path` over to its replacement `new_path` and records
+/// that `source` satisfied the update, deleting the old file if `del_prev` is set.
+fn finish_update(dir: &Path, old_path: &Path, new_path: &Path, source: Source, del_prev: bool) {
+    let old_name = old_path.file_name().unwrap_or_default().to_string_lossy();
+    let new_name = new_path.file_name().unwrap_or_default().to_string_lossy();
+    let remove_old = del_prev && old_name != new_name;
+    let res = DirMetadata::modify(dir, |metadata| {
+        metadata.carry_over(&old_name, &new_name, remove_old);
+        metadata.set_updated_from(&new_name, source);
+    });
+    if let Err(err) = res {
+        error!("Failed to record the update of {}: {}", old_name, err);
+    }
+    if remove_old && let Err(err) = fs::remove_file(old_path) {
+        error!("Failed to delete {}: {}", old_name, err);
+    }
+}
+
+/// Records the mod at `path` in the metadata of the directory it is in. Failures are only logged,
+/// since the mod itself was installed fine.
+pub fn record_install(path: &Path, source: Source, project_id: &str, parent: Option<&str>) {
+    let dir = path.parent().unwrap_or(Path::new("./"));
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let res = DirMetadata::record_install(dir, &file_name, source, project_id, parent);
+    if let Err(err) = res {
+        error!("Failed to record {}: {}", file_name, err);
+    }
+}
+
+/// The last component of a file name a server picked, so that it can't point outside the
+/// directory it is saved into. `None` if nothing usable is left.
+pub fn safe_file_name(name: &str) -> Option<&str> {
+    let name = name.rsplit(['/', '\\']).next()?;
+    (!name.is_empty() && name != "." && name != "..").then_some(name)
+}
+
+/// Finds the mods in `dir` (enabled or disabled) whose file name contains `query`, ignoring case.
+/// In a resource pack, shader pack or datapack folder, the packs are looked at instead.
+pub fn find_mod_files(dir: &Path, query: &str) -> Result<Vec<PathBuf>> {
+    let query = query.to_lowercase();
+    let content_type = ContentType::from_dir(dir);
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(file_name) = path.file_name().and_then(OsStr::to_str) else {
+            continue;
+        };
+        let file_name = file_name.to_lowercase();
+        if content_type.matches_file(&file_name) && file_name.contains(&query) {
+            files.push(path);
+        }
     }
-    Ok(())
+    files.sort();
+    Ok(files)
 }
 
 pub fn get_minecraft_dir() -> PathBuf {
@@ -248,12 +483,24 @@ impl fmt::Display for Link {
     }
 }
 
+/// The characters left alone in query keys, values and path segments: everything except the
+/// unreserved characters of RFC 3986 gets percent-encoded.
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Builds request urls for all the API wrappers, percent-encoding path segments and query
+/// parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UrlBuilder {
     pub base: String,
     pub path: String,
     pub params: Vec<(String, String)>,
 }
 impl UrlBuilder {
+    /// Starts a url at `base` joined with `path`, which is used as is.
     pub fn new(base: &str, path: &str) -> Self {
         Self {
             base: base.to_string(),
@@ -261,37 +508,70 @@ impl UrlBuilder {
             params: Vec::new(),
         }
     }
-    fn add_param(&mut self, key: &str, value: &str) {
+    /// Appends a percent-encoded path segment.
+    pub fn segment(mut self, segment: impl Display) -> Self {
+        self.path.push('/');
+        self.path
+            .extend(utf8_percent_encode(&segment.to_string(), URL_ENCODE_SET));
+        self
+    }
+    /// Adds a query parameter. Adding the same key again repeats it in the url.
+    pub fn param(mut self, key: &str, value: impl Display) -> Self {
         self.params.push((key.to_string(), value.to_string()));
+        self
+    }
+    /// Adds the parameter `key` once for every value.
+    pub fn params<I, V>(mut self, key: &str, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Display,
+    {
+        for value in values {
+            self = self.param(key, value);
+        }
+        self
+    }
+    /// Adds a parameter holding `value` as JSON, like Modrinth's `game_versions=["1.21"]`.
+    pub fn json_param(self, key: &str, value: &impl Serialize) -> Self {
+        let value = serde_json::to_string(value).unwrap_or_default();
+        self.param(key, value)
+    }
+    pub fn build(&self) -> std::result::Result<url::Url, url::ParseError> {
+        url::Url::parse(&self.to_string())
     }
 }
 
 impl fmt::Display for UrlBuilder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut url = self.base.clone();
-        url.push_str(&self.path);
-        if self.params.is_empty() {
-            return write!(f, "{}", url);
-        }
-        let mut iter = self.params.iter();
-        let first = iter.next().unwrap();
-        url.push('?');
-        url.push_str(&first.0);
-        url.push('=');
-        url.push_str(&first.1);
-        for param in iter {
-            url.push('&');
-            url.push_str(&param.0);
-            url.push('=');
-            url.push_str(&param.1);
+        write!(f, "{}{}", self.base, self.path)?;
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            write!(
+                f,
+                "{}{}={}",
+                if i == 0 { '?' } else { '&' },
+                utf8_percent_encode(key, URL_ENCODE_SET),
+                utf8_percent_encode(value, URL_ENCODE_SET)
+            )?;
         }
-        write!(f, "{}", url)
+        Ok(())
     }
 }
 
 #[derive(
-    Debug, clap::ValueEnum, PartialEq, Default, Eq, Clone, Display, Hash, EnumIter, strum::AsRefStr,
+    Debug,
+    PartialEq,
+    Default,
+    Eq,
+    Clone,
+    Display,
+    Hash,
+    EnumIter,
+    strum::AsRefStr,
+    Serialize,
+    Deserialize,
 )]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
 pub enum ModLoader {
     Forge,
     #[default]
@@ -300,58 +580,152 @@ pub enum ModLoader {
     NeoForge,
     Cauldron,
     LiteLoader,
+    /// Paper servers, which also load Spigot and Bukkit plugins
+    Paper,
+    /// Spigot servers, which also load Bukkit plugins
+    Spigot,
+    Bukkit,
+    /// The Velocity proxy
+    Velocity,
     Any,
 }
 
+impl ModLoader {
+    /// Whether the loader runs server plugins rather than mods.
+    pub fn is_plugin_loader(&self) -> bool {
+        matches!(
+            self,
+            ModLoader::Paper | ModLoader::Spigot | ModLoader::Bukkit | ModLoader::Velocity
+        )
+    }
+
+    /// Whether the loader can also load mods or plugins built for `other`, like Quilt loading
+    /// Fabric mods and Paper loading Bukkit plugins.
+    pub fn loads(&self, other: &ModLoader) -> bool {
+        self == other
+            || matches!(
+                (self, other),
+                (ModLoader::Quilt, ModLoader::Fabric)
+                    | (ModLoader::Paper, ModLoader::Spigot | ModLoader::Bukkit)
+                    | (ModLoader::Spigot, ModLoader::Bukkit)
+            )
+    }
+
+    /// Parses a loader as Modrinth and Hangar name them, e.g. `neoforge` or `PAPER`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let loader = match name.to_lowercase().as_str() {
+            "fabric" => ModLoader::Fabric,
+            "forge" => ModLoader::Forge,
+            "quilt" => ModLoader::Quilt,
+            "neoforge" => ModLoader::NeoForge,
+            "cauldron" => ModLoader::Cauldron,
+            "liteloader" => ModLoader::LiteLoader,
+            "paper" => ModLoader::Paper,
+            "spigot" => ModLoader::Spigot,
+            "bukkit" => ModLoader::Bukkit,
+            "velocity" => ModLoader::Velocity,
+            _ => return None,
+        };
+        Some(loader)
+    }
+}
+
+/// Updates a plugin downloaded from Hangar, which is recognised by its install record since
+/// Hangar can't look plugins up by hash.
+pub async fn update_file_hangar(
+    path: &Path,
+    new_version: &str,
+    loader: Option<ModLoader>,
+) -> Result<PathBuf> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let dir_metadata = DirMetadata::load(path.parent().unwrap_or(Path::new("./")))?;
+    let Some(record) = dir_metadata
+        .get_install(&file_name)
+        .filter(|record| record.source == Source::Hangar)
+    else {
+        bail!("{} was not installed from Hangar", file_name);
+    };
+    // Like `add`, fall back to Paper when the configured loader has no plugins
+    let loader = loader
+        .filter(ModLoader::is_plugin_loader)
+        .unwrap_or(ModLoader::Paper);
+    info!("Checking Hangar for {}", record.project_id);
+    let path = hangar_wrapper::HangarAPI::new()
+        .update(&record.project_id, new_version, &loader, path)
+        .await?;
+    Ok(path)
+}
+
+/// Updates a mod downloaded from the releases of a git forge. `forge_source` is the forge the
+/// mod was downloaded from, one of the sources for which [`Source::is_forge`] holds.
+#[cfg(feature = "github")]
 pub async fn update_file_github(
     github: GHReleasesAPI,
+    forge_source: &Source,
     filename: &str,
     new_version: &str,
     prefix: &str,
-) -> Result<()> {
+    loader: Option<ModLoader>,
+) -> Result<PathBuf> {
     let metadata = Metadata::get_all_metadata(PathBuf::from(filename));
     let Ok(metadata) = metadata else {
         bail!("Could not find metadata for {}", filename);
     };
     let source: Result<Source> = match metadata.get("source") {
-        Some(source) => Ok(Source::from_str(source, true).unwrap()),
+        Some(source) => source.as_str().try_into().map_err(|err: String| eyre!(err)),
         None => bail!("No key found"),
     };
 
-    if let Ok(Source::Github) = source {
-        info!("Checking Github for mod");
-        let repo = metadata.get("repo");
-        if repo.is_none() {
-            bail!("Could not find repo for {}", filename);
-        }
-        let repo = repo.unwrap();
-        let split = repo.split("/").collect_vec();
-        let update = github.get_releases(split[0].trim(), split[1]).await;
-
-        if update.is_err() {
-            bail!(
-                "Could not find update for {}: {:?}",
-                filename,
-                update.err().unwrap()
-            );
-        }
-        let update = update.unwrap();
-        let mod_ = gh_releases::get_mod_from_release(&update, "fabric", new_version).await?;
-        mod_.download(format!("{}/{}", prefix, mod_.name).into(), split.join("/"))
-            .await
-            .unwrap();
-        Ok(())
-    } else {
-        Err(Error::NoReleases)?
+    match source {
+        Ok(source) if source == *forge_source && source.is_forge() => {}
+        _ => Err(Error::NoReleases)?,
+    }
+    info!("Checking {} for mod", forge_source);
+    let Some(repo) = metadata.get("repo") else {
+        bail!("Could not find repo for {}", filename);
+    };
+    let Some(spec) = gh_releases::RepoSpec::parse(repo) else {
+        bail!("Invalid repo {} for {}", repo, filename);
+    };
+    let Some(github) = github.for_repo(&spec, Some(forge_source)) else {
+        bail!("Unknown forge for {}", repo);
+    };
+    // Without a loader, stay on the one the installed jar was built for
+    let loader = loader
+        .or_else(|| {
+            let name = Path::new(filename).file_name()?.to_string_lossy();
+            gh_releases::loader_from_name(&name)
+        })
+        .unwrap_or(ModLoader::Any);
+    // The pattern is kept next to the mod, which `prefix` only happens to match
+    let dir = Path::new(filename).parent().unwrap_or(Path::new("./"));
+    let pattern = DirMetadata::load(dir)?
+        .get_asset_pattern(repo)
+        .map(gh_releases::AssetPattern::parse)
+        .transpose()?;
+    let selector = gh_releases::AssetSelector::new(new_version, loader).pattern(pattern);
+    let update = github
+        .download_mod(
+            &spec.owner,
+            &spec.repo,
+            &selector,
+            Path::new(prefix),
+            forge_source,
+        )
+        .await;
+    match update {
+        Ok(path) => Ok(path),
+        Err(err) => bail!("Could not find update for {}: {:?}", filename, err),
     }
 }
 
+#[cfg(feature = "curseforge")]
 pub async fn update_file_curseforge(
     curseforge: CurseForgeAPI,
     filename: &str,
     new_version: &str,
     prefix: &str,
-) -> Result<()> {
+) -> Result<PathBuf> {
     let mod_ = curseforge
         .get_mod_from_file(PathBuf::from(filename))
         .await?;
@@ -362,8 +736,91 @@ pub async fn update_file_curseforge(
     let Some(new_version) = new_mod.get_version_and_loader(new_version) else {
         bail!("Version {new_version} not found for {filename}");
     };
-    curseforge
+    let path = curseforge
         .download_mod(new_mod.id, new_version.file_id, prefix.into())
         .await?;
-    Ok(())
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_url_builder_encodes_params() {
+        let url = UrlBuilder::new("https://api.modrinth.com/v2", "/project")
+            .segment("fabric api")
+            .segment("version")
+            .json_param("game_versions", &["1.21"])
+            .param("query", "rock & stone #1 ü")
+            .params("id", [1, 2]);
+        assert_eq!(
+            url.to_string(),
+            "https://api.modrinth.com/v2/project/fabric%20api/version\
+             ?game_versions=%5B%221.21%22%5D&query=rock%20%26%20stone%20%231%20%C3%BC&id=1&id=2"
+        );
+        assert!(url.build().is_ok());
+    }
+
+    #[test]
+    fn test_safe_file_name() {
+        assert_eq!(safe_file_name("sodium-0.6.0.jar"), Some("sodium-0.6.0.jar"));
+        assert_eq!(safe_file_name("../../.bashrc"), Some(".bashrc"));
+        assert_eq!(safe_file_name("..\\plugins\\evil.jar"), Some("evil.jar"));
+        assert_eq!(safe_file_name("plugins/.."), None);
+        assert_eq!(safe_file_name(""), None);
+    }
+
+    #[test]
+    fn test_find_mod_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for file_name in ["sodium.jar", "iris.jar.disabled", "notes.txt"] {
+            fs::write(dir.path().join(file_name), "").unwrap();
+        }
+        let mut metadata = DirMetadata::default();
+        metadata.pin("sodium.jar", None);
+        metadata.save(dir.path()).unwrap();
+
+        let file_names = |query| {
+            find_mod_files(dir.path(), query)
+                .unwrap()
+                .into_iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+                .collect_vec()
+        };
+        // The metadata file is never taken for a mod, so e.g. `toggle` can't disable it
+        assert_eq!(file_names(""), ["iris.jar.disabled", "sodium.jar"]);
+        assert_eq!(file_names("SODIUM"), ["sodium.jar"]);
+    }
+
+    #[test]
+    fn test_source_order() {
+        let priority = [Source::CurseForge, Source::Url, Source::Modrinth];
+        assert_eq!(
+            source_order(Some(&Source::Modrinth), &priority, true, ContentType::Mod),
+            vec![Source::Modrinth, Source::CurseForge]
+        );
+        assert_eq!(
+            source_order(None, &priority, false, ContentType::Mod),
+            vec![Source::CurseForge]
+        );
+        assert_eq!(
+            source_order(Some(&Source::Github), &[], true, ContentType::Shader),
+            vec![Source::Modrinth, Source::CurseForge]
+        );
+        // Without a priority every source is tried, always in the same order
+        assert_eq!(
+            source_order(None, &[], true, ContentType::Mod),
+            vec![
+                Source::Modrinth,
+                Source::Github,
+                Source::CurseForge,
+                Source::Gitea,
+                Source::Gitlab,
+                Source::Hangar,
+                Source::Maven
+            ]
+        );
+    }
 }
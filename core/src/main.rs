@@ -2,10 +2,9 @@ use color_eyre::eyre::Result;
 mod actions;
 use clap::Parser;
 use cli::*;
-use futures::lock::Mutex;
 use modder::*;
 use std::{collections::HashSet, sync::Arc};
-use tracing::{error, info, level_filters::LevelFilter};
+use tracing::{error, info, level_filters::LevelFilter, warn};
 use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
@@ -0,0 +1,774 @@
+use crate::ModLoader;
+use itertools::Itertools;
+use serde::Deserialize;
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::Display,
+    fs::File,
+    io::{Cursor, Read, Seek},
+    path::Path,
+};
+
+/// Where each loader expects its metadata inside a jar, most specific first.
+const MANIFEST_FILES: [(&str, ModLoader); 4] = [
+    ("fabric.mod.json", ModLoader::Fabric),
+    ("quilt.mod.json", ModLoader::Quilt),
+    ("META-INF/neoforge.mods.toml", ModLoader::NeoForge),
+    ("META-INF/mods.toml", ModLoader::Forge),
+];
+
+/// Where Fabric and Forge keep the jars nested in a mod.
+const NESTED_JAR_DIRS: [&str; 2] = ["META-INF/jars/", "META-INF/jarjar/"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Error reading the jar file: {0}")]
+    IOErr(#[from] std::io::Error),
+    #[error("Error unarchiving the jar file: {0}")]
+    Unzip(#[from] zip::result::ZipError),
+    #[error("Error parsing the mod metadata: {0}")]
+    JsonErr(#[from] serde_json::Error),
+    #[error("Error parsing the mod metadata: {0}")]
+    TomlErr(#[from] toml::de::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// The metadata a mod declares for its loader, e.g. in `fabric.mod.json` or `mods.toml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModManifest {
+    pub id: String,
+    pub name: Option<String>,
+    pub version: String,
+    pub loader: ModLoader,
+    /// Required dependencies by mod id, including `minecraft` and the loader itself.
+    pub depends: BTreeMap<String, VersionRange>,
+    /// Mods it can't be loaded with, by mod id: Fabric's `breaks`, or incompatible
+    /// dependencies in `mods.toml`.
+    pub breaks: BTreeMap<String, VersionRange>,
+    /// Mods it may misbehave with, by mod id: Fabric's `conflicts`, or discouraged
+    /// dependencies in `mods.toml`.
+    pub conflicts: BTreeMap<String, VersionRange>,
+    /// Other mod ids it stands in for, from Fabric's `provides`.
+    pub provides: Vec<String>,
+}
+
+impl ModManifest {
+    /// Reads every manifest in the jar at `path`. Jars built for several loaders have one per
+    /// loader, and `mods.toml` can declare more than one mod.
+    pub fn read_jar(path: &Path) -> Result<Vec<Self>> {
+        Self::read_archive(&mut zip::ZipArchive::new(File::open(path)?)?)
+    }
+
+    /// Like [`ModManifest::read_jar`], but also reads the jars nested in it, which are loaded
+    /// along with it. Nested jars whose manifests can't be read are skipped.
+    pub fn read_jar_nested(path: &Path) -> Result<Vec<Self>> {
+        let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+        let mut manifests = Self::read_archive(&mut archive)?;
+        let nested = archive
+            .file_names()
+            .filter(|name| {
+                name.ends_with(".jar") && NESTED_JAR_DIRS.iter().any(|dir| name.starts_with(dir))
+            })
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        for name in nested {
+            let mut bytes = Vec::new();
+            archive.by_name(&name)?.read_to_end(&mut bytes)?;
+            let Ok(mut nested) = zip::ZipArchive::new(Cursor::new(bytes)) else {
+                continue;
+            };
+            manifests.extend(Self::read_archive(&mut nested).unwrap_or_default());
+        }
+        Ok(manifests)
+    }
+
+    fn read_archive<R: Read + Seek>(archive: &mut zip::ZipArchive<R>) -> Result<Vec<Self>> {
+        let mut manifests = Vec::new();
+        for (file_name, loader) in MANIFEST_FILES {
+            let Ok(mut file) = archive.by_name(file_name) else {
+                continue;
+            };
+            let mut contents = String::new();
+            file.read_to_string(&mut contents)?;
+            match loader {
+                ModLoader::Fabric => manifests.push(Self::parse_fabric(&contents)?),
+                ModLoader::Quilt => manifests.push(Self::parse_quilt(&contents)?),
+                _ => manifests.extend(Self::parse_mods_toml(&contents, loader)?),
+            }
+        }
+        Ok(manifests)
+    }
+
+    pub fn parse_fabric(contents: &str) -> Result<Self> {
+        let manifest: FabricManifest = serde_json::from_str(contents)?;
+        Ok(Self {
+            id: manifest.id,
+            name: manifest.name,
+            version: manifest.version,
+            loader: ModLoader::Fabric,
+            depends: ranges(manifest.depends),
+            breaks: ranges(manifest.breaks),
+            conflicts: ranges(manifest.conflicts),
+            provides: manifest.provides,
+        })
+    }
+
+    pub fn parse_quilt(contents: &str) -> Result<Self> {
+        let manifest: QuiltManifest = serde_json::from_str(contents)?;
+        let loader = manifest.quilt_loader;
+        let required = |dependencies: Vec<QuiltDependency>| {
+            dependencies
+                .into_iter()
+                .filter_map(|dependency| match dependency {
+                    QuiltDependency::Id(id) => Some((id, VersionRange::any())),
+                    QuiltDependency::Object {
+                        id,
+                        versions,
+                        optional,
+                    } => (!optional)
+                        .then(|| (id, versions.map_or_else(VersionRange::any, Into::into))),
+                })
+                .collect()
+        };
+        Ok(Self {
+            id: loader.id,
+            name: loader.metadata.and_then(|metadata| metadata.name),
+            version: loader.version,
+            loader: ModLoader::Quilt,
+            depends: required(loader.depends),
+            breaks: required(loader.breaks),
+            conflicts: BTreeMap::new(),
+            provides: Vec::new(),
+        })
+    }
+
+    /// Parses a Forge or NeoForge `mods.toml`. Mods depending on `neoforge` are NeoForge mods,
+    /// whatever file they were read from.
+    pub fn parse_mods_toml(contents: &str, loader: ModLoader) -> Result<Vec<Self>> {
+        let manifest: ModsToml = toml::from_str(contents)?;
+        let manifests = manifest
+            .mods
+            .into_iter()
+            .map(|mod_| {
+                let of_kind = |kind: &str| -> BTreeMap<String, VersionRange> {
+                    manifest
+                        .dependencies
+                        .get(&mod_.mod_id)
+                        .into_iter()
+                        .flatten()
+                        .filter(|dependency| dependency.is_kind(kind))
+                        .map(|dependency| {
+                            let range = dependency
+                                .version_range
+                                .as_deref()
+                                .map_or_else(VersionRange::any, VersionRange::parse_maven);
+                            (dependency.mod_id.clone(), range)
+                        })
+                        .collect()
+                };
+                let depends = of_kind("required");
+                let breaks = of_kind("incompatible");
+                let conflicts = of_kind("discouraged");
+                let loader = if depends.contains_key("neoforge") {
+                    ModLoader::NeoForge
+                } else {
+                    loader.clone()
+                };
+                Self {
+                    id: mod_.mod_id,
+                    name: mod_.display_name,
+                    version: mod_.version.unwrap_or_default(),
+                    loader,
+                    depends,
+                    breaks,
+                    conflicts,
+                    provides: Vec::new(),
+                }
+            })
+            .collect();
+        Ok(manifests)
+    }
+
+    /// Whether the mod can be loaded by `loader`. Quilt loads Fabric mods.
+    pub fn supports_loader(&self, loader: &ModLoader) -> bool {
+        *loader == ModLoader::Any || loader.loads(&self.loader)
+    }
+
+    /// Whether the mod declares support for `game_version`. Mods that don't declare a Minecraft
+    /// dependency are assumed to support it.
+    pub fn supports_game_version(&self, game_version: &str) -> bool {
+        self.depends
+            .get("minecraft")
+            .is_none_or(|range| range.matches(game_version))
+    }
+}
+
+fn ranges(requirements: BTreeMap<String, FabricRequirement>) -> BTreeMap<String, VersionRange> {
+    requirements
+        .into_iter()
+        .map(|(id, requirement)| (id, requirement.into()))
+        .collect()
+}
+
+/// Explains why none of the `manifests` of a jar can be loaded by `loader`, or returns `None` if
+/// one can or there are none.
+pub fn loader_issue(manifests: &[ModManifest], loader: &ModLoader) -> Option<String> {
+    if manifests.is_empty()
+        || manifests
+            .iter()
+            .any(|manifest| manifest.supports_loader(loader))
+    {
+        return None;
+    }
+    let loaders = manifests
+        .iter()
+        .map(|manifest| manifest.loader.to_string())
+        .sorted()
+        .dedup()
+        .collect::<Vec<_>>();
+    Some(format!("built for {}, not {}", loaders.join(", "), loader))
+}
+
+/// Explains why none of the `manifests` of a jar fit `game_version` and `loader`, or returns
+/// `None` if one does. Jars without manifests can't be checked and are assumed to fit.
+pub fn compatibility_issue(
+    manifests: &[ModManifest],
+    game_version: &str,
+    loader: &ModLoader,
+) -> Option<String> {
+    if manifests.is_empty() {
+        return None;
+    }
+    if let Some(issue) = loader_issue(manifests, loader) {
+        return Some(issue);
+    }
+    let for_loader = manifests
+        .iter()
+        .filter(|manifest| manifest.supports_loader(loader))
+        .collect::<Vec<_>>();
+    if for_loader
+        .iter()
+        .any(|manifest| manifest.supports_game_version(game_version))
+    {
+        return None;
+    }
+    let ranges = for_loader
+        .iter()
+        .filter_map(|manifest| manifest.depends.get("minecraft"))
+        .map(|range| range.to_string())
+        .sorted()
+        .dedup()
+        .collect::<Vec<_>>();
+    Some(format!(
+        "requires Minecraft {}, not {}",
+        ranges.join(" or "),
+        game_version
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparator {
+    op: Op,
+    version: String,
+}
+
+impl Comparator {
+    fn new(op: Op, version: &str) -> Self {
+        Self {
+            op,
+            version: version.to_string(),
+        }
+    }
+    fn matches(&self, version: &str) -> bool {
+        let ordering = compare_versions(version, &self.version);
+        match self.op {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+        }
+    }
+}
+
+/// A requirement on the version of a dependency: any one of several sets of comparators that
+/// all have to hold. Both Fabric's predicates (`>=1.21 <1.22`, `1.21.x`, `~1.21`) and Maven
+/// ranges (`[1.21,1.22)`) as used by Forge are understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionRange {
+    source: String,
+    alternatives: Vec<Vec<Comparator>>,
+}
+
+impl VersionRange {
+    /// Matches every version.
+    pub fn any() -> Self {
+        Self {
+            source: "*".to_string(),
+            alternatives: Vec::new(),
+        }
+    }
+
+    /// Parses a Fabric version predicate, e.g. `>=1.20.5 <1.21.2` or `1.21.x`.
+    pub fn parse_fabric(predicate: &str) -> Self {
+        Self::parse_fabric_alternatives(&[predicate])
+    }
+
+    fn parse_fabric_alternatives(predicates: &[&str]) -> Self {
+        let alternatives = predicates
+            .iter()
+            .map(|predicate| {
+                predicate
+                    .split_whitespace()
+                    .flat_map(parse_fabric_term)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        // A predicate without comparators, like `*`, matches everything
+        let alternatives = if alternatives.iter().any(Vec::is_empty) {
+            Vec::new()
+        } else {
+            alternatives
+        };
+        Self {
+            source: predicates.join(" || "),
+            alternatives,
+        }
+    }
+
+    /// Parses a Maven version range, e.g. `[1.21,1.21.2)` or `[1.20.1]`. A bare version is a
+    /// minimum, like Forge treats it.
+    pub fn parse_maven(range: &str) -> Self {
+        let mut alternatives = Vec::new();
+        let mut rest = range.trim();
+        while !rest.is_empty() {
+            let Some(open) = rest.chars().next().filter(|c| *c == '[' || *c == '(') else {
+                if rest != "*" {
+                    alternatives.push(vec![Comparator::new(Op::Ge, rest)]);
+                }
+                break;
+            };
+            let Some(end) = rest.find([']', ')']) else {
+                break;
+            };
+            let close = rest[end..].chars().next().unwrap_or(')');
+            let inner = &rest[1..end];
+            let mut comparators = Vec::new();
+            match inner.split_once(',') {
+                Some((lower, upper)) => {
+                    let (lower, upper) = (lower.trim(), upper.trim());
+                    if !lower.is_empty() {
+                        let op = if open == '[' { Op::Ge } else { Op::Gt };
+                        comparators.push(Comparator::new(op, lower));
+                    }
+                    if !upper.is_empty() {
+                        let op = if close == ']' { Op::Le } else { Op::Lt };
+                        comparators.push(Comparator::new(op, upper));
+                    }
+                }
+                None => comparators.push(Comparator::new(Op::Eq, inner.trim())),
+            }
+            alternatives.push(comparators);
+            rest = rest[end + 1..].trim_start_matches([',', ' ']);
+        }
+        if alternatives.iter().any(Vec::is_empty) {
+            alternatives.clear();
+        }
+        Self {
+            source: range.to_string(),
+            alternatives,
+        }
+    }
+
+    /// Whether every version matches.
+    pub fn is_any(&self) -> bool {
+        self.alternatives.is_empty()
+    }
+
+    pub fn matches(&self, version: &str) -> bool {
+        self.alternatives.is_empty()
+            || self.alternatives.iter().any(|comparators| {
+                comparators
+                    .iter()
+                    .all(|comparator| comparator.matches(version))
+            })
+    }
+}
+
+impl Display for VersionRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Turns one space-separated term of a Fabric predicate into comparators.
+fn parse_fabric_term(term: &str) -> Vec<Comparator> {
+    let (op, version) = [
+        (">=", Some(Op::Ge)),
+        ("<=", Some(Op::Le)),
+        (">", Some(Op::Gt)),
+        ("<", Some(Op::Lt)),
+        ("=", Some(Op::Eq)),
+        ("~", None),
+        ("^", None),
+    ]
+    .into_iter()
+    .find_map(|(prefix, op)| {
+        term.strip_prefix(prefix)
+            .map(|version| (Some((prefix, op)), version))
+    })
+    .unwrap_or((None, term));
+    if version.is_empty() || version == "*" {
+        return Vec::new();
+    }
+    let parts = version.split('.').collect::<Vec<_>>();
+    if let Some(wildcard) = parts
+        .iter()
+        .position(|part| matches!(*part, "x" | "X" | "*"))
+    {
+        // `1.21.x` is everything from 1.21 up to the next minor version
+        let prefix = &parts[..wildcard];
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+        return vec![
+            Comparator::new(Op::Ge, &prefix.join(".")),
+            Comparator::new(Op::Lt, &format!("{}-", bump(prefix))),
+        ];
+    }
+    match op {
+        Some((_, Some(op))) => vec![Comparator::new(op, version)],
+        Some(("~", None)) => {
+            let prefix = &parts[..parts.len().min(2)];
+            vec![
+                Comparator::new(Op::Ge, version),
+                Comparator::new(Op::Lt, &format!("{}-", bump(prefix))),
+            ]
+        }
+        Some(_) => vec![
+            Comparator::new(Op::Ge, version),
+            Comparator::new(Op::Lt, &format!("{}-", bump(&parts[..1]))),
+        ],
+        None => vec![Comparator::new(Op::Eq, version)],
+    }
+}
+
+/// Increments the last numeric part of `parts`, e.g. `1.21` becomes `1.22`.
+fn bump(parts: &[&str]) -> String {
+    let mut parts = parts
+        .iter()
+        .map(|part| part.to_string())
+        .collect::<Vec<_>>();
+    if let Some(last) = parts.last_mut() {
+        *last = last
+            .parse::<u64>()
+            .map_or_else(|_| last.clone(), |n| (n + 1).to_string());
+    }
+    parts.join(".")
+}
+
+/// Compares versions part by part, numerically where both parts are numbers. Build metadata
+/// after `+` is ignored and a pre-release after `-` sorts before the release.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |version: &str| {
+        let version = version.split('+').next().unwrap_or_default().to_string();
+        match version.split_once('-') {
+            Some((core, pre)) => (core.to_string(), Some(pre.to_string())),
+            None => (version, None),
+        }
+    };
+    let ((a_core, a_pre), (b_core, b_pre)) = (split(a), split(b));
+    let a_parts = a_core.split('.').collect::<Vec<_>>();
+    let b_parts = b_core.split('.').collect::<Vec<_>>();
+    for i in 0..a_parts.len().max(b_parts.len()) {
+        let a = a_parts.get(i).copied().unwrap_or("0");
+        let b = b_parts.get(i).copied().unwrap_or("0");
+        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => compare_versions(&a, &b),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FabricRequirement {
+    One(String),
+    AnyOf(Vec<String>),
+}
+
+impl From<FabricRequirement> for VersionRange {
+    fn from(requirement: FabricRequirement) -> Self {
+        match requirement {
+            FabricRequirement::One(predicate) => VersionRange::parse_fabric(&predicate),
+            FabricRequirement::AnyOf(predicates) => {
+                let predicates = predicates.iter().map(String::as_str).collect::<Vec<_>>();
+                VersionRange::parse_fabric_alternatives(&predicates)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FabricManifest {
+    id: String,
+    #[serde(default)]
+    version: String,
+    name: Option<String>,
+    #[serde(default)]
+    depends: BTreeMap<String, FabricRequirement>,
+    #[serde(default)]
+    breaks: BTreeMap<String, FabricRequirement>,
+    #[serde(default)]
+    conflicts: BTreeMap<String, FabricRequirement>,
+    #[serde(default)]
+    provides: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct QuiltManifest {
+    quilt_loader: QuiltLoader,
+}
+
+#[derive(Deserialize)]
+struct QuiltLoader {
+    id: String,
+    #[serde(default)]
+    version: String,
+    metadata: Option<QuiltMetadata>,
+    #[serde(default)]
+    depends: Vec<QuiltDependency>,
+    #[serde(default)]
+    breaks: Vec<QuiltDependency>,
+}
+
+#[derive(Deserialize)]
+struct QuiltMetadata {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QuiltDependency {
+    Id(String),
+    Object {
+        id: String,
+        versions: Option<FabricRequirement>,
+        #[serde(default)]
+        optional: bool,
+    },
+}
+
+#[derive(Deserialize)]
+struct ModsToml {
+    #[serde(default)]
+    mods: Vec<ModsTomlMod>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, Vec<ModsTomlDependency>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsTomlMod {
+    mod_id: String,
+    version: Option<String>,
+    display_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsTomlDependency {
+    mod_id: String,
+    /// Forge's flag for required dependencies.
+    mandatory: Option<bool>,
+    /// NeoForge's replacement for `mandatory`, `required` by default.
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    version_range: Option<String>,
+}
+
+impl ModsTomlDependency {
+    /// Whether the dependency is of NeoForge's `kind`: `required`, `optional`, `incompatible`
+    /// or `discouraged`. Forge only knows mandatory and optional ones.
+    fn is_kind(&self, kind: &str) -> bool {
+        match (&self.kind, self.mandatory) {
+            (Some(own), _) => own.eq_ignore_ascii_case(kind),
+            (None, Some(mandatory)) => kind == if mandatory { "required" } else { "optional" },
+            (None, None) => kind == "required",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_fabric_predicates() {
+        let range = VersionRange::parse_fabric(">=1.21 <1.21.2");
+        assert!(range.matches("1.21"));
+        assert!(range.matches("1.21.1"));
+        assert!(!range.matches("1.21.2"));
+        assert!(!range.matches("1.20.6"));
+        let wildcard = VersionRange::parse_fabric("1.21.x");
+        assert!(wildcard.matches("1.21.4"));
+        assert!(!wildcard.matches("1.22"));
+        assert!(!wildcard.matches("1.22-pre1"));
+        let tilde = VersionRange::parse_fabric("~1.20.5");
+        assert!(tilde.matches("1.20.6"));
+        assert!(!tilde.matches("1.21"));
+        assert!(VersionRange::parse_fabric("1.21").matches("1.21"));
+        assert!(!VersionRange::parse_fabric("1.21").matches("1.21.4"));
+        assert!(VersionRange::parse_fabric("*").matches("1.8.9"));
+        let any_of: VersionRange =
+            FabricRequirement::AnyOf(vec!["1.20.1".to_string(), ">=1.21".to_string()]).into();
+        assert!(any_of.matches("1.20.1"));
+        assert!(any_of.matches("1.21.4"));
+        assert!(!any_of.matches("1.20.4"));
+    }
+
+    #[test]
+    fn test_maven_ranges() {
+        let range = VersionRange::parse_maven("[1.21,1.21.2)");
+        assert!(range.matches("1.21.1"));
+        assert!(!range.matches("1.21.2"));
+        assert!(VersionRange::parse_maven("[1.20.1]").matches("1.20.1"));
+        assert!(!VersionRange::parse_maven("[1.20.1]").matches("1.20.2"));
+        assert!(VersionRange::parse_maven("[1.20,)").matches("1.21.4"));
+        let union = VersionRange::parse_maven("[1.19,1.19.4],[1.20.1,1.21)");
+        assert!(union.matches("1.19.2"));
+        assert!(!union.matches("1.20"));
+        assert!(union.matches("1.20.4"));
+        assert!(VersionRange::parse_maven("47").matches("47.1.3"));
+    }
+
+    #[test]
+    fn test_parse_manifests() {
+        let fabric = ModManifest::parse_fabric(
+            r#"{"schemaVersion": 1, "id": "sodium", "version": "0.6.0", "name": "Sodium",
+                "depends": {"minecraft": ["1.21", "1.21.1"], "fabricloader": ">=0.16"}}"#,
+        )
+        .unwrap();
+        assert_eq!(fabric.id, "sodium");
+        assert!(fabric.supports_game_version("1.21.1"));
+        assert!(!fabric.supports_game_version("1.21.4"));
+        assert!(fabric.supports_loader(&ModLoader::Quilt));
+        assert!(!fabric.supports_loader(&ModLoader::Forge));
+
+        let neoforge = ModManifest::parse_mods_toml(
+            r#"
+            modLoader = "javafml"
+            loaderVersion = "[4,)"
+            [[mods]]
+            modId = "jei"
+            version = "19.0.0"
+            [[dependencies.jei]]
+            modId = "neoforge"
+            type = "required"
+            versionRange = "[21.0,)"
+            [[dependencies.jei]]
+            modId = "minecraft"
+            type = "required"
+            versionRange = "[1.21,1.21.2)"
+            [[dependencies.jei]]
+            modId = "emi"
+            type = "optional"
+            [[dependencies.jei]]
+            modId = "toomanyitems"
+            type = "incompatible"
+            "#,
+            ModLoader::Forge,
+        )
+        .unwrap();
+        assert_eq!(neoforge.len(), 1);
+        assert_eq!(neoforge[0].loader, ModLoader::NeoForge);
+        assert_eq!(
+            neoforge[0].depends.keys().collect::<Vec<_>>(),
+            ["minecraft", "neoforge"]
+        );
+        assert_eq!(
+            neoforge[0].breaks.keys().collect::<Vec<_>>(),
+            ["toomanyitems"]
+        );
+        assert_eq!(
+            compatibility_issue(&neoforge, "1.21.4", &ModLoader::NeoForge),
+            Some("requires Minecraft [1.21,1.21.2), not 1.21.4".to_string())
+        );
+        assert_eq!(
+            compatibility_issue(&neoforge, "1.21.1", &ModLoader::Fabric),
+            Some("built for NeoForge, not Fabric".to_string())
+        );
+        assert_eq!(
+            compatibility_issue(&neoforge, "1.21.1", &ModLoader::NeoForge),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_nested_jars() {
+        use std::io::Write;
+        let jar = |files: &[(&str, &[u8])]| {
+            let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            for (name, contents) in files {
+                zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                    .unwrap();
+                zip.write_all(contents).unwrap();
+            }
+            zip.finish().unwrap().into_inner()
+        };
+        let nested = jar(&[(
+            "fabric.mod.json",
+            br#"{"id": "fabric-api-base", "version": "0.4.0"}"#,
+        )]);
+        let outer = jar(&[
+            (
+                "fabric.mod.json",
+                br#"{"id": "fabric-api", "version": "0.100.0", "provides": ["fabric"]}"#,
+            ),
+            ("META-INF/jars/fabric-api-base-0.4.0.jar", &nested),
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fabric-api.jar");
+        std::fs::write(&path, outer).unwrap();
+
+        assert_eq!(ModManifest::read_jar(&path).unwrap().len(), 1);
+        let manifests = ModManifest::read_jar_nested(&path).unwrap();
+        assert_eq!(
+            manifests
+                .iter()
+                .map(|manifest| manifest.id.as_str())
+                .collect::<Vec<_>>(),
+            ["fabric-api", "fabric-api-base"]
+        );
+        assert_eq!(manifests[0].provides, ["fabric"]);
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("1.21.10", "1.21.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.21", "1.21.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.21-pre1", "1.21"), Ordering::Less);
+        assert_eq!(compare_versions("0.6.0+mc1.21", "0.6.0"), Ordering::Equal);
+    }
+}
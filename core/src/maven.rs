@@ -0,0 +1,539 @@
+use crate::cli::Source;
+use crate::config::Config;
+use crate::gh_releases::{AssetPattern, contains_version};
+use crate::info::ProjectInfo;
+use crate::manifest::{self, ModManifest, compare_versions};
+use crate::metadata::{DirMetadata, MavenArtifact};
+use crate::{Link, ModLoader, UrlBuilder, record_install};
+use itertools::Itertools;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Repositories searched when the config doesn't list any.
+pub const DEFAULT_REPOSITORIES: &[&str] = &[
+    "https://maven.fabricmc.net",
+    "https://maven.neoforged.net/releases",
+    "https://modmaven.dev",
+    "https://repo1.maven.org/maven2",
+];
+/// How many versions are downloaded at most while looking for one that fits.
+const MAX_DOWNLOADED_CANDIDATES: usize = 5;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Error sending the request: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Error reading or writing the artifact: {0}")]
+    IOErr(#[from] std::io::Error),
+    #[error("Error parsing maven-metadata.xml: {0}")]
+    Xml(#[from] quick_xml::DeError),
+    #[error("Error recording the artifact: {0}")]
+    Metadata(#[from] crate::metadata::Error),
+    #[error("Invalid version pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
+    #[error("Invalid coordinate {0}, expected <group>:<artifact>[:<version>[:<classifier>]]")]
+    InvalidCoordinate(String),
+    #[error("Could not find {0} in any Maven repository")]
+    NotFound(String),
+    #[error("No version of {0} fits")]
+    NoMatchingVersion(String),
+    #[error("Checksum mismatch for {file}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A Maven coordinate, written `group:artifact[:version[:classifier]]`. The version may be a glob
+/// or `regex:` pattern, and is left empty to pick a classifier without a version, as in
+/// `group:artifact::classifier`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coordinate {
+    pub group: String,
+    pub artifact: String,
+    pub version: Option<String>,
+    pub classifier: Option<String>,
+}
+
+impl Coordinate {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        if spec.contains(['/', '\\']) || spec.contains(char::is_whitespace) {
+            return None;
+        }
+        let parts = spec.split(':').collect::<Vec<_>>();
+        let non_empty =
+            |part: Option<&&str>| part.filter(|part| !part.is_empty()).map(|p| p.to_string());
+        let (Some(group), Some(artifact)) = (non_empty(parts.first()), non_empty(parts.get(1)))
+        else {
+            return None;
+        };
+        if parts.len() > 4 {
+            return None;
+        }
+        Some(Self {
+            group,
+            artifact,
+            version: non_empty(parts.get(2)),
+            classifier: non_empty(parts.get(3)),
+        })
+    }
+
+    /// `group:artifact`, identifying the artifact across versions.
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.group, self.artifact)
+    }
+
+    /// Whether the version is a pattern rather than an exact version.
+    pub fn has_version_pattern(&self) -> bool {
+        self.version
+            .as_deref()
+            .is_some_and(|version| version.starts_with("regex:") || version.contains(['*', '?']))
+    }
+
+    fn base_url(&self, repository: &str) -> UrlBuilder {
+        let url = UrlBuilder::new(repository.trim_end_matches('/'), "");
+        self.group
+            .split('.')
+            .fold(url, |url, part| url.segment(part))
+            .segment(&self.artifact)
+    }
+
+    pub fn metadata_url(&self, repository: &str) -> String {
+        self.base_url(repository)
+            .segment("maven-metadata.xml")
+            .to_string()
+    }
+
+    pub fn file_name(&self, version: &str) -> String {
+        match &self.classifier {
+            Some(classifier) => format!("{}-{}-{}.jar", self.artifact, version, classifier),
+            None => format!("{}-{}.jar", self.artifact, version),
+        }
+    }
+
+    pub fn artifact_url(&self, repository: &str, version: &str) -> String {
+        self.base_url(repository)
+            .segment(version)
+            .segment(self.file_name(version))
+            .to_string()
+    }
+}
+
+impl Display for Coordinate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key())?;
+        match (&self.version, &self.classifier) {
+            (version, Some(classifier)) => write!(
+                f,
+                ":{}:{}",
+                version.as_deref().unwrap_or_default(),
+                classifier
+            ),
+            (Some(version), None) => write!(f, ":{}", version),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl From<&MavenArtifact> for Coordinate {
+    fn from(artifact: &MavenArtifact) -> Self {
+        let (group, name) = artifact
+            .coordinate
+            .split_once(':')
+            .unwrap_or(("", &artifact.coordinate));
+        Self {
+            group: group.to_string(),
+            artifact: name.to_string(),
+            version: None,
+            classifier: artifact.classifier.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MavenMetadata {
+    pub group_id: Option<String>,
+    pub artifact_id: Option<String>,
+    #[serde(default)]
+    pub versioning: Versioning,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Versioning {
+    pub latest: Option<String>,
+    pub release: Option<String>,
+    #[serde(default)]
+    pub versions: Versions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Versions {
+    #[serde(default)]
+    pub version: Vec<String>,
+}
+
+impl MavenMetadata {
+    pub fn parse(xml: &str) -> Result<Self> {
+        Ok(quick_xml::de::from_str(xml)?)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MavenAPI {
+    client: reqwest::Client,
+    repositories: Vec<String>,
+}
+
+impl MavenAPI {
+    pub fn new(repositories: Vec<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            repositories,
+        }
+    }
+
+    /// Searches `repository` if given, otherwise the repositories in the config or
+    /// [`DEFAULT_REPOSITORIES`].
+    pub fn from_config(repository: Option<String>) -> Self {
+        let repositories = match repository {
+            Some(repository) => vec![repository],
+            None => {
+                let config = Config::load().unwrap_or_else(|err| {
+                    warn!("Ignoring the config file: {}", err);
+                    Config::default()
+                });
+                if config.maven.repositories.is_empty() {
+                    DEFAULT_REPOSITORIES.iter().map(|r| r.to_string()).collect()
+                } else {
+                    config.maven.repositories
+                }
+            }
+        };
+        Self::new(repositories)
+    }
+
+    /// Finds the first repository publishing `coordinate`, returning it with the artifact's
+    /// metadata.
+    pub async fn get_metadata(&self, coordinate: &Coordinate) -> Result<(String, MavenMetadata)> {
+        for repository in &self.repositories {
+            let url = coordinate.metadata_url(repository);
+            debug!("Looking up {} in {}", coordinate.key(), url);
+            let response = self.client.get(&url).send().await?;
+            if !response.status().is_success() {
+                debug!("{} returned {}", url, response.status());
+                continue;
+            }
+            let metadata = MavenMetadata::parse(&response.text().await?)?;
+            return Ok((repository.clone(), metadata));
+        }
+        Err(Error::NotFound(coordinate.key()))
+    }
+
+    /// Downloads the newest version of `coordinate` that fits `game_version` and `loader` into
+    /// `dir`, recording the artifact for later updates.
+    pub async fn download(
+        &self,
+        coordinate: &Coordinate,
+        game_version: &str,
+        loader: &ModLoader,
+        pattern: Option<&AssetPattern>,
+        dir: &Path,
+    ) -> Result<PathBuf> {
+        let path = self
+            .fetch(coordinate, game_version, loader, pattern, dir, None)
+            .await?
+            .ok_or_else(|| Error::NoMatchingVersion(coordinate.to_string()))?;
+        record_install(&path, Source::Maven, &coordinate.key(), None);
+        Ok(path)
+    }
+
+    /// Downloads the newest version of `coordinate` that fits `game_version` and `loader` into
+    /// `dir` and records the artifact, without recording why the mod is installed. Returns `None`
+    /// if that version's file is `installed`.
+    #[allow(clippy::too_many_arguments)]
+    async fn fetch(
+        &self,
+        coordinate: &Coordinate,
+        game_version: &str,
+        loader: &ModLoader,
+        pattern: Option<&AssetPattern>,
+        dir: &Path,
+        installed: Option<&str>,
+    ) -> Result<Option<PathBuf>> {
+        let (repository, metadata) = self.get_metadata(coordinate).await?;
+        let pinned = coordinate
+            .version
+            .as_deref()
+            .filter(|_| !coordinate.has_version_pattern());
+        let candidates = select_versions(
+            &metadata.versioning.versions.version,
+            pinned,
+            game_version,
+            pattern,
+        );
+        let temp_dir = tempfile::tempdir()?;
+        for version in candidates.iter().take(MAX_DOWNLOADED_CANDIDATES) {
+            // Candidates come newest first, so nothing newer fits
+            if installed == Some(coordinate.file_name(version).as_str()) {
+                return Ok(None);
+            }
+            let url = coordinate.artifact_url(&repository, version);
+            let response = self.client.get(&url).send().await?;
+            // Not every version is published with every classifier
+            if response.status() == StatusCode::NOT_FOUND {
+                debug!("{} does not exist", url);
+                continue;
+            }
+            let contents = response.error_for_status()?.bytes().await?;
+            self.verify(&url, &contents).await?;
+            let file_name = coordinate.file_name(version);
+            let temp_path = temp_dir.path().join(&file_name);
+            fs::write(&temp_path, &contents)?;
+            let issue = match ModManifest::read_jar(&temp_path) {
+                Ok(manifests) => manifest::compatibility_issue(&manifests, game_version, loader),
+                Err(err) => {
+                    debug!("Could not read the mod metadata of {}: {}", file_name, err);
+                    None
+                }
+            };
+            if let Some(reason) = issue {
+                debug!("Skipping {}: {}", file_name, reason);
+                continue;
+            }
+            let path = dir.join(&file_name);
+            fs::copy(&temp_path, &path)?;
+            let artifact = MavenArtifact {
+                repository: repository.clone(),
+                coordinate: coordinate.key(),
+                classifier: coordinate.classifier.clone(),
+                version_pattern: pattern.map(|_| coordinate.version.clone().unwrap_or_default()),
+            };
+            DirMetadata::modify(dir, |metadata| metadata.set_artifact(&file_name, artifact))?;
+            return Ok(Some(path));
+        }
+        Err(Error::NoMatchingVersion(coordinate.to_string()))
+    }
+
+    /// Checks `contents` against the `.sha512` or `.sha1` file published next to `url`. Artifacts
+    /// without either are accepted with a warning.
+    async fn verify(&self, url: &str, contents: &[u8]) -> Result<()> {
+        for extension in ["sha512", "sha1"] {
+            let response = self
+                .client
+                .get(format!("{}.{}", url, extension))
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                continue;
+            }
+            let text = response.text().await?;
+            // Some repositories append the file name after the hash
+            let expected = text
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+            let actual = checksum(extension, contents);
+            if expected != actual {
+                return Err(Error::ChecksumMismatch {
+                    file: url.to_string(),
+                    expected,
+                    actual,
+                });
+            }
+            debug!("Verified {} with its {}", url, extension);
+            return Ok(());
+        }
+        warn!("{} has no checksum to verify it with", url);
+        Ok(())
+    }
+
+    pub async fn get_project_info(
+        &self,
+        coordinate: &Coordinate,
+        game_version: Option<&str>,
+    ) -> Result<ProjectInfo> {
+        let (repository, metadata) = self.get_metadata(coordinate).await?;
+        let versions = &metadata.versioning.versions.version;
+        let version = match game_version {
+            Some(game_version) => select_versions(versions, None, game_version, None)
+                .into_iter()
+                .next(),
+            None => metadata
+                .versioning
+                .release
+                .clone()
+                .or(metadata.versioning.latest.clone()),
+        };
+        Ok(ProjectInfo {
+            source: Source::Maven,
+            id: coordinate.key(),
+            title: coordinate.artifact.clone(),
+            summary: format!("{} in {}", coordinate.key(), repository),
+            links: vec![Link::new(
+                "Repository".to_string(),
+                coordinate.base_url(&repository).to_string(),
+            )],
+            version,
+            ..Default::default()
+        })
+    }
+}
+
+fn checksum(extension: &str, contents: &[u8]) -> String {
+    match extension {
+        "sha1" => sha1_smol::Sha1::from(contents).digest().to_string(),
+        _ => hex::encode(hmac_sha512::Hash::hash(contents)),
+    }
+}
+
+/// The versions worth trying, newest first. Snapshots are skipped, and versions naming
+/// `game_version` are preferred over the rest, since many artifacts carry it in their version.
+pub fn select_versions(
+    versions: &[String],
+    pinned: Option<&str>,
+    game_version: &str,
+    pattern: Option<&AssetPattern>,
+) -> Vec<String> {
+    if let Some(pinned) = pinned {
+        return vec![pinned.to_string()];
+    }
+    let candidates = versions
+        .iter()
+        .filter(|version| !version.ends_with("-SNAPSHOT"))
+        .filter(|version| pattern.is_none_or(|pattern| pattern.is_match(version)))
+        .sorted_by(|a, b| compare_versions(b, a))
+        .cloned()
+        .collect::<Vec<_>>();
+    let for_game_version = candidates
+        .iter()
+        .filter(|version| contains_version(version, game_version))
+        .cloned()
+        .collect::<Vec<_>>();
+    if for_game_version.is_empty() {
+        candidates
+    } else {
+        for_game_version
+    }
+}
+
+/// Updates the mod at `path` to the newest version of the artifact it was downloaded as. Returns
+/// `None` if that version is the one installed.
+pub async fn update(
+    path: &Path,
+    artifact: &MavenArtifact,
+    game_version: &str,
+    loader: Option<ModLoader>,
+) -> Result<Option<PathBuf>> {
+    let dir = path.parent().unwrap_or(Path::new("./"));
+    let mut coordinate = Coordinate::from(artifact);
+    coordinate.version = artifact.version_pattern.clone();
+    let pattern = artifact
+        .version_pattern
+        .as_deref()
+        .map(AssetPattern::parse)
+        .transpose()?;
+    let installed = path.file_name().map(|name| name.to_string_lossy());
+    info!("Checking {} for {}", artifact.repository, coordinate.key());
+    MavenAPI::new(vec![artifact.repository.clone()])
+        .fetch(
+            &coordinate,
+            game_version,
+            &loader.unwrap_or(ModLoader::Any),
+            pattern.as_ref(),
+            dir,
+            installed.as_deref(),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_coordinate() {
+        let coordinate = Coordinate::parse("net.fabricmc.fabric-api:fabric-api:0.100.*:sources");
+        assert_eq!(
+            coordinate,
+            Some(Coordinate {
+                group: "net.fabricmc.fabric-api".to_string(),
+                artifact: "fabric-api".to_string(),
+                version: Some("0.100.*".to_string()),
+                classifier: Some("sources".to_string()),
+            })
+        );
+        let coordinate = coordinate.unwrap();
+        assert!(coordinate.has_version_pattern());
+        assert_eq!(
+            coordinate.artifact_url("https://maven.fabricmc.net/", "0.100.1"),
+            "https://maven.fabricmc.net/net/fabricmc/fabric-api/fabric-api/0.100.1/fabric-api-0.100.1-sources.jar"
+        );
+        assert_eq!(
+            Coordinate::parse("dev.emi:emi-fabric::api")
+                .unwrap()
+                .to_string(),
+            "dev.emi:emi-fabric::api"
+        );
+        assert_eq!(Coordinate::parse("sodium"), None);
+        assert_eq!(Coordinate::parse("CaffeineMC/sodium"), None);
+        assert_eq!(Coordinate::parse("https://example.com/mod.jar"), None);
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(
+            checksum("sha1", b"abc"),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert!(checksum("sha512", b"abc").starts_with("ddaf35a193617aba"));
+    }
+
+    #[test]
+    fn test_select_versions() {
+        let metadata = MavenMetadata::parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata>
+  <groupId>net.fabricmc.fabric-api</groupId>
+  <artifactId>fabric-api</artifactId>
+  <versioning>
+    <latest>0.101.0+1.21-SNAPSHOT</latest>
+    <release>0.100.1+1.21</release>
+    <versions>
+      <version>0.92.2+1.20.1</version>
+      <version>0.100.0+1.21</version>
+      <version>0.100.1+1.21</version>
+      <version>0.100.1+1.21.1</version>
+      <version>0.101.0+1.21-SNAPSHOT</version>
+    </versions>
+  </versioning>
+</metadata>"#,
+        )
+        .unwrap();
+        let versions = &metadata.versioning.versions.version;
+        assert_eq!(versions.len(), 5);
+        assert_eq!(
+            select_versions(versions, None, "1.21", None),
+            vec!["0.100.1+1.21", "0.100.0+1.21"]
+        );
+        let pattern = AssetPattern::parse("0.92.*").unwrap();
+        assert_eq!(
+            select_versions(versions, None, "1.21", Some(&pattern)),
+            vec!["0.92.2+1.20.1"]
+        );
+        assert_eq!(
+            select_versions(versions, Some("0.100.0+1.21"), "1.20.1", None),
+            vec!["0.100.0+1.21"]
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     env::temp_dir,
     fs::{self, File},
     io::{Cursor, Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
 };
 use zip::{ZipWriter, write::FileOptions};
 
@@ -12,6 +14,12 @@ use crate::cli::Source;
 
 pub struct Metadata;
 
+/// Name of the file, relative to a mods directory, that stores modder's per-directory metadata.
+pub const DIR_METADATA_FILE: &str = ".modder.json";
+
+/// Serializes read-modify-write cycles of [`DirMetadata`], since mods are downloaded concurrently.
+static DIR_METADATA_LOCK: Mutex<()> = Mutex::new(());
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Error reading or writing the metadata file: {0}")]
@@ -109,3 +117,469 @@ impl Metadata {
         Ok(hashmap)
     }
 }
+
+/// Metadata that belongs to a mods directory as a whole rather than to a single jar.
+/// It is stored as JSON in [`DIR_METADATA_FILE`] inside the directory.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DirMetadata {
+    /// Pinned mods, keyed by their file name (without a `.disabled` suffix).
+    #[serde(default)]
+    pub pins: BTreeMap<String, Pin>,
+    /// Why each mod was installed, keyed by its file name (without a `.disabled` suffix).
+    #[serde(default)]
+    pub installs: BTreeMap<String, InstallRecord>,
+    /// Glob or `regex:` patterns that release assets of a GitHub repo have to match, keyed by
+    /// `<owner>/<repo>`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub asset_patterns: BTreeMap<String, String>,
+    /// Where mods added from a url or a local file came from, keyed by their file name (without
+    /// a `.disabled` suffix).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub origins: BTreeMap<String, Origin>,
+    /// The Maven artifacts mods were downloaded as, keyed by their file name (without a
+    /// `.disabled` suffix).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub artifacts: BTreeMap<String, MavenArtifact>,
+    /// The sources to update a mod from, in order, overriding the global priority. Keyed by file
+    /// name (without a `.disabled` suffix).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sources: BTreeMap<String, Vec<Source>>,
+    /// The source each mod was last updated from, keyed by its file name (without a `.disabled`
+    /// suffix).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub updated_from: BTreeMap<String, Source>,
+}
+
+/// The url or file a mod was downloaded or copied from.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Origin {
+    /// The url, or the absolute path of the local file.
+    pub location: String,
+    /// The SHA-512 of the jar when it was added.
+    pub sha512: String,
+    /// The `ETag` the url was served with, used to check for a newer file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// The `Last-Modified` date the url was served with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// Whether `update` checks the origin for a newer file.
+    #[serde(default)]
+    pub check_updates: bool,
+}
+
+impl Origin {
+    /// The source the mod came from: a url or a local file.
+    pub fn source(&self) -> Source {
+        if self.location.starts_with("http") {
+            Source::Url
+        } else {
+            Source::Local
+        }
+    }
+}
+
+/// The Maven artifact a mod was downloaded as, so `update` can look for newer versions of it.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MavenArtifact {
+    /// The repository the artifact was found in.
+    pub repository: String,
+    /// `<group>:<artifact>`, without a version.
+    pub coordinate: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classifier: Option<String>,
+    /// Glob or `regex:` pattern new versions have to match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_pattern: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Pin {
+    /// The version the mod is held at, if one was given when pinning. It was checked against the
+    /// installed file then.
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InstallRecord {
+    pub source: Source,
+    /// The Modrinth project id, CurseForge mod id or GitHub repo of the mod.
+    pub project_id: String,
+    /// Whether the user asked for this mod, as opposed to it only being pulled in by other mods.
+    pub explicit: bool,
+    /// The project ids of the mods that required this one.
+    #[serde(default)]
+    pub dependency_of: Vec<String>,
+}
+
+impl DirMetadata {
+    /// Loads the metadata of `dir`, returning an empty one if the directory has none yet.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(DIR_METADATA_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(dir.join(DIR_METADATA_FILE), contents)?;
+        Ok(())
+    }
+    /// Loads the metadata of `dir`, applies `f` to it and saves it again.
+    pub fn modify(dir: &Path, f: impl FnOnce(&mut Self)) -> Result<()> {
+        let _guard = DIR_METADATA_LOCK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut metadata = Self::load(dir)?;
+        f(&mut metadata);
+        metadata.save(dir)
+    }
+    /// Records that `file_name` was installed into `dir`, either explicitly or as a dependency of
+    /// the mod with the project id `parent`.
+    pub fn record_install(
+        dir: &Path,
+        file_name: &str,
+        source: Source,
+        project_id: &str,
+        parent: Option<&str>,
+    ) -> Result<()> {
+        Self::modify(dir, |metadata| {
+            metadata.add_install(file_name, source, project_id, parent)
+        })
+    }
+    pub fn add_install(
+        &mut self,
+        file_name: &str,
+        source: Source,
+        project_id: &str,
+        parent: Option<&str>,
+    ) {
+        let record = self
+            .installs
+            .entry(key(file_name))
+            .or_insert_with(|| InstallRecord {
+                source,
+                project_id: project_id.to_string(),
+                explicit: false,
+                dependency_of: Vec::new(),
+            });
+        match parent {
+            Some(parent) => {
+                if !record.dependency_of.iter().any(|p| p == parent) {
+                    record.dependency_of.push(parent.to_string());
+                }
+            }
+            None => record.explicit = true,
+        }
+    }
+    /// Adds `parent` to the dependents of an already installed mod, returning whether the mod
+    /// was found.
+    pub fn add_dependent(&mut self, project_id: &str, parent: &str) -> bool {
+        let Some(record) = self
+            .installs
+            .values_mut()
+            .find(|record| record.project_id == project_id)
+        else {
+            return false;
+        };
+        if !record.dependency_of.iter().any(|p| p == parent) {
+            record.dependency_of.push(parent.to_string());
+        }
+        true
+    }
+    /// Copies what is recorded about `old_file_name` to the file that replaced it, forgetting the
+    /// old file if `remove_old` is set.
+    pub fn carry_over(&mut self, old_file_name: &str, new_file_name: &str, remove_old: bool) {
+        let record = if remove_old {
+            self.installs.remove(&key(old_file_name))
+        } else {
+            self.installs.get(&key(old_file_name)).cloned()
+        };
+        if let Some(record) = record {
+            self.installs.insert(key(new_file_name), record);
+        }
+        let origin = if remove_old {
+            self.origins.remove(&key(old_file_name))
+        } else {
+            self.origins.get(&key(old_file_name)).cloned()
+        };
+        // A fresh download records its own origin, which is newer than the old one
+        if let Some(origin) = origin {
+            self.origins.entry(key(new_file_name)).or_insert(origin);
+        }
+        let artifact = if remove_old {
+            self.artifacts.remove(&key(old_file_name))
+        } else {
+            self.artifacts.get(&key(old_file_name)).cloned()
+        };
+        if let Some(artifact) = artifact {
+            self.artifacts.entry(key(new_file_name)).or_insert(artifact);
+        }
+        let sources = if remove_old {
+            self.sources.remove(&key(old_file_name))
+        } else {
+            self.sources.get(&key(old_file_name)).cloned()
+        };
+        if let Some(sources) = sources {
+            self.sources.insert(key(new_file_name), sources);
+        }
+        if remove_old {
+            self.updated_from.remove(&key(old_file_name));
+        }
+    }
+    /// Forgets everything recorded about `file_name`.
+    pub fn remove_mod(&mut self, file_name: &str) -> Option<InstallRecord> {
+        self.pins.remove(&key(file_name));
+        self.origins.remove(&key(file_name));
+        self.artifacts.remove(&key(file_name));
+        self.sources.remove(&key(file_name));
+        self.updated_from.remove(&key(file_name));
+        self.installs.remove(&key(file_name))
+    }
+    /// Drops `project_ids` from the dependents of every mod and returns the file names of the
+    /// mods that were only installed as dependencies and are no longer required by anything.
+    pub fn orphans_after_removing(&mut self, project_ids: &[String]) -> Vec<String> {
+        let mut orphans = Vec::new();
+        for (file_name, record) in self.installs.iter_mut() {
+            let required = !record.dependency_of.is_empty();
+            record
+                .dependency_of
+                .retain(|parent| !project_ids.contains(parent));
+            if required && !record.explicit && record.dependency_of.is_empty() {
+                orphans.push(file_name.clone());
+            }
+        }
+        orphans
+    }
+    /// The record of how and why `file_name` was installed, if modder installed it.
+    pub fn get_install(&self, file_name: &str) -> Option<&InstallRecord> {
+        self.installs.get(&key(file_name))
+    }
+    /// Describes why `file_name` is installed, e.g. `as a dependency of sodium.jar`.
+    pub fn describe_install(&self, file_name: &str) -> Option<String> {
+        let record = self.installs.get(&key(file_name))?;
+        let parents = record
+            .dependency_of
+            .iter()
+            .map(|parent| self.display_name(parent))
+            .join(", ");
+        let description = match (record.explicit, parents.is_empty()) {
+            (true, true) => "explicitly".to_string(),
+            (true, false) => format!("explicitly, also required by {}", parents),
+            (false, _) => format!("as a dependency of {}", parents),
+        };
+        Some(description)
+    }
+    /// Returns every chain of mods that requires `file_name`. Each chain starts with `file_name`
+    /// and follows the mods that required it until it reaches one that nothing else requires.
+    pub fn requirement_chains(&self, file_name: &str) -> Vec<Vec<String>> {
+        let mut chains = Vec::new();
+        self.collect_chains(vec![key(file_name)], &mut chains);
+        chains
+    }
+    fn collect_chains(&self, chain: Vec<String>, chains: &mut Vec<Vec<String>>) {
+        let current = chain.last().unwrap();
+        let parents = self
+            .installs
+            .get(current)
+            .map(|record| record.dependency_of.clone())
+            .unwrap_or_default();
+        if parents.is_empty() {
+            if chain.len() > 1 {
+                chains.push(chain);
+            }
+            return;
+        }
+        for parent in parents {
+            let parent = self.display_name(&parent);
+            let mut chain = chain.clone();
+            if chain.contains(&parent) {
+                // Dependency cycle, stop here.
+                chain.push(parent);
+                chains.push(chain);
+                continue;
+            }
+            chain.push(parent);
+            self.collect_chains(chain, chains);
+        }
+    }
+    /// The file name of the mod with the given project id, or the id itself if it isn't installed.
+    fn display_name(&self, project_id: &str) -> String {
+        self.installs
+            .iter()
+            .find(|(_, record)| record.project_id == project_id)
+            .map(|(file_name, _)| file_name.clone())
+            .unwrap_or_else(|| project_id.to_string())
+    }
+    pub fn pin(&mut self, file_name: &str, version: Option<String>) {
+        self.pins.insert(key(file_name), Pin { version });
+    }
+    pub fn unpin(&mut self, file_name: &str) -> Option<Pin> {
+        self.pins.remove(&key(file_name))
+    }
+    pub fn get_pin(&self, file_name: &str) -> Option<&Pin> {
+        self.pins.get(&key(file_name))
+    }
+    pub fn set_origin(&mut self, file_name: &str, origin: Origin) {
+        self.origins.insert(key(file_name), origin);
+    }
+    pub fn get_origin(&self, file_name: &str) -> Option<&Origin> {
+        self.origins.get(&key(file_name))
+    }
+    pub fn set_artifact(&mut self, file_name: &str, artifact: MavenArtifact) {
+        self.artifacts.insert(key(file_name), artifact);
+    }
+    pub fn get_artifact(&self, file_name: &str) -> Option<&MavenArtifact> {
+        self.artifacts.get(&key(file_name))
+    }
+    /// Sets the sources `file_name` is updated from, or makes it follow the global priority
+    /// again if `sources` is empty.
+    pub fn set_sources(&mut self, file_name: &str, sources: Vec<Source>) {
+        if sources.is_empty() {
+            self.sources.remove(&key(file_name));
+        } else {
+            self.sources.insert(key(file_name), sources);
+        }
+    }
+    pub fn get_sources(&self, file_name: &str) -> Option<&[Source]> {
+        self.sources.get(&key(file_name)).map(Vec::as_slice)
+    }
+    pub fn set_updated_from(&mut self, file_name: &str, source: Source) {
+        self.updated_from.insert(key(file_name), source);
+    }
+    pub fn get_updated_from(&self, file_name: &str) -> Option<&Source> {
+        self.updated_from.get(&key(file_name))
+    }
+    pub fn set_asset_pattern(&mut self, repo: &str, pattern: &str) {
+        self.asset_patterns
+            .insert(repo.to_lowercase(), pattern.to_string());
+    }
+    pub fn get_asset_pattern(&self, repo: &str) -> Option<&str> {
+        self.asset_patterns
+            .get(&repo.to_lowercase())
+            .map(String::as_str)
+    }
+}
+
+/// Disabled mods keep their metadata, so entries are keyed by the enabled file name.
+fn key(file_name: &str) -> String {
+    file_name
+        .strip_suffix(".disabled")
+        .unwrap_or(file_name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_pins_ignore_disabled_suffix() {
+        let mut metadata = DirMetadata::default();
+        metadata.pin("sodium-0.6.0.jar", Some("0.6.0".to_string()));
+        assert_eq!(
+            metadata.get_pin("sodium-0.6.0.jar.disabled"),
+            Some(&Pin {
+                version: Some("0.6.0".to_string())
+            })
+        );
+        assert!(metadata.unpin("sodium-0.6.0.jar.disabled").is_some());
+        assert!(metadata.get_pin("sodium-0.6.0.jar").is_none());
+    }
+
+    #[test]
+    fn test_dir_metadata_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            DirMetadata::load(dir.path()).unwrap(),
+            DirMetadata::default()
+        );
+        let mut metadata = DirMetadata::default();
+        metadata.pin("lithium.jar", None);
+        metadata.save(dir.path()).unwrap();
+        assert_eq!(DirMetadata::load(dir.path()).unwrap(), metadata);
+    }
+
+    #[test]
+    fn test_sources_follow_updates() {
+        let mut metadata = DirMetadata::default();
+        metadata.set_sources("sodium-0.5.jar", vec![Source::CurseForge, Source::Modrinth]);
+        metadata.set_updated_from("sodium-0.5.jar", Source::Modrinth);
+        metadata.carry_over("sodium-0.5.jar", "sodium-0.6.jar", true);
+        metadata.set_updated_from("sodium-0.6.jar", Source::CurseForge);
+        assert_eq!(metadata.get_sources("sodium-0.5.jar"), None);
+        assert_eq!(metadata.get_updated_from("sodium-0.5.jar"), None);
+        assert_eq!(
+            metadata.get_sources("sodium-0.6.jar.disabled"),
+            Some([Source::CurseForge, Source::Modrinth].as_slice())
+        );
+        assert_eq!(
+            metadata.get_updated_from("sodium-0.6.jar"),
+            Some(&Source::CurseForge)
+        );
+        metadata.set_sources("sodium-0.6.jar", Vec::new());
+        assert_eq!(metadata.get_sources("sodium-0.6.jar"), None);
+    }
+
+    #[test]
+    fn test_orphans_after_removing() {
+        let mut metadata = DirMetadata::default();
+        metadata.add_install("sodium-extra.jar", Source::Modrinth, "PtjYWJkn", None);
+        metadata.add_install("iris.jar", Source::Modrinth, "YL57xq9U", None);
+        metadata.add_install("sodium.jar", Source::Modrinth, "AANobbMI", Some("PtjYWJkn"));
+        metadata.add_dependent("AANobbMI", "YL57xq9U");
+        metadata.add_install(
+            "fabric-api.jar",
+            Source::Modrinth,
+            "P7dR8mSH",
+            Some("PtjYWJkn"),
+        );
+        metadata.add_install(
+            "lithium.jar",
+            Source::Modrinth,
+            "gvQqBUqZ",
+            Some("PtjYWJkn"),
+        );
+        metadata.add_install("lithium.jar", Source::Modrinth, "gvQqBUqZ", None);
+
+        metadata.remove_mod("sodium-extra.jar");
+        let orphans = metadata.orphans_after_removing(&["PtjYWJkn".to_string()]);
+        assert_eq!(orphans, vec!["fabric-api.jar".to_string()]);
+        assert_eq!(
+            metadata.installs["sodium.jar"].dependency_of,
+            vec!["YL57xq9U".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_requirement_chains() {
+        let mut metadata = DirMetadata::default();
+        metadata.add_install("sodium-extra.jar", Source::Modrinth, "PtjYWJkn", None);
+        metadata.add_install("iris.jar", Source::Modrinth, "YL57xq9U", None);
+        metadata.add_install("sodium.jar", Source::Modrinth, "AANobbMI", Some("PtjYWJkn"));
+        metadata.add_dependent("AANobbMI", "YL57xq9U");
+        metadata.add_install(
+            "fabric-api.jar",
+            Source::Modrinth,
+            "P7dR8mSH",
+            Some("AANobbMI"),
+        );
+
+        assert_eq!(
+            metadata.requirement_chains("fabric-api.jar"),
+            vec![
+                vec!["fabric-api.jar", "sodium.jar", "sodium-extra.jar"],
+                vec!["fabric-api.jar", "sodium.jar", "iris.jar"],
+            ]
+        );
+        assert_eq!(
+            metadata.describe_install("sodium.jar").unwrap(),
+            "as a dependency of sodium-extra.jar, iris.jar"
+        );
+        assert_eq!(metadata.describe_install("iris.jar").unwrap(), "explicitly");
+        assert!(metadata.requirement_chains("iris.jar").is_empty());
+    }
+}
@@ -1 +1,2 @@
 pub mod modrinth;
+pub mod search;
@@ -1,11 +1,23 @@
 #![allow(dead_code)]
+use super::search::SearchQuery;
+use crate::changelog::{self, ChangelogEntry, ModChangelog};
+use crate::check;
+use crate::cli::Source;
+use crate::config::Channel;
+use crate::content::ContentType;
+#[cfg(feature = "github")]
 use crate::gh_releases::{self};
+use crate::info::ProjectInfo;
 use crate::metadata::Error as MetadataError;
-use crate::{Link, ModLoader, calc_sha512};
+use crate::resolver::{Candidate, DependencyKind, InstalledProject, Requirement, VersionProvider};
+use crate::{Link, ModLoader, UrlBuilder, calc_sha512, record_install};
 use color_eyre::eyre::ContextCompat;
+#[cfg(feature = "colored")]
 use colored::Colorize;
 use futures::lock::Mutex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::{fmt::Display, fs};
 use tracing::{self, debug, error, info};
@@ -23,6 +35,7 @@ pub enum Error {
     MetadataErr(#[from] MetadataError),
     #[error("Unknown error: {0}")]
     UnknownError(#[from] color_eyre::eyre::ErrReport),
+    #[cfg(feature = "github")]
     #[error("Error getting mod from github: {0}")]
     GithubError(#[from] gh_releases::Error),
     #[error("Error writing file: {0}")]
@@ -31,6 +44,15 @@ pub enum Error {
 
 type Result<T> = color_eyre::Result<T, Error>;
 
+impl Error {
+    /// Whether Modrinth answered that the project or version doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::RequestErr(err) if err.status() == Some(reqwest::StatusCode::NOT_FOUND))
+    }
+}
+
+pub const API_URL: &str = "https://api.modrinth.com/v2";
+#[cfg(feature = "colored")]
 const GRAY: (u8, u8, u8) = (128, 128, 128);
 
 #[derive(Debug, Deserialize, Clone)]
@@ -150,9 +172,21 @@ struct GalleryImage {
     ordering: Option<i32>,
 }
 
+#[derive(Debug, Deserialize)]
+struct TeamMember {
+    role: String,
+    user: TeamUser,
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamUser {
+    username: String,
+}
+
 impl GetProject {
     pub async fn from_id(id: &str) -> Option<Self> {
-        let res = reqwest::get(format!("https://api.modrinth.com/v2/project/{}", id)).await;
+        let url = UrlBuilder::new(API_URL, "/project").segment(id);
+        let res = reqwest::get(url.to_string()).await;
         if res.is_err() {
             error!("Error getting project: {}", res.err().unwrap());
             return None;
@@ -181,31 +215,62 @@ impl GetProject {
 pub struct Modrinth;
 
 impl Modrinth {
+    /// Gets every version of a project, newest first.
+    pub async fn get_versions(mod_name: &str) -> Result<Vec<VersionData>> {
+        let url = UrlBuilder::new(API_URL, "/project")
+            .segment(mod_name)
+            .segment("version");
+        let versions = reqwest::get(url.to_string()).await?.error_for_status()?;
+        let versions = versions.text().await?;
+        serde_json::from_str(&versions).map_err(Error::SerdeErr)
+    }
+    /// Gets the versions of a project for `version`, made for any of `loaders` (any loader if
+    /// empty).
     async fn get_version_data(
         mod_name: &str,
         version: &str,
-        mod_loader: &str,
+        loaders: &[String],
     ) -> Result<Vec<VersionData>> {
-        debug!(mod_name = ?mod_name, version = ?version, mod_loader = ?mod_loader);
-        let versions = reqwest::get(format!(
-        "https://api.modrinth.com/v2/project/{}/version?game_versions=[\"{}\"]&loaders=[\"{}\"]",
-        mod_name, version, mod_loader.to_lowercase()
-    ))
-    .await
-    .expect("Failed to get versions");
+        debug!(mod_name = ?mod_name, version = ?version, loaders = ?loaders);
+        let mut url = UrlBuilder::new(API_URL, "/project")
+            .segment(mod_name)
+            .segment("version")
+            .json_param("game_versions", &[version]);
+        if !loaders.is_empty() {
+            url = url.json_param("loaders", &loaders);
+        }
+        let versions = reqwest::get(url.to_string())
+            .await
+            .expect("Failed to get versions");
 
         let versions = versions.text().await.unwrap();
         debug!(versions = ?versions);
         serde_json::from_str(&versions).map_err(Error::SerdeErr)
     }
     pub async fn search_mods(query: &str, limit: u16, offset: u16) -> ProjectSearch {
-        let client = reqwest::Client::new();
-        let res = client .get(format!("https://api.modrinth.com/v2/search?query={}&limit={}&index=relevance&facets=%5B%5B%22project_type%3Amod%22%5D%5D&offset={}",query,limit, offset )) .send().await.unwrap();
-
-        let res_text = res.text().await.unwrap();
-
-        let parsed: ProjectSearch = serde_json::from_str(&res_text).unwrap();
-        parsed
+        Modrinth::search_projects(query, ContentType::Mod, limit, offset)
+            .await
+            .unwrap()
+    }
+    /// Searches for projects of `content_type`, e.g. resource packs or shaders.
+    pub async fn search_projects(
+        query: &str,
+        content_type: ContentType,
+        limit: u16,
+        offset: u16,
+    ) -> Result<ProjectSearch> {
+        let query = SearchQuery::new(query)
+            .project_type(content_type.modrinth_project_type())
+            .limit(limit)
+            .offset(offset);
+        Modrinth::search(&query).await
+    }
+    pub async fn search(query: &SearchQuery) -> Result<ProjectSearch> {
+        let url = query.to_url().to_string();
+        debug!(url = ?url);
+        let res = reqwest::get(url).await?.error_for_status()?;
+        let res_text = res.text().await?;
+        serde_json::from_str(&res_text).map_err(Error::SerdeErr)
     }
 
     pub async fn get_version(
@@ -213,8 +278,21 @@ impl Modrinth {
         version: &str,
         loader: ModLoader,
     ) -> Option<VersionData> {
-        #[allow(clippy::unnecessary_to_owned)]
-        let versions = Modrinth::get_version_data(mod_name, version, &loader.to_string()).await;
+        Modrinth::get_content_version(mod_name, version, ContentType::Mod, loader, None).await
+    }
+
+    /// Gets the newest version of a project of `content_type` for `version` on `channel`, or on
+    /// any channel if `None`. `loader` is only used for mods, packs are looked up for the loaders
+    /// Modrinth files them under.
+    pub async fn get_content_version(
+        mod_name: &str,
+        version: &str,
+        content_type: ContentType,
+        loader: ModLoader,
+        channel: Option<Channel>,
+    ) -> Option<VersionData> {
+        let loaders = content_type.modrinth_loaders(&loader);
+        let versions = Modrinth::get_version_data(mod_name, version, &loaders).await;
         if versions.is_err() {
             error!(
                 "Error parsing versions for mod {}: {}. This may mean that this mod is not available for this version",
@@ -229,7 +307,18 @@ impl Modrinth {
             error!("No versions found for mod {} for {}", mod_name, version);
             return None;
         }
-        Some(versions[0].clone())
+        let version_data = versions.into_iter().find(|version_data| {
+            channel.is_none_or(|channel| channel.allows(&version_data.get_version_type()))
+        });
+        if version_data.is_none() {
+            error!(
+                "No {} versions found for mod {} for {}",
+                channel.unwrap_or_default(),
+                mod_name,
+                version
+            );
+        }
+        version_data
     }
 
     pub async fn get_top_mods(limit: u16) -> Vec<Project> {
@@ -249,7 +338,7 @@ impl Modrinth {
         }
         info!(temp_mods = ?temp_mods.lock().await.len(), "Got mods");
 
-        if limit % 100 != 0 {
+        if !limit.is_multiple_of(100) {
             let temp_mods = Arc::clone(&temp_mods.clone());
             handles.push(tokio::spawn(async move {
                 let res = Modrinth::search_mods("", limit % 100, (limit / 100) * 100).await;
@@ -271,48 +360,6 @@ impl Modrinth {
         );
         mods
     }
-    pub async fn download_dependencies(
-        mod_: &Mod,
-        version: &str,
-        prev_deps: Arc<Mutex<Vec<Dependency>>>,
-        prefix: &str,
-        loader: ModLoader,
-    ) {
-        let mod_ = Modrinth::get_version(&mod_.slug, version, loader.clone()).await;
-        let mut prev_deps = prev_deps.lock().await;
-        let mut handles = Vec::new();
-
-        if let Some(mod_) = mod_ {
-            for dependency in mod_.dependencies.unwrap() {
-                let loader = loader.clone();
-                if prev_deps.contains(&dependency) {
-                    info!(
-                        "Skipping dependency {}",
-                        dependency.file_name.unwrap_or("Unknown".to_string())
-                    );
-                    continue;
-                }
-                prev_deps.push(dependency.clone());
-                let dependency =
-                    Modrinth::get_version(&dependency.project_id.unwrap(), version, loader).await;
-
-                if let Some(dependency) = dependency {
-                    info!(
-                        "Downloading dependency {}",
-                        dependency.clone().files.unwrap()[0].filename
-                    );
-                    let prefix = prefix.to_string();
-                    let handle = tokio::spawn(async move {
-                        download_file(&dependency.files.unwrap()[0], &prefix).await;
-                    });
-                    handles.push(handle);
-                }
-            }
-        }
-        for handle in handles {
-            handle.await.unwrap();
-        }
-    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -349,8 +396,10 @@ impl Display for Project {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 #[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
 pub enum SupportLevel {
     Required,
     Optional,
@@ -378,9 +427,9 @@ pub enum MonetizationStatus {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProjectSearch {
     pub hits: Vec<Project>,
-    offset: u16,
-    limit: u16,
-    total_hits: u16,
+    pub offset: u16,
+    pub limit: u16,
+    pub total_hits: u32,
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -407,13 +456,75 @@ impl Display for Mod {
 impl VersionData {
     pub async fn from_hash(hash: String) -> Result<Self> {
         // TODO: Add this to the API
-        let res = reqwest::get(format!("https://api.modrinth.com/v2/version_file/{hash}"))
-            .await
-            .unwrap();
+        let url = UrlBuilder::new(API_URL, "/version_file").segment(hash);
+        let res = reqwest::get(url.to_string()).await.unwrap();
         let res = res.text().await.unwrap();
         let res: Result<VersionData> = serde_json::from_str(&res).map_err(Error::SerdeErr);
         res
     }
+    /// Looks up the versions of many files at once by their sha512 hashes. Files Modrinth doesn't
+    /// know are left out.
+    pub async fn from_hashes(hashes: &[String]) -> Result<HashMap<String, Self>> {
+        let url = UrlBuilder::new(API_URL, "/version_files");
+        let body = serde_json::json!({
+            "hashes": hashes,
+            "algorithm": "sha512",
+        });
+        let res = reqwest::Client::new()
+            .post(url.to_string())
+            .header("content-type", "application/json")
+            .body(serde_json::to_string(&body)?)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(serde_json::from_str(&res.text().await?)?)
+    }
+    /// Gets a version by its id.
+    pub async fn from_id(id: &str) -> Result<Self> {
+        let url = UrlBuilder::new(API_URL, "/version").segment(id);
+        let res = reqwest::get(url.to_string()).await?.error_for_status()?;
+        Ok(serde_json::from_str(&res.text().await?)?)
+    }
+    /// The file to download: the primary one, or the first if none is marked primary.
+    pub fn primary_file(&self) -> Option<&File> {
+        let files = self.files.as_deref()?;
+        files.iter().find(|file| file.primary).or(files.first())
+    }
+    /// The version as a candidate for the resolver, with its dependencies unless `content_type`
+    /// isn't a mod.
+    pub fn to_candidate(&self, content_type: ContentType) -> Option<Candidate> {
+        let file = self.primary_file()?;
+        let dependencies = self
+            .dependencies
+            .iter()
+            .flatten()
+            .filter(|_| content_type.is_mod())
+            .filter_map(|dependency| {
+                Some(Requirement {
+                    project_id: dependency.project_id.clone(),
+                    version_id: dependency.version_id.clone(),
+                    kind: DependencyKind::from_modrinth(dependency.dependency_type.as_deref()?)?,
+                })
+            })
+            .collect();
+        Some(Candidate {
+            project_id: self.project_id.clone(),
+            version_id: self.id.clone(),
+            file_name: file.filename.clone(),
+            url: Some(file.url.clone()),
+            dependencies,
+        })
+    }
+    /// The projects this version declares itself incompatible with.
+    pub fn incompatible_projects(&self) -> Vec<String> {
+        self.dependencies
+            .iter()
+            .flatten()
+            .filter(|dependency| dependency.dependency_type.as_deref() == Some("incompatible"))
+            .filter_map(|dependency| dependency.project_id.clone())
+            .collect()
+    }
+    #[cfg(feature = "colored")]
     pub fn format_verbose(&self, mod_name: &str, categories: &[String]) -> String {
         let mut output = String::new();
         let url = format!("https://modrinth.com/mod/{}", self.project_id);
@@ -449,6 +560,7 @@ impl VersionData {
 
         output
     }
+    #[cfg(feature = "colored")]
     pub fn format(&self, mod_name: &str) -> String {
         let mut output = String::new();
         let url = format!("https://modrinth.com/mod/{}", self.project_id);
@@ -485,44 +597,191 @@ impl VersionData {
     }
 }
 
+/// Gets the details of a Modrinth project, with the changelog of its newest version for
+/// `game_version` and `loader`.
+pub async fn get_project_info(
+    id: &str,
+    game_version: Option<&str>,
+    loader: Option<ModLoader>,
+) -> Result<ProjectInfo> {
+    let url = UrlBuilder::new(API_URL, "/project").segment(id);
+    let res = reqwest::get(url.to_string()).await?.error_for_status()?;
+    let project: GetProject = serde_json::from_str(&res.text().await?)?;
+    let url = UrlBuilder::new(API_URL, "/project")
+        .segment(&project.id)
+        .segment("members");
+    let res = reqwest::get(url.to_string()).await?.error_for_status()?;
+    let members: Vec<TeamMember> = serde_json::from_str(&res.text().await?)?;
+    let versions = Modrinth::get_versions(&project.id).await?;
+    let loader = loader
+        .filter(|loader| *loader != ModLoader::Any)
+        .map(|loader| loader.to_string().to_lowercase());
+    let version = versions.into_iter().find(|version| {
+        let game_version_matches = game_version.is_none_or(|game_version| {
+            version
+                .game_versions
+                .as_ref()
+                .is_some_and(|versions| versions.iter().any(|v| v == game_version))
+        });
+        let loader_matches = loader.as_ref().is_none_or(|loader| {
+            version
+                .loaders
+                .as_ref()
+                .is_some_and(|loaders| loaders.contains(loader))
+        });
+        game_version_matches && loader_matches
+    });
+
+    let mut links = vec![Link::new(
+        "Page".to_string(),
+        format!(
+            "https://modrinth.com/{}/{}",
+            project.project_type, project.slug
+        ),
+    )];
+    let urls = [
+        ("Issues", &project.issues_url),
+        ("Source", &project.source_url),
+        ("Wiki", &project.wiki_url),
+        ("Discord", &project.discord_url),
+    ];
+    for (text, url) in urls {
+        if let Some(url) = url {
+            links.push(Link::new(text.to_string(), url.clone()));
+        }
+    }
+    for donation in &project.donation_urls {
+        links.push(Link::new(
+            format!("Donate ({})", donation.platform),
+            donation.url.clone(),
+        ));
+    }
+    if let Some(url) = &project.license.url {
+        links.push(Link::new("License".to_string(), url.clone()));
+    }
+    Ok(ProjectInfo {
+        source: Source::Modrinth,
+        id: project.id,
+        title: project.title,
+        summary: project.description,
+        authors: members
+            .into_iter()
+            .map(|member| format!("{} ({})", member.user.username, member.role))
+            .collect(),
+        license: Some(project.license.name)
+            .filter(|name| !name.is_empty())
+            .or(Some(project.license.id)),
+        client_side: Some(project.client_side),
+        server_side: Some(project.server_side),
+        game_versions: project.game_versions,
+        loaders: project.loaders,
+        downloads: project.downloads as u64,
+        links,
+        version: version.as_ref().map(VersionData::get_version),
+        changelog: version.and_then(|version| version.changelog),
+    })
+}
+
 pub async fn update_from_file(
     filename: &str,
     new_version: &str,
     prefix: &str,
     loader: Option<ModLoader>,
-) -> Result<()> {
+    channel: Option<Channel>,
+) -> Result<PathBuf> {
     let hash = calc_sha512(filename);
     let version_data = VersionData::from_hash(hash).await?;
-    let loader = if let Some(loader) = loader {
-        loader
+    let content_type = ContentType::from_dir(Path::new(prefix));
+    let new_version_data = if content_type.is_mod() {
+        let loader = if let Some(loader) = loader {
+            loader
+        } else {
+            let loaders = version_data.loaders.unwrap_or(vec!["fabric".to_string()]);
+            ModLoader::from_name(loaders.first().context("No loader found")?)
+                .unwrap_or(ModLoader::Any)
+        };
+        Modrinth::get_content_version(
+            &version_data.project_id,
+            new_version,
+            ContentType::Mod,
+            loader,
+            channel,
+        )
+        .await
     } else {
-        match version_data
-            .loaders
-            .unwrap_or(vec!["fabric".to_string()])
-            .first()
-            .context("No loader found")?
-            .as_str()
-        {
-            "fabric" => ModLoader::Fabric,
-            "forge" => ModLoader::Forge,
-            "quilt" => ModLoader::Quilt,
-            "neoforge" => ModLoader::NeoForge,
-            "cauldron" => ModLoader::Cauldron,
-            "liteloader" => ModLoader::LiteLoader,
-            _ => ModLoader::Any,
-        }
+        Modrinth::get_content_version(
+            &version_data.project_id,
+            new_version,
+            content_type,
+            ModLoader::Any,
+            channel,
+        )
+        .await
     };
 
-    let new_version_data =
-        Modrinth::get_version(&version_data.project_id, new_version, loader).await;
-
     let Some(new_version_data) = new_version_data else {
         return Err(Error::NoVersionsFound(filename.to_string()));
     };
 
-    download_file(&new_version_data.clone().files.unwrap()[0], prefix).await;
+    let file = new_version_data
+        .primary_file()
+        .ok_or_else(|| Error::NoVersionsFound(filename.to_string()))?;
+    download_file(file, prefix).await;
+
+    Ok(PathBuf::from(prefix).join(&file.filename))
+}
+
+/// Collects the changelogs of the versions between the mod at `old_path` and the one at
+/// `new_path`, skipping versions for other loaders.
+pub async fn changelog_between(old_path: &Path, new_path: &Path) -> Result<ModChangelog> {
+    let old = VersionData::from_hash(calc_sha512(&old_path.to_string_lossy())).await?;
+    let new = VersionData::from_hash(calc_sha512(&new_path.to_string_lossy())).await?;
+    let loaders = new.loaders.clone().unwrap_or_default();
+    let versions = Modrinth::get_versions(&new.project_id)
+        .await?
+        .into_iter()
+        .filter(|version| {
+            version.id == old.id
+                || version
+                    .loaders
+                    .as_ref()
+                    .is_none_or(|v| v.iter().any(|loader| loaders.contains(loader)))
+        })
+        .collect::<Vec<_>>();
+    let entries = changelog::between(
+        &versions,
+        |version| version.id == old.id,
+        |version| version.id == new.id,
+    )
+    .iter()
+    .map(|version| ChangelogEntry {
+        version: version.get_version(),
+        changelog: version.changelog.clone().unwrap_or_default(),
+    })
+    .collect();
+    let name = match GetProject::from_id(&new.project_id).await {
+        Some(project) => project.get_title(),
+        None => new.project_id.clone(),
+    };
+    Ok(ModChangelog {
+        name,
+        source: Source::Modrinth,
+        from: old.get_version(),
+        to: new.get_version(),
+        entries,
+    })
+}
 
-    Ok(())
+/// Downloads the primary file of `version_data` into `prefix` and records it as explicitly
+/// installed, returning the path it was written to.
+pub async fn download_version(version_data: &VersionData, prefix: &str) -> Result<PathBuf> {
+    let file = version_data
+        .primary_file()
+        .ok_or_else(|| Error::NoVersionsFound(version_data.project_id.clone()))?;
+    download_file(file, prefix).await;
+    let path = Path::new(prefix).join(&file.filename);
+    record_install(&path, Source::Modrinth, &version_data.project_id, None);
+    Ok(path)
 }
 
 pub async fn download_file(file: &File, prefix: &str) {
@@ -533,3 +792,80 @@ pub async fn download_file(file: &File, prefix: &str) {
     )
     .unwrap();
 }
+
+/// Looks up Modrinth versions for [`crate::resolver::Resolver`].
+#[derive(Debug, Clone)]
+pub struct ModrinthProvider {
+    pub game_version: String,
+    pub loader: ModLoader,
+    pub content_type: ContentType,
+    pub channel: Option<Channel>,
+}
+
+impl VersionProvider for ModrinthProvider {
+    fn source(&self) -> Source {
+        Source::Modrinth
+    }
+
+    async fn identify(&self, dir: &Path) -> color_eyre::Result<Vec<InstalledProject>> {
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let hashes = check::jar_files(dir)?
+            .into_iter()
+            .map(|path| {
+                let hash = calc_sha512(&path.to_string_lossy());
+                (path, hash)
+            })
+            .collect::<Vec<_>>();
+        let versions = VersionData::from_hashes(
+            &hashes
+                .iter()
+                .map(|(_, hash)| hash.clone())
+                .collect::<Vec<_>>(),
+        )
+        .await?;
+        let installed = hashes
+            .into_iter()
+            .filter_map(|(path, hash)| {
+                let version = versions.get(&hash)?;
+                Some(InstalledProject {
+                    project_id: version.project_id.clone(),
+                    file_name: path.file_name()?.to_string_lossy().to_string(),
+                    incompatible: version.incompatible_projects(),
+                })
+            })
+            .collect();
+        Ok(installed)
+    }
+
+    async fn find(&self, requirement: &Requirement) -> color_eyre::Result<Option<Candidate>> {
+        let version_data = match (&requirement.version_id, &requirement.project_id) {
+            (Some(version_id), _) => Some(VersionData::from_id(version_id).await?),
+            (None, Some(project_id)) => {
+                Modrinth::get_content_version(
+                    project_id,
+                    &self.game_version,
+                    self.content_type,
+                    self.loader.clone(),
+                    self.channel,
+                )
+                .await
+            }
+            (None, None) => None,
+        };
+        Ok(version_data.and_then(|version_data| version_data.to_candidate(self.content_type)))
+    }
+
+    async fn download(&self, candidate: &Candidate, dir: &Path) -> color_eyre::Result<PathBuf> {
+        let url = candidate
+            .url
+            .as_deref()
+            .context("The version has no file to download")?;
+        let res = reqwest::get(url).await?.error_for_status()?;
+        fs::create_dir_all(dir)?;
+        let path = dir.join(&candidate.file_name);
+        fs::write(&path, res.bytes().await?)?;
+        Ok(path)
+    }
+}
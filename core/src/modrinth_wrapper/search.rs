@@ -0,0 +1,211 @@
+use super::modrinth::{API_URL, SupportLevel};
+use crate::{ModLoader, UrlBuilder};
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// How Modrinth orders search results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum SearchIndex {
+    #[default]
+    Relevance,
+    Downloads,
+    Follows,
+    Newest,
+    Updated,
+}
+
+/// A single search filter. Facets in the same group are OR'd together, groups are AND'd.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Facet {
+    ProjectType(String),
+    Category(String),
+    Loader(ModLoader),
+    GameVersion(String),
+    ClientSide(SupportLevel),
+    ServerSide(SupportLevel),
+    License(String),
+}
+
+impl Facet {
+    fn as_filter(&self) -> String {
+        match self {
+            Facet::ProjectType(project_type) => format!("project_type:{}", project_type),
+            Facet::Category(category) => format!("categories:{}", category),
+            // Modrinth stores loaders among the categories of a project
+            Facet::Loader(loader) => format!("categories:{}", loader.to_string().to_lowercase()),
+            Facet::GameVersion(version) => format!("versions:{}", version),
+            Facet::ClientSide(level) => format!("client_side:{}", level),
+            Facet::ServerSide(level) => format!("server_side:{}", level),
+            Facet::License(license) => format!("license:{}", license),
+        }
+    }
+}
+
+/// A Modrinth search, built up with facets before being turned into a request url.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    query: String,
+    facets: Vec<Vec<Facet>>,
+    index: SearchIndex,
+    limit: u16,
+    offset: u16,
+}
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            facets: vec![vec![Facet::ProjectType("mod".to_string())]],
+            index: SearchIndex::default(),
+            limit: 10,
+            offset: 0,
+        }
+    }
+}
+
+impl SearchQuery {
+    /// A search for mods matching `query`, ordered by relevance.
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+    /// Replaces the project type (`mod` by default).
+    pub fn project_type(mut self, project_type: &str) -> Self {
+        self.facets
+            .retain(|group| !matches!(group.first(), Some(Facet::ProjectType(_))));
+        self.facets
+            .push(vec![Facet::ProjectType(project_type.to_string())]);
+        self
+    }
+    /// Only matches projects in every one of `categories`.
+    pub fn categories<I, S>(mut self, categories: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for category in categories {
+            self.facets.push(vec![Facet::Category(category.into())]);
+        }
+        self
+    }
+    /// Only matches projects supporting any of `loaders`.
+    pub fn loaders(self, loaders: impl IntoIterator<Item = ModLoader>) -> Self {
+        let group = loaders
+            .into_iter()
+            .filter(|loader| *loader != ModLoader::Any)
+            .map(Facet::Loader)
+            .collect();
+        self.facet_group(group)
+    }
+    /// Only matches projects available for any of `game_versions`.
+    pub fn game_versions<I, S>(self, game_versions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let group = game_versions
+            .into_iter()
+            .map(|version| Facet::GameVersion(version.into()))
+            .collect();
+        self.facet_group(group)
+    }
+    pub fn client_side(self, level: SupportLevel) -> Self {
+        self.facet_group(vec![Facet::ClientSide(level)])
+    }
+    pub fn server_side(self, level: SupportLevel) -> Self {
+        self.facet_group(vec![Facet::ServerSide(level)])
+    }
+    /// Only matches projects under `license`, given as an SPDX id such as `mit`.
+    pub fn license(self, license: &str) -> Self {
+        self.facet_group(vec![Facet::License(license.to_string())])
+    }
+    /// Adds a group of facets of which any has to match. Empty groups are ignored.
+    pub fn facet_group(mut self, group: Vec<Facet>) -> Self {
+        if !group.is_empty() {
+            self.facets.push(group);
+        }
+        self
+    }
+    pub fn index(mut self, index: SearchIndex) -> Self {
+        self.index = index;
+        self
+    }
+    /// The number of results per page, at most 100.
+    pub fn limit(mut self, limit: u16) -> Self {
+        self.limit = limit.min(100);
+        self
+    }
+    pub fn offset(mut self, offset: u16) -> Self {
+        self.offset = offset;
+        self
+    }
+    /// Shows the 1-based `page` of results, `limit` results per page.
+    pub fn page(self, page: u16) -> Self {
+        let offset = page.saturating_sub(1).saturating_mul(self.limit);
+        self.offset(offset)
+    }
+
+    /// The facets as the array of arrays Modrinth expects.
+    fn facet_filters(&self) -> Vec<Vec<String>> {
+        self.facets
+            .iter()
+            .map(|group| group.iter().map(Facet::as_filter).collect())
+            .collect()
+    }
+
+    pub fn to_url(&self) -> UrlBuilder {
+        UrlBuilder::new(API_URL, "/search")
+            .param("query", &self.query)
+            .json_param("facets", &self.facet_filters())
+            .param("index", self.index)
+            .param("limit", self.limit)
+            .param("offset", self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_facet_filters() {
+        let query = SearchQuery::new("")
+            .categories(["optimization"])
+            .loaders([ModLoader::Fabric, ModLoader::Quilt])
+            .game_versions(["1.21.4"])
+            .client_side(SupportLevel::Required)
+            .license("mit");
+        assert_eq!(
+            query.facet_filters(),
+            [
+                vec!["project_type:mod"],
+                vec!["categories:optimization"],
+                vec!["categories:fabric", "categories:quilt"],
+                vec!["versions:1.21.4"],
+                vec!["client_side:required"],
+                vec!["license:mit"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_url_encodes_query() {
+        let url = SearchQuery::new("just enough items&x=1")
+            .index(SearchIndex::Newest)
+            .limit(5)
+            .page(3)
+            .to_url()
+            .to_string();
+        assert_eq!(
+            url,
+            "https://api.modrinth.com/v2/search?query=just%20enough%20items%26x%3D1\
+             &facets=%5B%5B%22project_type%3Amod%22%5D%5D&index=newest&limit=5&offset=10"
+        );
+    }
+}
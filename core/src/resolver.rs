@@ -0,0 +1,758 @@
+use crate::cli::Source;
+use crate::metadata::{self, DirMetadata};
+use crate::record_install;
+use futures::future::try_join_all;
+use itertools::Itertools;
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+use strum::Display;
+use tracing::{error, info};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(
+        "The dependencies can't be satisfied, nothing was downloaded:\n{}",
+        .0.iter().map(|conflict| format!("  - {}", conflict)).join("\n")
+    )]
+    Conflicts(Vec<Conflict>),
+    #[error("Error looking up a version: {0}")]
+    Lookup(#[from] color_eyre::eyre::Report),
+    #[error("Error reading the directory metadata: {0}")]
+    Metadata(#[from] metadata::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// How a mod relates to one it declares a dependency on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum DependencyKind {
+    /// The mod doesn't work without it.
+    Required,
+    /// The mod works with or without it.
+    Optional,
+    /// The mod doesn't work alongside it.
+    Incompatible,
+    /// It is bundled in the mod's jar and needs no download.
+    Embedded,
+}
+
+impl DependencyKind {
+    /// Parses a Modrinth `dependency_type`.
+    pub fn from_modrinth(dependency_type: &str) -> Option<Self> {
+        let kind = match dependency_type {
+            "required" => DependencyKind::Required,
+            "optional" => DependencyKind::Optional,
+            "incompatible" => DependencyKind::Incompatible,
+            "embedded" => DependencyKind::Embedded,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// Maps a CurseForge `relationType`. Tools are treated as optional, and libraries the file
+    /// includes as embedded.
+    pub fn from_curseforge(relation_type: u32) -> Option<Self> {
+        let kind = match relation_type {
+            1 | 6 => DependencyKind::Embedded,
+            2 | 4 => DependencyKind::Optional,
+            3 => DependencyKind::Required,
+            5 => DependencyKind::Incompatible,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+/// A dependency on a project, or on a particular version of it if `version_id` is pinned.
+/// Modrinth dependencies can name only the version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    pub project_id: Option<String>,
+    pub version_id: Option<String>,
+    pub kind: DependencyKind,
+}
+
+impl Requirement {
+    /// A required dependency on the newest fitting version of `project_id`.
+    pub fn project(project_id: &str) -> Self {
+        Self {
+            project_id: Some(project_id.to_string()),
+            version_id: None,
+            kind: DependencyKind::Required,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match (&self.project_id, &self.version_id) {
+            (Some(project_id), Some(version_id)) => format!("{} ({})", project_id, version_id),
+            (Some(project_id), None) => project_id.clone(),
+            (None, Some(version_id)) => format!("version {}", version_id),
+            (None, None) => "an unnamed project".to_string(),
+        }
+    }
+}
+
+/// A version picked for a project, with what it depends on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub project_id: String,
+    pub version_id: String,
+    pub file_name: String,
+    pub url: Option<String>,
+    pub dependencies: Vec<Requirement>,
+}
+
+/// A mod in the directory, identified by its file, with the projects it declares incompatible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledProject {
+    pub project_id: String,
+    pub file_name: String,
+    pub incompatible: Vec<String>,
+}
+
+/// Looks up the versions of a source that fit the game version and loader being installed for.
+#[allow(async_fn_in_trait)]
+pub trait VersionProvider {
+    fn source(&self) -> Source;
+    /// Identifies the mods in `dir` by their files, whether or not modder installed them. Sources
+    /// that can't look files up find none.
+    async fn identify(&self, _dir: &Path) -> color_eyre::Result<Vec<InstalledProject>> {
+        Ok(Vec::new())
+    }
+    /// The pinned version of the requirement, or the newest fitting version of its project.
+    /// `None` if there is no such version.
+    async fn find(&self, requirement: &Requirement) -> color_eyre::Result<Option<Candidate>>;
+    /// Downloads the candidate into `dir`, returning the path it was written to.
+    async fn download(&self, candidate: &Candidate, dir: &Path) -> color_eyre::Result<PathBuf>;
+}
+
+/// Why a set of mods can't be installed together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// No version of a project fits the game version and loader.
+    Unavailable {
+        project: String,
+        required_by: Option<String>,
+    },
+    /// A mod being installed or already installed is declared incompatible by another.
+    Incompatible {
+        project: String,
+        declared_by: String,
+    },
+    /// Mods require different versions of the same project.
+    VersionMismatch {
+        project: String,
+        versions: Vec<String>,
+    },
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Conflict::Unavailable {
+                project,
+                required_by: Some(required_by),
+            } => write!(
+                f,
+                "{} requires {}, which has no version for this game version and loader",
+                required_by, project
+            ),
+            Conflict::Unavailable {
+                project,
+                required_by: None,
+            } => write!(
+                f,
+                "{} has no version for this game version and loader",
+                project
+            ),
+            Conflict::Incompatible {
+                project,
+                declared_by,
+            } => write!(f, "{} is incompatible with {}", declared_by, project),
+            Conflict::VersionMismatch { project, versions } => write!(
+                f,
+                "{} is required at different versions: {}",
+                project,
+                versions.join(", ")
+            ),
+        }
+    }
+}
+
+/// A version to download, and the projects that require it. Mods asked for directly aren't
+/// required by anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved {
+    pub candidate: Candidate,
+    pub required_by: Vec<String>,
+}
+
+/// An optional dependency that was left out, and has a version that could be installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionalDependency {
+    pub project_id: String,
+    /// The file of the version that would be installed.
+    pub file_name: String,
+    /// The file name of the mod that can use it.
+    pub wanted_by: String,
+}
+
+impl Display for OptionalDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (optional for {})", self.file_name, self.wanted_by)
+    }
+}
+
+/// Everything that has to be downloaded to install a set of mods, worked out before any of it is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Resolution {
+    /// The versions to download, each after the one that pulled it in.
+    pub install: Vec<Resolved>,
+    /// Required projects that are already installed, with the project requiring them.
+    pub satisfied: Vec<(String, String)>,
+    /// Optional dependencies that weren't asked for.
+    pub optional: Vec<OptionalDependency>,
+}
+
+/// Builds the full dependency graph of a set of mods from a source.
+pub struct Resolver<'a, P: VersionProvider> {
+    provider: &'a P,
+    /// Project ids of the mods already in the directory.
+    installed: HashSet<String>,
+    /// Projects the mods already in the directory declare incompatible, with the file declaring
+    /// it.
+    installed_incompatible: Vec<(String, String)>,
+    /// Project ids of the optional dependencies to install too.
+    with_optional: HashSet<String>,
+}
+
+impl<'a, P: VersionProvider> Resolver<'a, P> {
+    pub fn new(provider: &'a P) -> Self {
+        Self {
+            provider,
+            installed: HashSet::new(),
+            installed_incompatible: Vec::new(),
+            with_optional: HashSet::new(),
+        }
+    }
+
+    /// Treats the projects as installed: they satisfy requirements without a download.
+    pub fn installed(mut self, project_ids: impl IntoIterator<Item = String>) -> Self {
+        self.installed.extend(project_ids);
+        self
+    }
+
+    /// Treats the identified projects as installed, and the projects they declare incompatible
+    /// as conflicting with them.
+    pub fn installed_projects(
+        mut self,
+        projects: impl IntoIterator<Item = InstalledProject>,
+    ) -> Self {
+        for project in projects {
+            self.installed_incompatible.extend(
+                project
+                    .incompatible
+                    .into_iter()
+                    .map(|incompatible| (incompatible, project.file_name.clone())),
+            );
+            self.installed.insert(project.project_id);
+        }
+        self
+    }
+
+    /// Treats the projects in `dir` as installed: those the source installed, and those it
+    /// identifies by their files, such as jars added by hand.
+    pub async fn installed_in(self, dir: &Path) -> Result<Self> {
+        let source = self.provider.source();
+        let installed = DirMetadata::load(dir)?
+            .installs
+            .into_values()
+            .filter(|record| record.source == source)
+            .map(|record| record.project_id)
+            .collect_vec();
+        let identified = self.provider.identify(dir).await?;
+        Ok(self.installed(installed).installed_projects(identified))
+    }
+
+    /// Installs these optional dependencies along with the required ones.
+    pub fn with_optional(mut self, project_ids: impl IntoIterator<Item = String>) -> Self {
+        self.with_optional.extend(project_ids);
+        self
+    }
+
+    /// Resolves `roots` and everything they need, transitively. Roots are always picked, even
+    /// if installed. Every conflict found is reported at once.
+    pub async fn resolve(&self, roots: Vec<Requirement>) -> Result<Resolution> {
+        let mut resolution = Resolution::default();
+        let mut conflicts = Vec::new();
+        // Index into `resolution.install` by project id
+        let mut picked: BTreeMap<String, usize> = BTreeMap::new();
+        let mut pinned: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut incompatible: Vec<(String, String)> = Vec::new();
+        let mut queue: VecDeque<(Requirement, Option<String>)> =
+            roots.into_iter().map(|root| (root, None)).collect();
+
+        while let Some((requirement, parent)) = queue.pop_front() {
+            if let Some(project_id) = &requirement.project_id {
+                if let Some(&index) = picked.get(project_id) {
+                    let resolved = &mut resolution.install[index];
+                    if let Some(version_id) = &requirement.version_id {
+                        pinned
+                            .entry(project_id.clone())
+                            .or_default()
+                            .push(version_id.clone());
+                    }
+                    if let Some(parent) = parent
+                        && !resolved.required_by.contains(&parent)
+                    {
+                        resolved.required_by.push(parent);
+                    }
+                    continue;
+                }
+                if let Some(parent) = &parent
+                    && self.installed.contains(project_id)
+                {
+                    resolution
+                        .satisfied
+                        .push((project_id.clone(), parent.clone()));
+                    continue;
+                }
+            }
+            let Some(candidate) = self.provider.find(&requirement).await? else {
+                conflicts.push(Conflict::Unavailable {
+                    project: requirement.describe(),
+                    required_by: parent.map(|parent| self.display_name(&resolution, &parent)),
+                });
+                continue;
+            };
+            // Dependencies naming only a version are only known by project once looked up
+            if requirement.project_id.is_none()
+                && (picked.contains_key(&candidate.project_id)
+                    || parent.is_some() && self.installed.contains(&candidate.project_id))
+            {
+                queue.push_front((
+                    Requirement {
+                        project_id: Some(candidate.project_id),
+                        ..requirement
+                    },
+                    parent,
+                ));
+                continue;
+            }
+            if let Some(version_id) = &requirement.version_id {
+                pinned
+                    .entry(candidate.project_id.clone())
+                    .or_default()
+                    .push(version_id.clone());
+            }
+            for dependency in &candidate.dependencies {
+                let wanted_by = Some(candidate.project_id.clone());
+                match dependency.kind {
+                    DependencyKind::Required => queue.push_back((dependency.clone(), wanted_by)),
+                    DependencyKind::Optional => match &dependency.project_id {
+                        Some(project_id) if self.with_optional.contains(project_id) => {
+                            queue.push_back((dependency.clone(), wanted_by))
+                        }
+                        Some(project_id) if !self.installed.contains(project_id) => {
+                            resolution.optional.push(OptionalDependency {
+                                project_id: project_id.clone(),
+                                file_name: String::new(),
+                                wanted_by: candidate.project_id.clone(),
+                            })
+                        }
+                        _ => {}
+                    },
+                    DependencyKind::Incompatible => {
+                        if let Some(project_id) = &dependency.project_id {
+                            incompatible.push((project_id.clone(), candidate.project_id.clone()));
+                        }
+                    }
+                    DependencyKind::Embedded => {}
+                }
+            }
+            picked.insert(candidate.project_id.clone(), resolution.install.len());
+            resolution.install.push(Resolved {
+                candidate,
+                required_by: parent.into_iter().collect(),
+            });
+        }
+
+        for (project_id, mut versions) in pinned {
+            let picked_version = &resolution.install[picked[&project_id]].candidate.version_id;
+            versions.push(picked_version.clone());
+            let versions = versions.into_iter().unique().collect_vec();
+            if versions.len() > 1 {
+                conflicts.push(Conflict::VersionMismatch {
+                    project: self.display_name(&resolution, &project_id),
+                    versions,
+                });
+            }
+        }
+        for (project_id, declared_by) in incompatible {
+            if picked.contains_key(&project_id) || self.installed.contains(&project_id) {
+                conflicts.push(Conflict::Incompatible {
+                    project: self.display_name(&resolution, &project_id),
+                    declared_by: self.display_name(&resolution, &declared_by),
+                });
+            }
+        }
+        // Installed mods can rule out the new ones too
+        for (project_id, declared_by) in &self.installed_incompatible {
+            if picked.contains_key(project_id) {
+                conflicts.push(Conflict::Incompatible {
+                    project: self.display_name(&resolution, project_id),
+                    declared_by: declared_by.clone(),
+                });
+            }
+        }
+        // Optional dependencies something else required are installed anyway
+        resolution
+            .optional
+            .retain(|optional| !picked.contains_key(&optional.project_id));
+        let optional = std::mem::take(&mut resolution.optional)
+            .into_iter()
+            .unique_by(|optional| optional.project_id.clone())
+            .collect_vec();
+        let found = try_join_all(optional.iter().map(|optional| async move {
+            let requirement = Requirement::project(&optional.project_id);
+            self.provider.find(&requirement).await
+        }))
+        .await?;
+        resolution.optional = optional
+            .into_iter()
+            .zip(found)
+            .filter_map(|(optional, candidate)| {
+                Some(OptionalDependency {
+                    file_name: candidate?.file_name,
+                    wanted_by: self.display_name(&resolution, &optional.wanted_by),
+                    ..optional
+                })
+            })
+            .collect();
+
+        if !conflicts.is_empty() {
+            return Err(Error::Conflicts(conflicts));
+        }
+        Ok(resolution)
+    }
+
+    /// The file name of a picked project, or its id.
+    fn display_name(&self, resolution: &Resolution, project_id: &str) -> String {
+        resolution
+            .install
+            .iter()
+            .find(|resolved| resolved.candidate.project_id == project_id)
+            .map(|resolved| resolved.candidate.file_name.clone())
+            .unwrap_or_else(|| project_id.to_string())
+    }
+}
+
+impl Resolution {
+    /// Downloads every resolved version into `dir` and records why each is installed. Stops at
+    /// the first failed download.
+    pub async fn install<P: VersionProvider>(
+        &self,
+        provider: &P,
+        dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        let downloads = self.install.iter().map(|resolved| async move {
+            let candidate = &resolved.candidate;
+            match resolved.required_by.first() {
+                Some(_) => info!("Downloading dependency {}", candidate.file_name),
+                None => info!("Downloading {}", candidate.file_name),
+            }
+            let path = provider.download(candidate, dir).await?;
+            record_install(
+                &path,
+                provider.source(),
+                &candidate.project_id,
+                resolved.required_by.first().map(String::as_str),
+            );
+            for parent in resolved.required_by.iter().skip(1) {
+                record_dependent(dir, &candidate.project_id, parent);
+            }
+            Ok::<_, Error>(path)
+        });
+        let paths = try_join_all(downloads).await?;
+        for (project_id, parent) in &self.satisfied {
+            info!(
+                "Skipping dependency {}, it is already installed",
+                project_id
+            );
+            record_dependent(dir, project_id, parent);
+        }
+        Ok(paths)
+    }
+}
+
+/// Records that `parent` requires the installed `project_id`. Failures are only logged.
+fn record_dependent(dir: &Path, project_id: &str, parent: &str) {
+    let res = DirMetadata::modify(dir, |metadata| {
+        metadata.add_dependent(project_id, parent);
+    });
+    if let Err(err) = res {
+        error!("Failed to record dependency {}: {}", project_id, err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    /// Versions by project id, plus versions only reachable by id.
+    #[derive(Default)]
+    struct FakeProvider {
+        latest: HashMap<String, Candidate>,
+        by_id: HashMap<String, Candidate>,
+    }
+
+    impl FakeProvider {
+        fn with(
+            mut self,
+            project_id: &str,
+            version_id: &str,
+            dependencies: &[Requirement],
+        ) -> Self {
+            let candidate = Candidate {
+                project_id: project_id.to_string(),
+                version_id: version_id.to_string(),
+                file_name: format!("{}-{}.jar", project_id, version_id),
+                url: None,
+                dependencies: dependencies.to_vec(),
+            };
+            self.by_id.insert(version_id.to_string(), candidate.clone());
+            self.latest.insert(project_id.to_string(), candidate);
+            self
+        }
+
+        fn with_old(mut self, project_id: &str, version_id: &str) -> Self {
+            let candidate = Candidate {
+                project_id: project_id.to_string(),
+                version_id: version_id.to_string(),
+                file_name: format!("{}-{}.jar", project_id, version_id),
+                url: None,
+                dependencies: Vec::new(),
+            };
+            self.by_id.insert(version_id.to_string(), candidate);
+            self
+        }
+    }
+
+    impl VersionProvider for FakeProvider {
+        fn source(&self) -> Source {
+            Source::Modrinth
+        }
+        async fn find(&self, requirement: &Requirement) -> color_eyre::Result<Option<Candidate>> {
+            Ok(match (&requirement.version_id, &requirement.project_id) {
+                (Some(version_id), _) => self.by_id.get(version_id).cloned(),
+                (None, Some(project_id)) => self.latest.get(project_id).cloned(),
+                (None, None) => None,
+            })
+        }
+        async fn download(&self, candidate: &Candidate, dir: &Path) -> color_eyre::Result<PathBuf> {
+            Ok(dir.join(&candidate.file_name))
+        }
+    }
+
+    fn dependency(project_id: &str, kind: DependencyKind) -> Requirement {
+        Requirement {
+            kind,
+            ..Requirement::project(project_id)
+        }
+    }
+
+    fn picked(resolution: &Resolution) -> Vec<(&str, Vec<&str>)> {
+        resolution
+            .install
+            .iter()
+            .map(|resolved| {
+                (
+                    resolved.candidate.file_name.as_str(),
+                    resolved.required_by.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_resolve_transitive() {
+        let provider = FakeProvider::default()
+            .with(
+                "create",
+                "c1",
+                &[
+                    dependency("flywheel", DependencyKind::Required),
+                    dependency("jei", DependencyKind::Optional),
+                    // No version fits, so it isn't offered
+                    dependency("emi", DependencyKind::Optional),
+                    dependency("registrate", DependencyKind::Embedded),
+                ],
+            )
+            .with(
+                "flywheel",
+                "f1",
+                &[dependency("fabric-api", DependencyKind::Required)],
+            )
+            .with(
+                "sodium",
+                "s1",
+                &[dependency("fabric-api", DependencyKind::Required)],
+            )
+            .with("fabric-api", "a1", &[])
+            .with("jei", "j1", &[]);
+        let resolution = Resolver::new(&provider)
+            .resolve(vec![
+                Requirement::project("create"),
+                Requirement::project("sodium"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            picked(&resolution),
+            vec![
+                ("create-c1.jar", vec![]),
+                ("sodium-s1.jar", vec![]),
+                ("flywheel-f1.jar", vec!["create"]),
+                ("fabric-api-a1.jar", vec!["sodium", "flywheel"]),
+            ]
+        );
+        assert_eq!(
+            resolution.optional,
+            vec![OptionalDependency {
+                project_id: "jei".to_string(),
+                file_name: "jei-j1.jar".to_string(),
+                wanted_by: "create-c1.jar".to_string(),
+            }]
+        );
+
+        let resolution = Resolver::new(&provider)
+            .installed(["fabric-api".to_string()])
+            .with_optional(["jei".to_string()])
+            .resolve(vec![Requirement::project("create")])
+            .await
+            .unwrap();
+        assert_eq!(
+            picked(&resolution),
+            vec![
+                ("create-c1.jar", vec![]),
+                ("flywheel-f1.jar", vec!["create"]),
+                ("jei-j1.jar", vec!["create"]),
+            ]
+        );
+        assert_eq!(
+            resolution.satisfied,
+            vec![("fabric-api".to_string(), "flywheel".to_string())]
+        );
+        assert!(resolution.optional.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_pinned_versions() {
+        let pinned = |version_id: &str| Requirement {
+            project_id: Some("lib".to_string()),
+            version_id: Some(version_id.to_string()),
+            kind: DependencyKind::Required,
+        };
+        let provider = FakeProvider::default()
+            .with("a", "a1", &[pinned("l1")])
+            .with(
+                "b",
+                "b1",
+                &[Requirement {
+                    project_id: None,
+                    ..pinned("l1")
+                }],
+            )
+            .with("c", "c1", &[pinned("l2")])
+            .with("lib", "l2", &[])
+            .with_old("lib", "l1");
+        let resolution = Resolver::new(&provider)
+            .resolve(vec![Requirement::project("a"), Requirement::project("b")])
+            .await
+            .unwrap();
+        assert_eq!(
+            picked(&resolution),
+            vec![
+                ("a-a1.jar", vec![]),
+                ("b-b1.jar", vec![]),
+                ("lib-l1.jar", vec!["a", "b"]),
+            ]
+        );
+
+        let err = Resolver::new(&provider)
+            .resolve(vec![Requirement::project("a"), Requirement::project("c")])
+            .await
+            .unwrap_err();
+        let Error::Conflicts(conflicts) = err else {
+            panic!("Expected conflicts, got {:?}", err);
+        };
+        assert_eq!(
+            conflicts,
+            vec![Conflict::VersionMismatch {
+                project: "lib-l1.jar".to_string(),
+                versions: vec!["l1".to_string(), "l2".to_string()],
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_conflicts() {
+        let provider = FakeProvider::default()
+            .with(
+                "sodium",
+                "s1",
+                &[dependency("optifine", DependencyKind::Incompatible)],
+            )
+            .with(
+                "iris",
+                "i1",
+                &[dependency("missing", DependencyKind::Required)],
+            );
+        let err = Resolver::new(&provider)
+            .installed(["optifine".to_string()])
+            .resolve(vec![
+                Requirement::project("sodium"),
+                Requirement::project("iris"),
+            ])
+            .await
+            .unwrap_err();
+        let Error::Conflicts(conflicts) = err else {
+            panic!("Expected conflicts, got {:?}", err);
+        };
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "iris-i1.jar requires missing, which has no version for this game version and \
+                 loader",
+                "sodium-s1.jar is incompatible with optifine",
+            ]
+        );
+
+        // A jar added by hand is only known by its file, and can rule out a new mod too
+        let err = Resolver::new(&provider)
+            .installed_projects([InstalledProject {
+                project_id: "optifine".to_string(),
+                file_name: "OptiFine_1.21.jar".to_string(),
+                incompatible: vec!["iris".to_string()],
+            }])
+            .resolve(vec![Requirement::project("iris")])
+            .await
+            .unwrap_err();
+        let Error::Conflicts(conflicts) = err else {
+            panic!("Expected conflicts, got {:?}", err);
+        };
+        assert_eq!(
+            conflicts.last().map(ToString::to_string).as_deref(),
+            Some("OptiFine_1.21.jar is incompatible with iris-i1.jar")
+        );
+    }
+}
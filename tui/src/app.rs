@@ -182,12 +182,12 @@ impl App {
     fn render(&mut self, tui: &mut Tui) -> Result<()> {
         tui.draw(|frame| {
             for component in self.components.iter_mut() {
-                if component.get_mode() == self.mode {
-                    if let Err(err) = component.draw(frame, frame.area()) {
-                        let _ = self
-                            .action_tx
-                            .send(Action::Error(format!("Failed to draw: {:?}", err)));
-                    }
+                if component.get_mode() == self.mode
+                    && let Err(err) = component.draw(frame, frame.area())
+                {
+                    let _ = self
+                        .action_tx
+                        .send(Action::Error(format!("Failed to draw: {:?}", err)));
                 }
             }
         })?;
@@ -1,15 +1,18 @@
-use super::Component;
+use super::{Component, details::Details};
 use crate::{action::Action, app::Mode, config::Config};
 use color_eyre::Result;
 use crossterm::event::KeyCode;
-use futures::{executor::block_on, lock::Mutex};
+use futures::executor::block_on;
 use modder::{
     MOD_LOADERS, ModLoader, calc_sha512,
     cli::{SOURCES, Source},
-    curseforge_wrapper::{API_KEY, CurseForgeAPI},
-    gh_releases::{GHReleasesAPI, get_mod_from_release},
-    metadata::Metadata,
-    modrinth_wrapper::modrinth::{self, GetProject, Mod, Modrinth, VersionData},
+    content::ContentType,
+    curseforge_wrapper::{CurseForgeAPI, CurseForgeProvider, SearchOptions},
+    gh_releases::{AssetPattern, AssetSelector, GHReleasesAPI, RepoSpec},
+    metadata::{DirMetadata, Metadata},
+    modrinth_wrapper::modrinth::{GetProject, Modrinth, ModrinthProvider, VersionData},
+    record_install,
+    resolver::{OptionalDependency, Requirement, Resolver, VersionProvider},
 };
 use ratatui::{prelude::*, widgets::*};
 use std::{
@@ -17,8 +20,8 @@ use std::{
     fmt::Debug,
     fs,
     hash::{Hash, Hasher},
-    path::PathBuf,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::LazyLock,
 };
 use style::palette::tailwind::SLATE;
 use throbber_widgets_tui::{Throbber, ThrobberState};
@@ -45,8 +48,18 @@ pub struct AddComponent {
     logger_state: TuiWidgetState,
     throbber_state: ThrobberState,
     loader_list: LoaderList,
+    details: Option<Details>,
+    optional_list: OptionalList,
+    /// The last page of search results loaded into `search_result_list`.
+    results_page: u32,
+    has_more_results: bool,
 }
 
+const MODRINTH_PAGE_SIZE: u16 = 100;
+const CURSEFORGE_PAGE_SIZE: u32 = 30;
+/// Built once, so the config and credentials files are read (and warned about) only once.
+static CURSEFORGE: LazyLock<CurseForgeAPI> = LazyLock::new(CurseForgeAPI::from_env);
+
 #[derive(Debug, Clone, Default)]
 struct SourceList {
     list_items: Vec<Source>,
@@ -72,6 +85,17 @@ struct CurrentModsList {
     state: ListState,
 }
 
+/// Optional dependencies of the mods being added, offered before they are downloaded.
+#[derive(Debug, Clone, Default)]
+struct OptionalList {
+    list_items: Vec<OptionalDependency>,
+    /// Project ids of the picked dependencies
+    picked: HashSet<String>,
+    state: ListState,
+    /// The mods waiting on the pick
+    pending: Vec<SearchResult>,
+}
+
 #[derive(Default, Clone)]
 struct AddList {
     list_items: Vec<SearchResult>,
@@ -83,7 +107,12 @@ trait AddListItem {
     fn get_name(&self) -> String;
 }
 trait Downloadable {
-    async fn download(&self, dir: PathBuf) -> Result<()>;
+    /// Downloads the mod, with the optional dependencies in `with_optional` if it can use them.
+    async fn download(&self, dir: PathBuf, with_optional: Vec<String>) -> Result<()>;
+    /// The optional dependencies that could be added along with the mod.
+    async fn optional_dependencies(&self, _dir: PathBuf) -> Result<Vec<OptionalDependency>> {
+        Ok(Vec::new())
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Hash, Eq)]
@@ -105,6 +134,7 @@ pub struct GithubAddListItem {
     repo: String,
     version: String,
     game_version: String,
+    loader: ModLoader,
     selected: bool,
 }
 
@@ -135,6 +165,7 @@ impl Hash for GithubAddListItem {
         self.repo.hash(state);
         self.version.hash(state);
         self.game_version.hash(state);
+        self.loader.hash(state);
     }
 }
 
@@ -145,6 +176,7 @@ impl PartialEq for GithubAddListItem {
             && self.repo == other.repo
             && self.version == other.version
             && self.game_version == other.game_version
+            && self.loader == other.loader
     }
 }
 
@@ -157,6 +189,36 @@ impl AddListItem for ModrinthAddListItem {
 }
 
 impl SearchResult {
+    /// The source, project id, game version and loader to look up the details of this result with.
+    fn details_query(&self) -> (Source, String, String, Option<ModLoader>) {
+        match self {
+            SearchResult::ModrinthMod(mod_) => (
+                Source::Modrinth,
+                mod_.project_id.clone(),
+                mod_.game_version.clone(),
+                Some(mod_.mod_loader.clone()),
+            ),
+            SearchResult::Github(github) => (
+                github.source.clone(),
+                github.repo.clone(),
+                github.game_version.clone(),
+                None,
+            ),
+            SearchResult::CurseForgeMod(curseforge) => (
+                Source::CurseForge,
+                curseforge.id.to_string(),
+                curseforge.game_version.clone(),
+                Some(curseforge.loader.clone()),
+            ),
+        }
+    }
+    async fn optional_dependencies(&self, dir: PathBuf) -> Result<Vec<OptionalDependency>> {
+        match self {
+            SearchResult::ModrinthMod(mod_) => mod_.optional_dependencies(dir).await,
+            SearchResult::Github(github) => github.optional_dependencies(dir).await,
+            SearchResult::CurseForgeMod(curseforge) => curseforge.optional_dependencies(dir).await,
+        }
+    }
     fn get_is_selected(&self) -> bool {
         match self {
             SearchResult::ModrinthMod(mod_) => mod_.selected,
@@ -173,39 +235,33 @@ impl SearchResult {
     }
 }
 
+impl ModrinthAddListItem {
+    fn provider(&self) -> ModrinthProvider {
+        ModrinthProvider {
+            game_version: self.game_version.clone(),
+            loader: self.mod_loader.clone(),
+            content_type: ContentType::Mod,
+            channel: None,
+        }
+    }
+}
+
 impl Downloadable for ModrinthAddListItem {
-    async fn download(&self, dir: PathBuf) -> Result<()> {
+    async fn download(&self, dir: PathBuf, with_optional: Vec<String>) -> Result<()> {
         debug!(game_version = ?&self.game_version);
         debug!(slug = ?&self.slug);
         debug!(mod_loader = ?&self.mod_loader);
-        let version_data =
-            Modrinth::get_version(&self.slug, &self.game_version, self.mod_loader.clone()).await;
-        if let Some(version_data) = version_data {
-            modrinth::download_file(
-                &version_data.clone().files.unwrap()[0],
-                &dir.to_string_lossy(),
-            )
-            .await;
-            let mod_ = Mod {
-                slug: self.slug.clone(),
-                title: self.name.clone(),
-            };
-            let dependencies = Arc::new(Mutex::new(Vec::new()));
-            Modrinth::download_dependencies(
-                &mod_,
-                &self.game_version,
-                dependencies,
-                &dir.to_string_lossy(),
-                self.mod_loader.clone(),
-            )
-            .await;
-        } else {
-            error!(
-                "Could not find version {} for {}",
-                &self.game_version, &self.name
-            );
-        }
-        Ok(())
+        install(
+            &self.provider(),
+            &self.slug,
+            &self.name,
+            &dir,
+            with_optional,
+        )
+        .await
+    }
+    async fn optional_dependencies(&self, dir: PathBuf) -> Result<Vec<OptionalDependency>> {
+        optional_dependencies(&self.provider(), &self.slug, &dir).await
     }
 }
 impl AddListItem for GithubAddListItem {
@@ -219,50 +275,99 @@ impl AddListItem for CurseForgeAddListItem {
     }
 }
 impl Downloadable for GithubAddListItem {
-    async fn download(&self, dir: PathBuf) -> Result<()> {
-        let gh = GHReleasesAPI::new();
-        let [owner, repo] = self.repo.split('/').collect::<Vec<&str>>()[..] else {
+    async fn download(&self, dir: PathBuf, _with_optional: Vec<String>) -> Result<()> {
+        let spec = RepoSpec::parse(&self.repo);
+        let Some((spec, gh)) = spec.and_then(|spec| {
+            let gh = GHReleasesAPI::from_env(None).for_repo(&spec, Some(&self.source))?;
+            Some((spec, gh))
+        }) else {
             error!("Invalid repo {}", self.repo);
             return Ok(());
         };
-        let version_data = gh.get_releases(owner, repo).await;
-        if let Ok(version_data) = version_data {
-            let release = get_mod_from_release(&version_data, "fabric", &self.game_version).await;
-            if let Ok(release) = release {
-                let url = release.get_download_url().unwrap();
-                let file_name = url.path_segments().unwrap().last().unwrap();
-                let path = format!("{}{}", dir.to_string_lossy(), file_name);
+        let pattern = DirMetadata::load(&dir)?
+            .get_asset_pattern(&self.repo)
+            .map(AssetPattern::parse)
+            .transpose()?;
+        let selector = AssetSelector::new(&self.game_version, self.loader.clone()).pattern(pattern);
+        let path = gh
+            .download_mod(&spec.owner, &spec.repo, &selector, &dir, &self.source)
+            .await;
+        match path {
+            Ok(path) => {
                 debug!(path = ?path);
-                release
-                    .download(path.clone().into(), self.repo.clone())
-                    .await
-                    .unwrap();
-            } else {
-                error!(err=?release.err().unwrap().to_string(), "Error finding or downloading mod");
+                record_install(&path, self.source.clone(), &self.repo, None);
             }
-        } else {
-            error!(
-                "Could not find version {} for {}: {version_data:?}",
-                &self.game_version, &self.name
-            );
+            Err(err) => error!(
+                err = ?err.to_string(),
+                "Could not find version {} for {}", &self.game_version, &self.name
+            ),
         }
         Ok(())
     }
 }
 
+impl CurseForgeAddListItem {
+    fn provider(&self) -> CurseForgeProvider {
+        CurseForgeProvider {
+            api: CURSEFORGE.clone(),
+            game_version: self.game_version.clone(),
+            loader: self.loader.clone(),
+            content_type: ContentType::Mod,
+        }
+    }
+}
+
 impl Downloadable for CurseForgeAddListItem {
-    async fn download(&self, dir: PathBuf) -> Result<()> {
-        let cf = CurseForgeAPI::new(env!("CURSEFORGE_API_KEY").to_string());
-        let files = cf
-            .get_mod_files(self.id, &self.game_version, self.loader.clone())
-            .await?;
-        let file_id = files[0].id;
-        let download_res = cf.download_mod(self.id, file_id, dir).await;
-        if download_res.is_err() {
-            return Err(download_res.err().unwrap().into());
+    async fn download(&self, dir: PathBuf, with_optional: Vec<String>) -> Result<()> {
+        let project_id = self.id.to_string();
+        install(
+            &self.provider(),
+            &project_id,
+            &self.name,
+            &dir,
+            with_optional,
+        )
+        .await
+    }
+    async fn optional_dependencies(&self, dir: PathBuf) -> Result<Vec<OptionalDependency>> {
+        optional_dependencies(&self.provider(), &self.id.to_string(), &dir).await
+    }
+}
+
+/// Installs a mod with everything it requires, logging why if they can't be satisfied.
+async fn install<P: VersionProvider>(
+    provider: &P,
+    project_id: &str,
+    name: &str,
+    dir: &Path,
+    with_optional: Vec<String>,
+) -> Result<()> {
+    let resolution = Resolver::new(provider)
+        .installed_in(dir)
+        .await?
+        .with_optional(with_optional)
+        .resolve(vec![Requirement::project(project_id)])
+        .await;
+    match resolution {
+        Ok(resolution) => {
+            resolution.install(provider, dir).await?;
         }
-        Ok(())
+        Err(err) => error!("Could not add {}: {}", name, err),
     }
+    Ok(())
+}
+
+async fn optional_dependencies<P: VersionProvider>(
+    provider: &P,
+    project_id: &str,
+    dir: &Path,
+) -> Result<Vec<OptionalDependency>> {
+    let resolution = Resolver::new(provider)
+        .installed_in(dir)
+        .await?
+        .resolve(vec![Requirement::project(project_id)])
+        .await?;
+    Ok(resolution.optional)
 }
 
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -276,6 +381,8 @@ enum State {
     VersionInput,
     SelectedList,
     ChangeLoader,
+    Details,
+    OptionalDependencies,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -288,6 +395,21 @@ struct CurrentModsListItem {
 
 const SELECTED_STYLE: Style = Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD);
 
+impl OptionalList {
+    fn toggle_selected(&mut self) {
+        let Some(optional) = self
+            .state
+            .selected()
+            .and_then(|selected| self.list_items.get(selected))
+        else {
+            return;
+        };
+        if !self.picked.remove(&optional.project_id) {
+            self.picked.insert(optional.project_id.clone());
+        }
+    }
+}
+
 impl FromIterator<CurrentModsListItem> for CurrentModsList {
     fn from_iter<I: IntoIterator<Item = CurrentModsListItem>>(iter: I) -> Self {
         let items = iter.into_iter().collect();
@@ -445,13 +567,91 @@ impl AddComponent {
         };
     }
     pub fn search(&mut self) -> Result<Option<Action>> {
+        if self.version_input.value().is_empty() {
+            return Ok(None);
+        }
+        self.results_page = 0;
+        self.search_result_list.list_items = self.fetch_results(0)?;
+        self.state = State::SearchResultList;
+        Ok(None)
+    }
+    /// Downloads the mods in the background, with the picked optional dependencies.
+    fn download(&mut self, selected: Vec<SearchResult>, with_optional: Vec<String>) {
+        info!("Downloading mods");
+        self.state = State::Downloading;
+        for selected in selected {
+            let dir = self.dir.clone();
+            let with_optional = with_optional.clone();
+            match selected {
+                SearchResult::ModrinthMod(mod_) => {
+                    info!("Downloading {}", mod_.get_name());
+                    tokio::spawn(async move {
+                        let download_res = mod_.download(dir, with_optional).await;
+                        match download_res {
+                            Ok(_) => {
+                                info!("Downloaded {}", mod_.get_name());
+                            }
+                            Err(err) => {
+                                error!("Failed to download {}: {err:?}", mod_.get_name());
+                            }
+                        }
+                    });
+                }
+                SearchResult::Github(mod_) => {
+                    info!("Downloading {}", mod_.get_name());
+                    tokio::spawn(async move {
+                        let download_res = mod_.download(dir, with_optional).await;
+                        match download_res {
+                            Ok(_) => {
+                                info!("Downloaded {}", mod_.get_name());
+                            }
+                            Err(err) => {
+                                error!("Failed to download {}: {err:?}", mod_.get_name());
+                            }
+                        }
+                    });
+                }
+                SearchResult::CurseForgeMod(mod_) => {
+                    info!("Downloading {}", mod_.get_name());
+                    tokio::spawn(async move {
+                        let res = mod_.download(dir, with_optional).await;
+                        match res {
+                            Ok(_) => {
+                                info!("Downloaded {}", mod_.get_name());
+                            }
+                            Err(err) => {
+                                error!("Failed to download {}: {err:?}", mod_.get_name());
+                            }
+                        }
+                    });
+                }
+            }
+        }
+        let dir = self.dir.clone();
+        let items = futures::executor::block_on(async move { get_mods(dir).await });
+        info!("Finished downloading mods");
+        self.list.list_items = items;
+        self.state = State::Normal;
+    }
+    /// Appends the next page of results for the current search to the results list.
+    fn load_more(&mut self) -> Result<()> {
+        if !self.has_more_results {
+            info!("No more results");
+            return Ok(());
+        }
+        let results = self.fetch_results(self.results_page + 1)?;
+        self.results_page += 1;
+        self.search_result_list.list_items.extend(results);
+        Ok(())
+    }
+    /// Runs the current search for the 0-based `page` of results, remembering whether there are
+    /// more pages after it.
+    fn fetch_results(&mut self, page: u32) -> Result<Vec<SearchResult>> {
         let version = self.version_input.value();
         let loader_idx = self.loader_list.state.selected().unwrap_or_default();
 
         let loader = self.loader_list.list_items[loader_idx].clone();
-        if version.is_empty() {
-            return Ok(None);
-        }
+        self.has_more_results = false;
         let search_term = self.input.value();
         let first_search = self.search_result_list.selected_items.is_empty();
         let search_results = match self.source_list.state.selected() {
@@ -459,9 +659,14 @@ impl AddComponent {
                 let selected = self.source_list.list_items[selected].clone();
                 match selected {
                     Source::Modrinth => {
-                        let mods =
-                            futures::executor::block_on(Modrinth::search_mods(search_term, 100, 0));
+                        let offset = page * MODRINTH_PAGE_SIZE as u32;
+                        let mods = futures::executor::block_on(Modrinth::search_mods(
+                            search_term,
+                            MODRINTH_PAGE_SIZE,
+                            offset as u16,
+                        ));
                         debug!(search = ?search_term);
+                        self.has_more_results = offset + (mods.hits.len() as u32) < mods.total_hits;
                         let hits = mods.hits;
                         debug!(search = ?hits);
                         hits.into_iter()
@@ -490,13 +695,18 @@ impl AddComponent {
                             })
                             .collect::<Vec<SearchResult>>()
                     }
-                    Source::Github => {
-                        let split = search_term.split('/').collect::<Vec<&str>>();
-                        let repo = split.last().unwrap_or(&"");
-                        let owner = split.first().unwrap_or(&"");
-                        let releases = futures::executor::block_on(
-                            GHReleasesAPI::new().get_releases(owner, repo),
-                        );
+                    Source::Github | Source::Gitea | Source::Gitlab => {
+                        let releases = match RepoSpec::parse(search_term).and_then(|spec| {
+                            let gh =
+                                GHReleasesAPI::from_env(None).for_repo(&spec, Some(&selected))?;
+                            Some((spec, gh))
+                        }) {
+                            Some((spec, gh)) => futures::executor::block_on(
+                                gh.get_releases(&spec.owner, &spec.repo),
+                            )
+                            .map_err(|err| err.to_string()),
+                            None => Err(format!("Invalid repo {}", search_term)),
+                        };
                         if let Ok(releases) = releases {
                             releases
                                 .into_iter()
@@ -506,10 +716,11 @@ impl AddComponent {
                                     };
                                     let mut github = GithubAddListItem {
                                         name: release.name?,
-                                        source: Source::Github,
+                                        source: selected.clone(),
                                         repo: search_term.to_string(),
                                         version: release.tag_name.clone(),
                                         game_version: version.to_string(),
+                                        loader: loader.clone(),
                                         selected: false,
                                     };
                                     let enabled = if first_search {
@@ -524,7 +735,7 @@ impl AddComponent {
                                 })
                                 .collect::<Vec<SearchResult>>()
                         } else {
-                            error!(err=?releases.err().unwrap().to_string(), "Error finding or downloading mod");
+                            error!(err=?releases.err().unwrap(), "Error finding or downloading mod");
                             Vec::new()
                         }
                     }
@@ -533,14 +744,22 @@ impl AddComponent {
                         let loader_idx = self.loader_list.state.selected().unwrap_or_default();
                         let search = self.input.value();
                         let loader = self.loader_list.list_items[loader_idx].clone();
-                        let cf = CurseForgeAPI::new(env!("CURSEFORGE_API_KEY").to_string());
+                        let cf = &*CURSEFORGE;
                         info!(
                             "Searching curseforge for {}. This may take a few seconds",
                             search
                         );
-                        let search_res =
-                            block_on(cf.search_mods(version, loader.clone(), search, 30))?;
+                        let options = SearchOptions::new(search)
+                            .game_version(version)
+                            .loader(loader.clone())
+                            .page_size(CURSEFORGE_PAGE_SIZE)
+                            .page(page);
+                        let search_res = block_on(cf.search(&options))?;
+                        self.has_more_results = search_res
+                            .pagination
+                            .is_some_and(|pagination| pagination.has_more());
                         search_res
+                            .data
                             .into_iter()
                             .map(|mod_| {
                                 let mut curseforge_add_list_item = CurseForgeAddListItem {
@@ -584,9 +803,7 @@ impl AddComponent {
             }
             None => Vec::new(),
         };
-        self.search_result_list.list_items = search_results;
-        self.state = State::SearchResultList;
-        Ok(None)
+        Ok(search_results)
     }
 }
 
@@ -759,11 +976,7 @@ impl Component for AddComponent {
 
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         match action {
-            Action::Tick => {
-                if self.state == State::Downloading {
-                    self.throbber_state.calc_next()
-                }
-            }
+            Action::Tick if self.state == State::Downloading => self.throbber_state.calc_next(),
             Action::Render => {
                 // add any logic here that should run on every render
             }
@@ -802,13 +1015,12 @@ impl Component for AddComponent {
                 KeyCode::Char('g') | KeyCode::Home => self.source_list.select_first(),
                 KeyCode::Char('G') | KeyCode::End => self.source_list.select_last(),
                 KeyCode::Char('q') => return Ok(Some(Action::Quit)),
-                KeyCode::Enter => {
+                KeyCode::Enter
                     if !self.version_input.value().is_empty()
                         && !self.input.value().is_empty()
-                        && self.loader_list.state.selected().is_some()
-                    {
-                        self.search()?;
-                    }
+                        && self.loader_list.state.selected().is_some() =>
+                {
+                    self.search()?;
                 }
                 KeyCode::Esc => {
                     self.state = State::Normal;
@@ -859,8 +1071,43 @@ impl Component for AddComponent {
             }
             return Ok(None);
         }
+        if self.state == State::Details {
+            if let Some(details) = self.details.as_mut() {
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => details.scroll_down(),
+                    KeyCode::Char('k') | KeyCode::Up => details.scroll_up(),
+                    KeyCode::Char('g') | KeyCode::Home => details.scroll_top(),
+                    KeyCode::Char('q') => return Ok(Some(Action::Quit)),
+                    KeyCode::Esc | KeyCode::Char('i') => {
+                        self.details = None;
+                        self.state = State::SearchResultList;
+                    }
+                    _ => {}
+                }
+            }
+            return Ok(None);
+        }
         if self.state == State::SearchResultList {
             match key.code {
+                KeyCode::Char('m') => self.load_more()?,
+                KeyCode::Char('i') => {
+                    let Some(selected) = self
+                        .search_result_list
+                        .state
+                        .selected()
+                        .and_then(|selected| self.search_result_list.list_items.get(selected))
+                    else {
+                        return Ok(None);
+                    };
+                    let (source, id, game_version, loader) = selected.details_query();
+                    match Details::fetch(&source, &id, Some(&game_version), loader) {
+                        Ok(details) => {
+                            self.details = Some(details);
+                            self.state = State::Details;
+                        }
+                        Err(err) => error!("Failed to get the details of {}: {}", id, err),
+                    }
+                }
                 KeyCode::Char('h') | KeyCode::Left => self.search_result_list.select_none(),
                 KeyCode::Char('j') | KeyCode::Down => self.search_result_list.select_next(),
                 KeyCode::Char('k') | KeyCode::Up => self.search_result_list.select_previous(),
@@ -888,73 +1135,57 @@ impl Component for AddComponent {
                         info!("No mod selected");
                         return Ok(None);
                     }
-                    info!("Downloading mods");
-                    self.state = State::Downloading;
                     self.input.reset();
                     self.search_result_list.state.select(None);
                     self.search_result_list.selected_items.clear();
 
-                    for selected in selected {
-                        let dir = self.dir.clone();
-                        match selected {
-                            SearchResult::ModrinthMod(mod_) => {
-                                info!("Downloading {}", mod_.get_name());
-                                tokio::spawn(async move {
-                                    let download_res = mod_.download(dir).await;
-                                    match download_res {
-                                        Ok(_) => {
-                                            info!("Downloaded {}", mod_.get_name());
-                                        }
-                                        Err(err) => {
-                                            error!(
-                                                "Failed to download {}: {err:?}",
-                                                mod_.get_name()
-                                            );
-                                        }
-                                    }
-                                });
-                            }
-                            SearchResult::Github(mod_) => {
-                                info!("Downloading {}", mod_.get_name());
-                                tokio::spawn(async move {
-                                    let download_res = mod_.download(dir).await;
-                                    match download_res {
-                                        Ok(_) => {
-                                            info!("Downloaded {}", mod_.get_name());
-                                        }
-                                        Err(err) => {
-                                            error!(
-                                                "Failed to download {}: {err:?}",
-                                                mod_.get_name()
-                                            );
-                                        }
-                                    }
-                                });
-                            }
-                            SearchResult::CurseForgeMod(mod_) => {
-                                info!("Downloading {}", mod_.get_name());
-                                tokio::spawn(async move {
-                                    let res = mod_.download(dir).await;
-                                    match res {
-                                        Ok(_) => {
-                                            info!("Downloaded {}", mod_.get_name());
-                                        }
-                                        Err(err) => {
-                                            error!(
-                                                "Failed to download {}: {err:?}",
-                                                mod_.get_name()
-                                            );
-                                        }
-                                    }
-                                });
+                    let mut optional = Vec::new();
+                    for selected in &selected {
+                        let dependencies =
+                            block_on(selected.optional_dependencies(self.dir.clone()));
+                        for dependency in dependencies.unwrap_or_default() {
+                            let project_id = &dependency.project_id;
+                            if !optional
+                                .iter()
+                                .any(|other: &OptionalDependency| &other.project_id == project_id)
+                            {
+                                optional.push(dependency);
                             }
                         }
                     }
-                    let dir = self.dir.clone();
-                    let items = futures::executor::block_on(async move { get_mods(dir).await });
-                    info!("Finished downloading mods");
-                    self.list.list_items = items;
-                    self.state = State::Normal;
+                    let selected = selected.into_iter().collect();
+                    if optional.is_empty() {
+                        self.download(selected, Vec::new());
+                        return Ok(None);
+                    }
+                    self.optional_list = OptionalList {
+                        list_items: optional,
+                        pending: selected,
+                        ..Default::default()
+                    };
+                    self.optional_list.state.select_first();
+                    self.state = State::OptionalDependencies;
+                }
+                _ => {}
+            };
+            return Ok(None);
+        }
+        if self.state == State::OptionalDependencies {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => self.optional_list.state.select_next(),
+                KeyCode::Char('k') | KeyCode::Up => self.optional_list.state.select_previous(),
+                KeyCode::Char('g') | KeyCode::Home => self.optional_list.state.select_first(),
+                KeyCode::Char('G') | KeyCode::End => self.optional_list.state.select_last(),
+                KeyCode::Char('q') => return Ok(Some(Action::Quit)),
+                KeyCode::Char(' ') => self.optional_list.toggle_selected(),
+                KeyCode::Enter => {
+                    let pending = std::mem::take(&mut self.optional_list.pending);
+                    let picked = self.optional_list.picked.drain().collect();
+                    self.download(pending, picked);
+                }
+                KeyCode::Esc => {
+                    let pending = std::mem::take(&mut self.optional_list.pending);
+                    self.download(pending, Vec::new());
                 }
                 _ => {}
             };
@@ -1032,7 +1263,11 @@ impl Component for AddComponent {
                     .border_type(BorderType::Rounded)
                     .border_style(search_results_list_border)
                     .title_top(Line::raw("Search Results").centered().bold())
-                    .title_bottom(Line::raw("Press `l` to select").right_aligned().bold()),
+                    .title_bottom(
+                        Line::raw("Press `l` to select, `i` for details, `m` for more")
+                            .right_aligned()
+                            .bold(),
+                    ),
             );
 
         let search_results_list_border = if self.state == State::SelectedList {
@@ -1131,6 +1366,12 @@ impl Component for AddComponent {
                 Source::Modrinth => "MR",
                 Source::Github => "GH",
                 Source::CurseForge => "CF",
+                Source::Gitea => "GT",
+                Source::Gitlab => "GL",
+                Source::Hangar => "HG",
+                Source::Maven => "MV",
+                Source::Url => "URL",
+                Source::Local => "LC",
             };
             ListItem::new(val.to_string()).style(Style::default().fg(Color::Yellow))
         }))
@@ -1153,6 +1394,10 @@ impl Component for AddComponent {
                 ModLoader::NeoForge => "NeoForge",
                 ModLoader::Cauldron => "Cauldron",
                 ModLoader::LiteLoader => "LiteLoader",
+                ModLoader::Paper => "Paper",
+                ModLoader::Spigot => "Spigot",
+                ModLoader::Bukkit => "Bukkit",
+                ModLoader::Velocity => "Velocity",
                 ModLoader::Any => "Any",
             };
             ListItem::new(val.to_string()).style(Style::default().fg(Color::Yellow))
@@ -1199,11 +1444,57 @@ impl Component for AddComponent {
         }
         frame.render_stateful_widget(source_list, ltr, &mut self.source_list.state);
         frame.render_stateful_widget(loader_list, lm2, &mut self.loader_list.state);
-        frame.render_stateful_widget(
-            search_results_list,
-            right_top,
-            &mut self.search_result_list.state,
-        );
+        match &self.details {
+            Some(details) if self.state == State::Details => {
+                let details_widget = details.widget().block(
+                    Block::new()
+                        .borders(Borders::ALL)
+                        .padding(Padding::uniform(1))
+                        .border_type(BorderType::Rounded)
+                        .border_style(Style::default().fg(Color::Yellow))
+                        .title_top(Line::raw("Details").centered().bold())
+                        .title_bottom(Line::raw("Press `Esc` to close").right_aligned().bold()),
+                );
+                frame.render_widget(details_widget, right_top);
+            }
+            _ if self.state == State::OptionalDependencies => {
+                let items = self.optional_list.list_items.iter().map(|optional| {
+                    let check = if self.optional_list.picked.contains(&optional.project_id) {
+                        "[x] "
+                    } else {
+                        "[ ] "
+                    };
+                    ListItem::new(format!("{}{}", check, optional))
+                });
+                let optional_list = List::new(items)
+                    .highlight_style(SELECTED_STYLE)
+                    .highlight_symbol("> ")
+                    .highlight_spacing(HighlightSpacing::Always)
+                    .block(
+                        Block::new()
+                            .borders(Borders::ALL)
+                            .padding(Padding::uniform(1))
+                            .border_type(BorderType::Rounded)
+                            .border_style(Style::default().fg(Color::Yellow))
+                            .title_top(Line::raw("Optional Dependencies").centered().bold())
+                            .title_bottom(
+                                Line::raw("Press `Space` to pick, `Enter` to add, `Esc` to skip")
+                                    .right_aligned()
+                                    .bold(),
+                            ),
+                    );
+                frame.render_stateful_widget(
+                    optional_list,
+                    right_top,
+                    &mut self.optional_list.state,
+                );
+            }
+            _ => frame.render_stateful_widget(
+                search_results_list,
+                right_top,
+                &mut self.search_result_list.state,
+            ),
+        }
         frame.render_widget(log_widget, right_bottom);
         frame.render_stateful_widget(selected_list, lb_1, &mut self.selected_list_state);
         frame.render_stateful_widget(list, lb_2, &mut self.list.state);
@@ -1305,8 +1596,7 @@ async fn get_mods(dir: PathBuf) -> Vec<CurrentModsListItem> {
             let version_data = VersionData::from_hash(hash).await;
             if version_data.is_err() {
                 debug!(path = ?path);
-                let cf = CurseForgeAPI::new(API_KEY.to_string());
-                let mod_ = cf.get_mod_from_file(path.clone()).await;
+                let mod_ = CURSEFORGE.get_mod_from_file(path.clone()).await;
                 if mod_.is_err() {
                     debug!(path = ?path);
                     let metadata = Metadata::get_all_metadata(path_str.clone().into());
@@ -1321,7 +1611,7 @@ async fn get_mods(dir: PathBuf) -> Vec<CurrentModsListItem> {
                         return None;
                     }
                     let repo = metadata.get("repo").unwrap();
-                    let repo_name = repo.split('/').last().unwrap();
+                    let repo_name = repo.split('/').next_back().unwrap();
                     let out = CurrentModsListItem {
                         name: repo_name.to_string(),
                         version_type: "GITHUB".to_string(),
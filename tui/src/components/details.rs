@@ -0,0 +1,117 @@
+use modder::{Clients, ModLoader, cli::Source, info::ProjectInfo};
+use ratatui::{prelude::*, widgets::*};
+
+/// A scrollable pane showing the details and latest changelog of a project.
+#[derive(Debug, Clone, Default)]
+pub struct Details {
+    info: ProjectInfo,
+    scroll: u16,
+}
+
+impl Details {
+    /// Fetches the details of `id` from `source`. This blocks, like the searches in the other
+    /// components.
+    pub fn fetch(
+        source: &Source,
+        id: &str,
+        game_version: Option<&str>,
+        loader: Option<ModLoader>,
+    ) -> color_eyre::Result<Self> {
+        let clients = Clients::from_env(None);
+        let info = futures::executor::block_on(ProjectInfo::fetch(
+            source,
+            id,
+            game_version,
+            loader,
+            &clients,
+        ))?;
+        Ok(Self { info, scroll: 0 })
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.scroll = self.scroll.saturating_add(1);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_top(&mut self) {
+        self.scroll = 0;
+    }
+
+    pub fn widget(&self) -> Paragraph<'static> {
+        let info = &self.info;
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let field = |name: &str, value: String, style: Style| {
+            Line::from(vec![
+                Span::styled(format!("\t{}: ", name), style.add_modifier(Modifier::BOLD)),
+                Span::raw(value),
+            ])
+        };
+        let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled(info.title.clone() + "  ", bold),
+                Span::styled(
+                    info.id.clone(),
+                    Style::default().add_modifier(Modifier::DIM),
+                ),
+            ]),
+            Line::raw(info.summary.clone()),
+            Line::default(),
+            field("Source", info.source.to_string(), Style::default()),
+            field("Authors", info.authors.join(", "), Style::default()),
+            field("License", or_dash(&info.license), Style::default()),
+        ];
+        if info.client_side.is_some() || info.server_side.is_some() {
+            lines.push(field(
+                "Client side",
+                or_dash(&info.client_side),
+                Style::default(),
+            ));
+            lines.push(field(
+                "Server side",
+                or_dash(&info.server_side),
+                Style::default(),
+            ));
+        }
+        lines.push(field(
+            "Game versions",
+            info.game_versions.join(", "),
+            Style::default().fg(Color::Green),
+        ));
+        lines.push(field(
+            "Loaders",
+            info.loaders.join(", "),
+            Style::default().fg(Color::Cyan),
+        ));
+        lines.push(field(
+            "Downloads",
+            info.downloads.to_string(),
+            Style::default(),
+        ));
+        for link in &info.links {
+            lines.push(field(
+                &link.text,
+                link.url.clone(),
+                Style::default().fg(Color::Blue),
+            ));
+        }
+        if let Some(changelog) = &info.changelog {
+            lines.push(Line::default());
+            lines.push(Line::from(vec![
+                Span::styled("Changelog  ", bold),
+                Span::styled(
+                    info.version.clone().unwrap_or_default(),
+                    Style::default().add_modifier(Modifier::DIM),
+                ),
+            ]));
+            lines.extend(changelog.lines().map(|line| Line::raw(line.to_string())));
+        }
+        Paragraph::new(lines)
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+    }
+}
@@ -1,12 +1,13 @@
-use super::Component;
+use super::{Component, details::Details};
 use crate::{action::Action, app::Mode, config::Config};
+use clap::ValueEnum;
 use color_eyre::Result;
 use crossterm::event::KeyCode;
 use modder::{
     calc_sha512,
     cli::Source,
-    curseforge_wrapper::{API_KEY, CurseForgeAPI},
-    metadata::Metadata,
+    curseforge_wrapper::CurseForgeAPI,
+    metadata::{DirMetadata, Metadata, Pin},
     modrinth_wrapper::modrinth::{GetProject, VersionData},
 };
 use ratatui::{prelude::*, widgets::*};
@@ -27,6 +28,7 @@ pub struct ListComponent {
     state: State,
     input: Input,
     dir: PathBuf,
+    details: Option<Details>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -48,10 +50,14 @@ struct ModListItem {
     name: String,
     source: Source,
     project_id: String,
+    /// The numeric mod id, for mods from CurseForge.
+    curseforge_id: Option<u32>,
     version: String,
     game_version: Option<String>,
     category: Option<String>,
     version_type: String,
+    pinned: Option<Pin>,
+    install_reason: Option<String>,
 }
 
 const SELECTED_STYLE: Style = Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD);
@@ -110,6 +116,32 @@ impl ListComponent {
             State::Search => State::Normal,
         };
     }
+    fn selected_item(&self) -> Option<&ModListItem> {
+        let items = if self.list.filtered_items.is_empty() {
+            &self.list.list_items
+        } else {
+            &self.list.filtered_items
+        };
+        let selected = self.list.state.selected()?;
+        items.get(selected.min(items.len().checked_sub(1)?))
+    }
+    fn show_details(&mut self) {
+        let Some(item) = self.selected_item() else {
+            return;
+        };
+        let id = match item.curseforge_id {
+            Some(id) => id.to_string(),
+            None => item.project_id.clone(),
+        };
+        let game_version = item
+            .game_version
+            .as_deref()
+            .and_then(|versions| versions.split(", ").next());
+        match Details::fetch(&item.source, &id, game_version, None) {
+            Ok(details) => self.details = Some(details),
+            Err(err) => error!("Failed to get the details of {}: {}", item.name, err),
+        }
+    }
 }
 
 impl Component for ListComponent {
@@ -170,6 +202,17 @@ impl Component for ListComponent {
             }
             return Ok(None);
         }
+        if let Some(details) = self.details.as_mut() {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => details.scroll_down(),
+                KeyCode::Char('k') | KeyCode::Up => details.scroll_up(),
+                KeyCode::Char('g') | KeyCode::Home => details.scroll_top(),
+                KeyCode::Char('q') => return Ok(Some(Action::Quit)),
+                KeyCode::Esc | KeyCode::Char('i') => self.details = None,
+                _ => {}
+            }
+            return Ok(None);
+        }
         match key.code {
             KeyCode::Char('h') | KeyCode::Left => self.list.select_none(),
             KeyCode::Char('j') | KeyCode::Down => self.list.select_next(),
@@ -182,6 +225,7 @@ impl Component for ListComponent {
             }
             KeyCode::Char('q') => return Ok(Some(Action::Quit)),
             KeyCode::Char('/') => self.toggle_state(),
+            KeyCode::Char('i') => self.show_details(),
             _ => {}
         };
         Ok(None)
@@ -309,20 +353,58 @@ impl Component for ListComponent {
                         ),
                         Span::raw(item.version_type.clone()),
                     ]),
+                    Line::from(vec![
+                        Span::styled(
+                            "\tPinned: ",
+                            Style::default()
+                                .fg(Color::Magenta)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(match &item.pinned {
+                            Some(Pin {
+                                version: Some(version),
+                            }) => format!("yes ({})", version),
+                            Some(_) => "yes".to_string(),
+                            None => "no".to_string(),
+                        }),
+                    ]),
+                    Line::from(vec![
+                        Span::styled(
+                            "\tInstalled: ",
+                            Style::default().add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(
+                            item.install_reason
+                                .clone()
+                                .unwrap_or_else(|| "-".to_string()),
+                        ),
+                    ]),
                     // Add more fields as needed
                 ];
-                let para = Paragraph::new(lines).alignment(Alignment::Left);
-                para
+
+                Paragraph::new(lines).alignment(Alignment::Left)
             } else {
                 Paragraph::new(Span::raw("No mod selected")).alignment(Alignment::Left)
             };
-        let right_widget = right_widget.block(
-            Block::new()
-                .borders(Borders::ALL)
-                .padding(Padding::uniform(1))
-                .title_top(Line::raw("info").centered().bold())
-                .border_type(BorderType::Rounded),
-        );
+        let right_widget = match &self.details {
+            Some(details) => details.widget().block(
+                Block::new()
+                    .borders(Borders::ALL)
+                    .padding(Padding::uniform(1))
+                    .title_top(Line::raw("details").centered().bold())
+                    .title_bottom(Line::raw("Press `Esc` to close").right_aligned().bold())
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            ),
+            None => right_widget.block(
+                Block::new()
+                    .borders(Borders::ALL)
+                    .padding(Padding::uniform(1))
+                    .title_top(Line::raw("info").centered().bold())
+                    .title_bottom(Line::raw("Press `i` for details").right_aligned().bold())
+                    .border_type(BorderType::Rounded),
+            ),
+        };
         let style = match self.state {
             State::Normal => Style::default(),
             State::Search => Color::Yellow.into(),
@@ -368,18 +450,32 @@ impl<'a> ModListItem {
         );
         let name = self.name.clone();
         let name_span = Span::styled(name.clone(), Style::default().add_modifier(Modifier::BOLD));
+        if self.pinned.is_some() {
+            let pin_span = Span::styled("PINNED  ", Style::default().fg(Color::Magenta));
+            return Line::from(vec![span, pin_span, id_span, name_span]);
+        }
         Line::from(vec![span, id_span, name_span])
     }
 }
 
 async fn get_mods(dir: PathBuf) -> Vec<ModListItem> {
-    let files = fs::read_dir(dir).unwrap();
+    let files = fs::read_dir(&dir).unwrap();
+    let dir_metadata = DirMetadata::load(&dir).unwrap_or_else(|err| {
+        error!("Failed to read the directory metadata: {}", err);
+        DirMetadata::default()
+    });
 
     let regex = regex::Regex::new(r#"\b\d+\.\d+(?:\.\d+)?(?:-(?:pre|rc)\d+)?\b"#);
     let mut output = Vec::new();
     let mut handles = Vec::new();
     for f in files {
         let regex = regex.clone();
+        let file_name = f
+            .as_ref()
+            .map(|f| f.file_name().to_string_lossy().to_string())
+            .unwrap_or_default();
+        let pinned = dir_metadata.get_pin(&file_name).cloned();
+        let install_reason = dir_metadata.describe_install(&file_name);
         let handle = tokio::spawn(async move {
             if f.is_err() {
                 return None;
@@ -401,7 +497,7 @@ async fn get_mods(dir: PathBuf) -> Vec<ModListItem> {
             let version_data = VersionData::from_hash(hash).await;
             if version_data.is_err() {
                 debug!(path = ?path);
-                let cf = CurseForgeAPI::new(API_KEY.to_string());
+                let cf = CurseForgeAPI::from_env();
                 let mod_ = cf.get_mod_from_file(path.clone()).await;
                 let file = cf.get_version_from_file(path.clone()).await;
                 if mod_.is_err() || file.is_err() {
@@ -418,16 +514,19 @@ async fn get_mods(dir: PathBuf) -> Vec<ModListItem> {
                         return None;
                     }
                     let repo = metadata.get("repo").unwrap();
-                    let repo_name = repo.split('/').last().unwrap();
+                    let repo_name = repo.split('/').next_back().unwrap();
                     let game_version = regex.unwrap().find(&path_str).unwrap().as_str().to_string();
                     let out = ModListItem {
                         name: repo_name.to_string(),
-                        source: Source::Github,
+                        source: Source::from_str(source, true).unwrap_or(Source::Github),
                         version: game_version,
                         game_version: None,
                         category: None,
                         version_type: "GITHUB".to_string(),
                         project_id: repo.to_string(),
+                        curseforge_id: None,
+                        pinned,
+                        install_reason,
                     };
                     return Some(out);
                 }
@@ -446,6 +545,9 @@ async fn get_mods(dir: PathBuf) -> Vec<ModListItem> {
                     category: Some(mod_.categories.iter().map(|c| c.name.clone()).collect()),
                     version_type: "CF".to_string(),
                     project_id: mod_.slug,
+                    curseforge_id: Some(mod_.id),
+                    pinned,
+                    install_reason,
                 };
                 return Some(out);
             }
@@ -465,6 +567,9 @@ async fn get_mods(dir: PathBuf) -> Vec<ModListItem> {
                 category: Some(project.get_categories().join(", ")),
                 version_type: version_data.get_version_type(),
                 project_id: version_data.project_id,
+                curseforge_id: None,
+                pinned,
+                install_reason,
             };
 
             Some(out)
@@ -1,11 +1,12 @@
 use super::Component;
 use crate::{action::Action, app::Mode, config::Config};
+use clap::ValueEnum;
 use color_eyre::Result;
 use crossterm::event::KeyCode;
 use modder::{
     calc_sha512,
     cli::Source,
-    curseforge_wrapper::{API_KEY, CurseForgeAPI},
+    curseforge_wrapper::CurseForgeAPI,
     metadata::Metadata,
     modrinth_wrapper::modrinth::{GetProject, VersionData},
 };
@@ -192,7 +193,7 @@ impl Component for ToggleComponent {
             KeyCode::Enter => {
                 self.state = State::Toggling;
                 for item in self.list.list_items.iter() {
-                    let filename = item.path.split('/').last().unwrap();
+                    let filename = item.path.split('/').next_back().unwrap();
                     let predicate = filename.contains("disabled");
                     if predicate && item.enabled {
                         let new_path = item.path.replace(".disabled", "");
@@ -348,8 +349,8 @@ impl Component for ToggleComponent {
                     ]),
                     // Add more fields as needed
                 ];
-                let para = Paragraph::new(lines).alignment(Alignment::Left);
-                para
+
+                Paragraph::new(lines).alignment(Alignment::Left)
             } else {
                 Paragraph::new(Span::raw("No mod selected")).alignment(Alignment::Left)
             };
@@ -464,7 +465,7 @@ async fn get_mods(dir: PathBuf) -> Vec<ToggleListItem> {
             let version_data = VersionData::from_hash(hash).await;
             if version_data.is_err() {
                 debug!(path = ?path);
-                let cf = CurseForgeAPI::new(API_KEY.to_string());
+                let cf = CurseForgeAPI::from_env();
                 let mod_ = cf.get_mod_from_file(path.clone()).await;
                 let file = cf.get_version_from_file(path.clone()).await;
                 if mod_.is_err() || file.is_err() {
@@ -481,11 +482,11 @@ async fn get_mods(dir: PathBuf) -> Vec<ToggleListItem> {
                         return None;
                     }
                     let repo = metadata.get("repo").unwrap();
-                    let repo_name = repo.split('/').last().unwrap();
+                    let repo_name = repo.split('/').next_back().unwrap();
                     let game_version = regex.unwrap().find(&path_str).unwrap().as_str().to_string();
                     let out = ToggleListItem {
                         name: repo_name.to_string(),
-                        source: Source::Github,
+                        source: Source::from_str(source, true).unwrap_or(Source::Github),
                         version: game_version,
                         game_version: None,
                         category: None,